@@ -0,0 +1,11 @@
+use std::{env, error::Error};
+
+// Hosts games for miktactoe clients. Pass an address to listen on, or it
+// listens on localhost port 7878.
+fn main() -> Result<(), Box<dyn Error>> {
+    let address = env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("127.0.0.1:7878"));
+    miktactoe::run_server(&address)?;
+    Ok(())
+}
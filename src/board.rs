@@ -1,48 +1,159 @@
 use crate::common::*;
 use rand::seq::SliceRandom;
-use std::collections::{HashMap, HashSet};
+use rand::Rng;
+use std::collections::{BTreeSet, HashMap};
 
+#[derive(Clone)]
 pub struct Board {
-    cells: [[CellState; 3]; 3],
-    marker_count: u8,
+    // A board can be a stack of layers, like the 4x4x4 cube in Qubic. The
+    // layers sit side by side, left to right, so as far as cell coordinates
+    // go a board with layers is just wider. `width` counts the cells across
+    // all of the layers.
+    width: usize,
+    height: usize,
+    layers: usize,
+    // The number of markers a player needs in a row to win.
+    win_length: usize,
+    // With gravity, a marker falls to the lowest empty cell of its column,
+    // like in Connect Four. Only that cell is a valid move.
+    gravity: bool,
+    cells: Vec<CellState>,
+    marker_count: usize,
     metadata: BoardMetadata,
 }
 
 #[derive(Debug, PartialEq)]
 pub enum BoardState {
-    Win,
+    // Holds the set the last move completed, so the UI can show it.
+    Win(SetType),
     Tie,
     Playing,
 }
 
 type SetIndex = usize;
 
-#[derive(Debug, Copy, Clone)]
+// A set is a straight run of `win_length` cells. Filling a set with your own
+// markers wins the game. On a 3x3 board with three in a row, every set is a
+// full row, column or diagonal. On larger boards, a row can hold several
+// overlapping sets. Each variant holds the (row, column) of the set's first cell.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum SetType {
-    Row(SetIndex),
-    Column(SetIndex),
-    Diag1,
-    Diag2,
+    // Runs left to right.
+    Row(SetIndex, SetIndex),
+    // Runs top to bottom.
+    Column(SetIndex, SetIndex),
+    // Runs top left to bottom right.
+    Diag1(SetIndex, SetIndex),
+    // Runs top right to bottom left.
+    Diag2(SetIndex, SetIndex),
+    // Runs through the layers of a cube, one cell in each, starting in the
+    // first layer. The two steps are how far the row and the column within
+    // the layer move from one layer to the next (-1, 0 or 1). (0, 0) goes
+    // straight through, and the four with neither step 0 are the space
+    // diagonals, from one corner of the cube to the opposite one.
+    Across(SetIndex, SetIndex, isize, isize),
 }
 
 impl Board {
     pub fn new() -> Board {
-        // The outer bracket represents the row index. The inner bracket represents the column
-        // index.
-        // ['_'; 3] forms a 1D array: _ _ _ (0 inner index, 1 inner index, 2 inner index)
-        //
-        // [['_'; 3]; 3] forms a 2D array: [_ _ _] (0 outer index)
-        //                                 [_ _ _] (1 outer index)
-        //                                 [_ _ _] (2 outer index)
-        // When indexing, the leftest bracket will index the outer array, meaning it will index the
-        // row. The rightest bracket will index the inner array, meaning it will index the column.
+        Board::with_size(3, 3, 3)
+    }
+
+    pub fn with_size(width: usize, height: usize, win_length: usize) -> Board {
+        Board::with_layers(width, height, 1, win_length)
+    }
+
+    // A size x size x size cube, where a set runs all the way across it.
+    pub fn cube(size: usize) -> Board {
+        Board::with_layers(size, size, size, size)
+    }
+
+    // A board where markers drop to the bottom of their column, like the
+    // 7x6 board with four in a row that Connect Four is played on.
+    pub fn with_gravity(width: usize, height: usize, win_length: usize) -> Board {
         Board {
-            cells: [[CellState::Empty; 3]; 3],
+            gravity: true,
+            ..Board::with_size(width, height, win_length)
+        }
+    }
+
+    // Stacks `layers` boards of `width` x `height` cells. Sets can run within
+    // a layer, or through all of them.
+    pub fn with_layers(width: usize, height: usize, layers: usize, win_length: usize) -> Board {
+        assert!(
+            width > 0 && height > 0 && layers > 0,
+            "Board must have at least one cell"
+        );
+        assert!(
+            win_length > 0 && (win_length <= width || win_length <= height),
+            "A {}x{} board can't fit {} in a row",
+            width,
+            height,
+            win_length
+        );
+
+        // The cells are stored in a flat vector, one row after another. So for
+        // a 3x3 board, the indexes look like this:
+        //     [0 1 2]
+        //     [3 4 5]
+        //     [6 7 8]
+        // The cell at (row, column) lives at index `row * width + column`.
+        // With layers, a row carries on into the same row of the next layer.
+        let width = width * layers;
+        Board {
+            width,
+            height,
+            layers,
+            win_length,
+            gravity: false,
+            cells: vec![CellState::Empty; width * height],
             marker_count: 0,
-            metadata: BoardMetadata::new(),
+            metadata: BoardMetadata::new(width, height, layers),
         }
     }
 
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_layers(&self) -> usize {
+        self.layers
+    }
+
+    // The width of one layer. Boards without layers are one layer wide.
+    pub fn get_layer_width(&self) -> usize {
+        self.width / self.layers
+    }
+
+    pub fn get_size(&self) -> BoardSize {
+        BoardSize::new(self.get_layer_width(), self.height, self.layers)
+    }
+
+    pub fn get_win_length(&self) -> usize {
+        self.win_length
+    }
+
+    pub fn has_gravity(&self) -> bool {
+        self.gravity
+    }
+
+    // The cell a marker dropped into `column` lands in, which is the lowest
+    // empty one. None if the column is full.
+    pub fn get_drop_cell(&self, column: usize) -> Option<CellCoord> {
+        (0..self.height)
+            .rev()
+            .map(|row| CellCoord::new(row, column))
+            .find(|&cell_coord| self.get_cell(cell_coord) == CellState::Empty)
+    }
+
+    fn get_cell(&self, cell_coord: CellCoord) -> CellState {
+        self.cells[cell_coord.row * self.width + cell_coord.column]
+    }
+
     fn get_cell_char(&self, cell_state: &CellState) -> char {
         match cell_state {
             CellState::X => 'X',
@@ -52,97 +163,183 @@ impl Board {
     }
 
     pub fn get_cellstate_char(&self, index: usize) -> char {
-        let row = index / 3;
-        let col = index % 3;
-        self.get_cell_char(&self.cells[row][col])
+        self.get_cell_char(&self.cells[index])
+    }
+
+    pub fn get_cell_type(&self, cell_coord: CellCoord) -> CellType {
+        get_cell_type(cell_coord, self.width, self.height, self.layers)
     }
 
     pub fn place_marker(&mut self, cell_coord: CellCoord, marker: Marker) {
+        let index = cell_coord.row * self.width + cell_coord.column;
         match marker {
-            Marker::X => self.cells[cell_coord.row][cell_coord.column] = CellState::X,
-            Marker::O => self.cells[cell_coord.row][cell_coord.column] = CellState::O,
+            Marker::X => self.cells[index] = CellState::X,
+            Marker::O => self.cells[index] = CellState::O,
         }
         self.marker_count += 1;
         self.update_board_metadata(cell_coord);
     }
 
+    // The inverse of place_marker(). Clears the cell and puts the board
+    // metadata back the way it was before the marker was placed.
+    pub fn remove_marker(&mut self, cell_coord: CellCoord) {
+        let index = cell_coord.row * self.width + cell_coord.column;
+        if self.cells[index] == CellState::Empty {
+            return;
+        }
+        self.cells[index] = CellState::Empty;
+        self.marker_count -= 1;
+
+        match self.get_cell_type(cell_coord) {
+            CellType::Corner => self.metadata.add_corner_move(cell_coord),
+            CellType::Edge => self.metadata.add_edge_move(cell_coord),
+            _ => (),
+        }
+        self.scan_sets();
+    }
+
+    pub fn get_valid_moves(&self) -> Vec<CellCoord> {
+        itertools::iproduct!(0..self.height, 0..self.width)
+            .map(|(row, column)| CellCoord::new(row, column))
+            .filter(|&cell_coord| self.validate_move(cell_coord) == Move::Valid)
+            .collect()
+    }
+
+    // In wild tic-tac-toe, either marker can go in any valid cell.
+    pub fn get_wild_moves(&self) -> Vec<WildMove> {
+        self.get_valid_moves()
+            .into_iter()
+            .flat_map(|cell| {
+                [Marker::X, Marker::O]
+                    .iter()
+                    .map(move |&marker| WildMove::new(cell, marker))
+            })
+            .collect()
+    }
+
     pub fn validate_move(&self, cell_coord: CellCoord) -> Move {
         // We only check the upper bound, because column and row are usize,
         // which is always >= 0.
-        if cell_coord.column <= 2 && cell_coord.row <= 2 {
-            if let CellState::Empty = self.cells[cell_coord.row][cell_coord.column] {
-                Move::Valid
-            } else {
-                Move::AlreadyUsed
+        if cell_coord.column < self.width && cell_coord.row < self.height {
+            match self.get_cell(cell_coord) {
+                CellState::Empty
+                    if self.gravity
+                        && self.get_drop_cell(cell_coord.column) != Some(cell_coord) =>
+                {
+                    Move::Floating
+                }
+                CellState::Empty => Move::Valid,
+                _ => Move::AlreadyUsed,
             }
         } else {
             Move::OutOfBounds
         }
     }
 
+    // Enumerates every set on the board. A set can only start where there's
+    // room for `win_length` cells in its direction. The rows, columns and
+    // diagonals of each layer come first, then the sets through the layers.
+    pub fn get_sets(&self) -> Vec<SetType> {
+        let length = self.win_length;
+        let width = self.get_layer_width();
+        let mut sets = Vec::new();
+        for layer_start in (0..self.width).step_by(width) {
+            if length <= width {
+                for (row, column) in itertools::iproduct!(0..self.height, 0..=width - length) {
+                    sets.push(SetType::Row(row, layer_start + column));
+                }
+            }
+            if length <= self.height {
+                for (row, column) in itertools::iproduct!(0..=self.height - length, 0..width) {
+                    sets.push(SetType::Column(row, layer_start + column));
+                }
+            }
+            if length <= width && length <= self.height {
+                for (row, column) in
+                    itertools::iproduct!(0..=self.height - length, 0..=width - length)
+                {
+                    sets.push(SetType::Diag1(row, layer_start + column));
+                }
+                for (row, column) in
+                    itertools::iproduct!(0..=self.height - length, length - 1..width)
+                {
+                    sets.push(SetType::Diag2(row, layer_start + column));
+                }
+            }
+        }
+
+        // A set through the layers has to fit in the cube in both of its
+        // other directions too.
+        let fits = |start: usize, step: isize, size: usize| match step {
+            -1 => start + 1 >= length,
+            1 => start + length <= size,
+            _ => true,
+        };
+        if self.layers > 1 && length == self.layers {
+            for (row, column, row_step, column_step) in
+                itertools::iproduct!(0..self.height, 0..width, -1..=1, -1..=1)
+            {
+                if fits(row, row_step, self.height) && fits(column, column_step, width) {
+                    sets.push(SetType::Across(row, column, row_step, column_step));
+                }
+            }
+        }
+        sets
+    }
+
+    pub fn get_set_coords(&self, set_type: SetType) -> Vec<CellCoord> {
+        // Moving to the next layer moves a whole layer's width to the right.
+        let layer_step = self.get_layer_width() as isize;
+        let (row, column, row_step, column_step) = match set_type {
+            SetType::Row(row, column) => (row, column, 0, 1),
+            SetType::Column(row, column) => (row, column, 1, 0),
+            SetType::Diag1(row, column) => (row, column, 1, 1),
+            SetType::Diag2(row, column) => (row, column, 1, -1),
+            SetType::Across(row, column, row_step, column_step) => {
+                (row, column, row_step, layer_step + column_step)
+            }
+        };
+        (0..self.win_length as isize)
+            .map(|step| {
+                CellCoord::new(
+                    (row as isize + step * row_step) as usize,
+                    (column as isize + step * column_step) as usize,
+                )
+            })
+            .collect()
+    }
+
     // check_board_state() takes the last move to better check the win condition.
     // Since we check for a win after every move, we only have to check
-    // the row, column, and diagnals that correspond to the most recently
-    // marked cell.
+    // the sets that contain the most recently marked cell.
     pub fn check_board_state(&self, last_move: CellCoord, marker: Marker) -> BoardState {
-        // Checking a row. So we keep the row (y coordinate) consistent.
-        // We use an iterator here to iterate each column in the row.
-        // We provide a lambda to `all()` to check whether each column
+        // We provide a lambda to `all()` to check whether each cell in a set
         // matches the last placed marker. This is done by matching on
         // `marker`, and using a match guard to specify an additional
         // condition in a match arm. This means the pattern *and* the
-        // match guard must match for the arm to be chosen. We make a lambda
-        // for the marker checking function, so we can reuse it for
-        // column and diagonal checks.
-        let marker_check = |&cell_marker| match marker {
+        // match guard must match for the arm to be chosen.
+        let marker_check = |cell_marker: CellState| match marker {
             Marker::X if cell_marker == CellState::X => true,
             Marker::O if cell_marker == CellState::O => true,
             _ => false,
         };
-        let winning_row = self.cells[last_move.row].iter().all(marker_check);
-        if winning_row {
-            return BoardState::Win;
-        }
-
-        // Checking a column. Similar strategy to rows above. However, here
-        // we must iterate through each row. While doing that with `iter()`,
-        // we use `map()` to transform the iteration from a row to a column
-        // index in that row, which gives us the character marker at that
-        // coordinate. We use `all()` just like we did above to see if all
-        // the characters in the column match the placed marker.
-        let winning_column = self
-            .cells
-            .iter()
-            .map(|row| &row[last_move.column])
-            .all(marker_check);
-        if winning_column {
-            return BoardState::Win;
-        }
-
-        // Checking top left to bottom right diagonal. Here, we zip together
-        // some sequence iterators to form the diagonal coordinates for us to
-        // check. We use `map()` to transform the iteration into the cell's
-        // value, and use the same `all()` function and lambda as above.
-        let winning_diag = (0..3)
-            .zip(0..3)
-            .map(|(row, column)| &self.cells[row][column])
-            .all(marker_check);
-        if winning_diag {
-            return BoardState::Win;
-        }
-
-        // Checking top right to bottom left diagonal.
-        let winning_diag = (0..3)
-            .rev()
-            .zip(0..3)
-            .map(|(row, column)| &self.cells[row][column])
-            .all(marker_check);
-        if winning_diag {
-            return BoardState::Win;
+
+        // A move can complete more than one set at once. Any of them will
+        // do, so we stop at the first.
+        let winning_set = self.get_sets().into_iter().find(|&set_type| {
+            let coords = self.get_set_coords(set_type);
+            coords.contains(&last_move)
+                && coords
+                    .into_iter()
+                    .map(|coord| self.get_cell(coord))
+                    .all(marker_check)
+        });
+        if let Some(set_type) = winning_set {
+            return BoardState::Win(set_type);
         }
 
         // No winners this move. Let's check if it's a tie.
-        if self.marker_count == 9 {
+        if self.marker_count == self.cells.len() {
             return BoardState::Tie;
         }
 
@@ -150,194 +347,173 @@ impl Board {
     }
 
     fn update_board_metadata(&mut self, last_move: CellCoord) {
-        match last_move.get_cell_type() {
+        match self.get_cell_type(last_move) {
             CellType::Corner => self.metadata.remove_corner_move(last_move),
             CellType::Edge => self.metadata.remove_edge_move(last_move),
             _ => (),
         }
+        self.scan_sets();
+    }
 
+    fn scan_sets(&mut self) {
         self.metadata.reset();
 
-        let get_row =
-            |row_index: usize, cells: &[[CellState; 3]; 3]| -> Vec<(CellState, CellCoord)> {
-                cells[row_index]
-                    .iter()
-                    .enumerate()
-                    .map(|(column_index, &cell_state)| {
-                        (cell_state, CellCoord::new(row_index, column_index))
-                    })
-                    .collect()
-            };
-
-        for row_index in 0..3 {
-            let row = get_row(row_index, &self.cells);
-            self.scan_set(row, SetType::Row(row_index));
-        }
-
-        let get_col =
-            |col_index: usize, cells: &[[CellState; 3]; 3]| -> Vec<(CellState, CellCoord)> {
-                cells
-                    .iter()
-                    .enumerate()
-                    .map(|(row_index, row)| (row[col_index], CellCoord::new(row_index, col_index)))
-                    .collect()
-            };
-
-        for col_index in 0..3 {
-            let col = get_col(col_index, &self.cells);
-            self.scan_set(col, SetType::Column(col_index));
+        for set_type in self.get_sets() {
+            self.scan_set(set_type);
         }
-
-        let get_diag1 = |cells: &[[CellState; 3]; 3]| -> Vec<(CellState, CellCoord)> {
-            vec![
-                (cells[0][0], CellCoord::new(0, 0)),
-                (cells[1][1], CellCoord::new(1, 1)),
-                (cells[2][2], CellCoord::new(2, 2)),
-            ]
-        };
-        let diag1 = get_diag1(&self.cells);
-        self.scan_set(diag1, SetType::Diag1);
-
-        let get_diag2 = |cells: &[[CellState; 3]; 3]| -> Vec<(CellState, CellCoord)> {
-            vec![
-                (cells[0][2], CellCoord::new(0, 2)),
-                (cells[1][1], CellCoord::new(1, 1)),
-                (cells[2][0], CellCoord::new(2, 0)),
-            ]
-        };
-        let diag2 = get_diag2(&self.cells);
-        self.scan_set(diag2, SetType::Diag2);
     }
 
-    fn scan_set(&mut self, set: Vec<(CellState, CellCoord)>, set_type: SetType) {
-        let (cell_states, cell_coords): (Vec<_>, Vec<_>) = set.into_iter().unzip();
-
-        let mut x_count: u8 = 0;
-        let mut x_coord: CellCoord = CellCoord::new(0, 0);
-        let mut o_count: u8 = 0;
-        let mut o_coord: CellCoord = CellCoord::new(0, 0);
-        let mut empty: u8 = 0;
-        let mut empty_coord: CellCoord = CellCoord::new(0, 0);
+    fn scan_set(&mut self, set_type: SetType) {
+        let mut x_count = 0;
+        let mut o_count = 0;
+        let mut empties = Vec::new();
 
-        for (index, cell) in cell_states.iter().enumerate() {
-            match cell {
-                CellState::X => {
-                    x_coord = cell_coords[index];
-                    x_count += 1;
-                }
-                CellState::O => {
-                    o_coord = cell_coords[index];
-                    o_count += 1;
-                }
-                CellState::Empty => {
-                    empty += 1;
-                    empty_coord = cell_coords[index];
-                }
+        for cell_coord in self.get_set_coords(set_type) {
+            match self.get_cell(cell_coord) {
+                CellState::X => x_count += 1,
+                CellState::O => o_count += 1,
+                CellState::Empty => empties.push(cell_coord),
             }
         }
 
-        match (x_count, o_count, empty) {
-            (2, 0, 1) => {
-                // winning move for X
-                self.metadata.add_winning_coord(empty_coord, Marker::X);
-            }
-            (0, 2, 1) => {
-                // winning move for O
-                self.metadata.add_winning_coord(empty_coord, Marker::O);
-            }
-            (1, 0, 2) => {
-                // This set is a fork potential for X.
-                match set_type {
-                    SetType::Row(index) => {
-                        self.metadata
-                            .add_potential_fork(x_coord, SetType::Row(index), Marker::X)
-                    }
-                    SetType::Column(index) => {
-                        self.metadata
-                            .add_potential_fork(x_coord, SetType::Column(index), Marker::X)
-                    }
-                    SetType::Diag1 => {
-                        self.metadata
-                            .add_potential_fork(x_coord, SetType::Diag1, Marker::X)
-                    }
-                    SetType::Diag2 => {
-                        self.metadata
-                            .add_potential_fork(x_coord, SetType::Diag2, Marker::X)
-                    }
-                }
+        // A set only matters to a player if their opponent hasn't blocked it.
+        // One marker short of full is a winning move. Two markers short means
+        // the set has fork potential.
+        for (marker, count, opponent_count) in
+            [(Marker::X, x_count, o_count), (Marker::O, o_count, x_count)]
+        {
+            if opponent_count > 0 {
+                continue;
             }
-            (0, 1, 2) => {
-                // This set is a fork potential for O.
-                match set_type {
-                    SetType::Row(index) => {
-                        self.metadata
-                            .add_potential_fork(o_coord, SetType::Row(index), Marker::O)
-                    }
-                    SetType::Column(index) => {
-                        self.metadata
-                            .add_potential_fork(o_coord, SetType::Column(index), Marker::O)
-                    }
-                    SetType::Diag1 => {
-                        self.metadata
-                            .add_potential_fork(o_coord, SetType::Diag1, Marker::O)
-                    }
-                    SetType::Diag2 => {
-                        self.metadata
-                            .add_potential_fork(o_coord, SetType::Diag2, Marker::O)
-                    }
-                }
+            if count + 1 == self.win_length {
+                self.metadata.add_winning_coord(empties[0], marker);
+            } else if count + 2 == self.win_length {
+                self.metadata
+                    .add_potential_fork(set_type, empties.clone(), marker);
             }
-            _ => (),
         }
     }
 
+    // With gravity, a set can be one marker short with the missing cell
+    // still floating, so only winning cells that can be played count.
     pub fn get_winning_move(&self, marker: Marker) -> Option<CellCoord> {
-        if let Some(mut vec) = self.metadata.get_winning_coords(marker) {
-            vec.pop()
-        } else {
-            None
+        self.metadata
+            .get_winning_coords(marker)?
+            .into_iter()
+            .rev()
+            .find(|&cell_coord| self.validate_move(cell_coord) == Move::Valid)
+    }
+
+    // In misère, completing a set loses, so these are the moves `marker` can
+    // make without losing on the spot.
+    pub fn get_safe_moves(&self, marker: Marker) -> Vec<CellCoord> {
+        let losing_moves = self.metadata.get_winning_coords(marker).unwrap_or_default();
+        self.get_valid_moves()
+            .into_iter()
+            .filter(|cell_coord| !losing_moves.contains(cell_coord))
+            .collect()
+    }
+
+    // Counts the sets through `cell_coord` that `marker` could still fill,
+    // because the other player has no markers in them.
+    pub fn count_open_sets(&self, cell_coord: CellCoord, marker: Marker) -> usize {
+        let opponent = Marker::opposite(marker);
+        self.get_sets()
+            .into_iter()
+            .map(|set_type| self.get_set_coords(set_type))
+            .filter(|coords| coords.contains(&cell_coord))
+            .filter(|coords| {
+                coords
+                    .iter()
+                    .all(|&coord| self.get_marker_at(coord) != Some(opponent))
+            })
+            .count()
+    }
+
+    pub fn get_marker_at(&self, cell_coord: CellCoord) -> Option<Marker> {
+        match self.get_cell(cell_coord) {
+            CellState::X => Some(Marker::X),
+            CellState::O => Some(Marker::O),
+            CellState::Empty => None,
         }
     }
 
+    // The cell on the opposite side of the middle of the board. Turning the
+    // board half way around puts every cell on its mirror cell, and every
+    // set on another set.
+    pub fn get_mirror_cell(&self, cell_coord: CellCoord) -> CellCoord {
+        CellCoord::new(
+            self.height - 1 - cell_coord.row,
+            self.width - 1 - cell_coord.column,
+        )
+    }
+
+    // Like winning moves, only forks that can be played right away count.
+    // They come top to bottom, left to right.
     pub fn get_forking_move(&self, marker: Marker) -> Vec<CellCoord> {
-        self.metadata.get_fork_coords(marker).into_iter().collect()
+        self.metadata
+            .get_fork_coords(marker)
+            .into_iter()
+            .filter(|&cell_coord| self.validate_move(cell_coord) == Move::Valid)
+            .collect()
     }
 
-    pub fn get_single_marker_sets(&self, marker: Marker) -> Vec<(CellCoord, SetType)> {
+    // Returns the sets that are two markers short for `marker`, along with
+    // the two empty cells in each set.
+    pub fn get_potential_fork_sets(&self, marker: Marker) -> Vec<(SetType, Vec<CellCoord>)> {
         self.metadata.get_potential_forks(marker)
     }
 
-    pub fn get_corner_move(&self) -> Option<CellCoord> {
-        self.metadata.get_corner_coords()
+    // Picks one of the empty corners at random. The caller supplies the RNG,
+    // so a seeded player makes the same picks every time.
+    pub fn get_corner_move<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<CellCoord> {
+        self.metadata.get_corner_coords(rng)
+    }
+
+    pub fn get_edge_move<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<CellCoord> {
+        self.metadata.get_edge_coords(rng)
     }
+}
 
-    pub fn get_edge_move(&self) -> Option<CellCoord> {
-        self.metadata.get_edge_coords()
+// Corners are the four corners of the board. Edges are the rest of the cells
+// along the border. Everything inside the border counts as the center. On a
+// board with layers, that's within the cell's own layer.
+fn get_cell_type(cell_coord: CellCoord, width: usize, height: usize, layers: usize) -> CellType {
+    let width = width / layers;
+    let column = cell_coord.column % width;
+    let on_top_or_bottom = cell_coord.row == 0 || cell_coord.row == height - 1;
+    let on_left_or_right = column == 0 || column == width - 1;
+    match (on_top_or_bottom, on_left_or_right) {
+        (true, true) => CellType::Corner,
+        (true, false) | (false, true) => CellType::Edge,
+        (false, false) => CellType::Center,
     }
 }
 
+#[derive(Clone)]
 struct BoardMetadata {
     winning_moves: HashMap<Marker, Vec<CellCoord>>,
     corner_moves: Vec<CellCoord>,
     edge_moves: Vec<CellCoord>,
-    x_potential_forks: Vec<(CellCoord, SetType)>,
-    o_potential_forks: Vec<(CellCoord, SetType)>,
+    x_potential_forks: Vec<(SetType, Vec<CellCoord>)>,
+    o_potential_forks: Vec<(SetType, Vec<CellCoord>)>,
 }
 
 impl BoardMetadata {
-    fn new() -> BoardMetadata {
-        let corner_moves = vec![
-            CellCoord::new(0, 0),
-            CellCoord::new(0, 2),
-            CellCoord::new(2, 2),
-            CellCoord::new(2, 0),
-        ];
-        let edge_moves = vec![
-            CellCoord::new(0, 1),
-            CellCoord::new(1, 2),
-            CellCoord::new(2, 1),
-            CellCoord::new(1, 0),
-        ];
+    fn new(width: usize, height: usize, layers: usize) -> BoardMetadata {
+        let cell_coords: Vec<CellCoord> = itertools::iproduct!(0..height, 0..width)
+            .map(|(row, column)| CellCoord::new(row, column))
+            .collect();
+        let corner_moves = cell_coords
+            .iter()
+            .copied()
+            .filter(|&coord| get_cell_type(coord, width, height, layers) == CellType::Corner)
+            .collect();
+        let edge_moves = cell_coords
+            .iter()
+            .copied()
+            .filter(|&coord| get_cell_type(coord, width, height, layers) == CellType::Edge)
+            .collect();
         BoardMetadata {
             winning_moves: HashMap::new(),
             corner_moves,
@@ -349,89 +525,47 @@ impl BoardMetadata {
 
     fn add_winning_coord(&mut self, winning_coord: CellCoord, marker: Marker) {
         // This may be the first time we access the winning_moves HashMap, so
-        // we use `entry().or_insert()`. This allows us to insert a new vector
+        // we use `entry().or_default()`. This allows us to insert a new vector
         // if there isn't one currently at the `marker` key.
-        let coords = self.winning_moves.entry(marker).or_insert(Vec::new());
+        let coords = self.winning_moves.entry(marker).or_default();
         coords.push(winning_coord);
     }
 
     fn get_winning_coords(&self, marker: Marker) -> Option<Vec<CellCoord>> {
-        if let Some(winning_moves) = self.winning_moves.get(&marker) {
-            Some(winning_moves.clone())
-        } else {
-            None
-        }
+        self.winning_moves.get(&marker).cloned()
     }
 
-    fn add_potential_fork(&mut self, coord: CellCoord, set_type: SetType, marker: Marker) {
+    fn add_potential_fork(&mut self, set_type: SetType, empties: Vec<CellCoord>, marker: Marker) {
         match marker {
-            Marker::X => self.x_potential_forks.push((coord, set_type)),
-            Marker::O => self.o_potential_forks.push((coord, set_type)),
+            Marker::X => self.x_potential_forks.push((set_type, empties)),
+            Marker::O => self.o_potential_forks.push((set_type, empties)),
         }
     }
 
-    fn get_fork_coords(&self, marker: Marker) -> HashSet<CellCoord> {
-        // Fork coordinates are the intersection of two sets (row, col, diag) that have
-        // fork potential.
-        let get_intersection = |set1: SetType, set2: SetType| -> Option<CellCoord> {
-            match (set1, set2) {
-                // Hmm. We have to account for any order of the sets. This is awkward.
-                // I should think of a better way.
-                (SetType::Row(row_index), SetType::Column(col_index)) => {
-                    Some(CellCoord::new(row_index, col_index))
-                }
-                (SetType::Column(col_index), SetType::Row(row_index)) => {
-                    Some(CellCoord::new(row_index, col_index))
-                }
-                (SetType::Row(row_index), SetType::Diag1) => {
-                    Some(CellCoord::new(row_index, row_index))
-                }
-                (SetType::Diag1, SetType::Row(row_index)) => {
-                    Some(CellCoord::new(row_index, row_index))
-                }
-                (SetType::Row(row_index), SetType::Diag2) => {
-                    Some(CellCoord::new(row_index, 2 - row_index))
-                }
-                (SetType::Diag2, SetType::Row(row_index)) => {
-                    Some(CellCoord::new(row_index, 2 - row_index))
-                }
-                (SetType::Column(col_index), SetType::Diag1) => {
-                    Some(CellCoord::new(col_index, col_index))
-                }
-                (SetType::Diag1, SetType::Column(col_index)) => {
-                    Some(CellCoord::new(col_index, col_index))
-                }
-                (SetType::Column(col_index), SetType::Diag2) => {
-                    Some(CellCoord::new(2 - col_index, col_index))
-                }
-                (SetType::Diag2, SetType::Column(col_index)) => {
-                    Some(CellCoord::new(2 - col_index, col_index))
-                }
-                _ => None,
-            }
+    fn get_fork_coords(&self, marker: Marker) -> BTreeSet<CellCoord> {
+        // Fork coordinates are the empty cells shared by two sets that have fork
+        // potential. Placing a marker there leaves each set one marker short, so
+        // the opponent can't block both. That only holds if the two sets are
+        // left waiting on different cells, though. Overlapping sets in the same
+        // line on a large board can share both of their empties.
+        let potential_forks = match marker {
+            Marker::X => &self.x_potential_forks,
+            Marker::O => &self.o_potential_forks,
         };
 
-        let mut result = HashSet::new();
-        match marker {
-            Marker::X => {
-                for entry1 in &self.x_potential_forks {
-                    for entry2 in &self.x_potential_forks {
-                        if entry1.0 != entry2.0 {
-                            if let Some(coord) = get_intersection(entry1.1, entry2.1) {
-                                result.insert(coord);
-                            }
-                        }
-                    }
+        // A BTreeSet keeps the cells in order, so players that take the first
+        // fork play the same one every time.
+        let mut result = BTreeSet::new();
+        for (set1, empties1) in potential_forks {
+            for (set2, empties2) in potential_forks {
+                if set1 == set2 {
+                    continue;
                 }
-            }
-            Marker::O => {
-                for entry1 in &self.o_potential_forks {
-                    for entry2 in &self.o_potential_forks {
-                        if entry1.0 != entry2.0 {
-                            if let Some(coord) = get_intersection(entry1.1, entry2.1) {
-                                result.insert(coord);
-                            }
-                        }
+                for &coord in empties1.iter().filter(|coord| empties2.contains(coord)) {
+                    let remaining1 = empties1.iter().find(|&&empty| empty != coord);
+                    let remaining2 = empties2.iter().find(|&&empty| empty != coord);
+                    if remaining1 != remaining2 {
+                        result.insert(coord);
                     }
                 }
             }
@@ -439,29 +573,41 @@ impl BoardMetadata {
         result
     }
 
-    fn get_potential_forks(&self, marker: Marker) -> Vec<(CellCoord, SetType)> {
+    fn get_potential_forks(&self, marker: Marker) -> Vec<(SetType, Vec<CellCoord>)> {
         match marker {
             Marker::X => self.x_potential_forks.clone(),
             Marker::O => self.o_potential_forks.clone(),
         }
     }
 
-    fn get_corner_coords(&self) -> Option<CellCoord> {
-        self.corner_moves.choose(&mut rand::thread_rng()).copied()
+    fn get_corner_coords<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<CellCoord> {
+        self.corner_moves.choose(rng).copied()
     }
 
     fn remove_corner_move(&mut self, coord: CellCoord) {
         self.corner_moves.retain(|&cell_coord| cell_coord != coord);
     }
 
-    fn get_edge_coords(&self) -> Option<CellCoord> {
-        self.edge_moves.choose(&mut rand::thread_rng()).copied()
+    fn add_corner_move(&mut self, coord: CellCoord) {
+        if !self.corner_moves.contains(&coord) {
+            self.corner_moves.push(coord);
+        }
+    }
+
+    fn get_edge_coords<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<CellCoord> {
+        self.edge_moves.choose(rng).copied()
     }
 
     fn remove_edge_move(&mut self, coord: CellCoord) {
         self.edge_moves.retain(|&cell_coord| cell_coord != coord);
     }
 
+    fn add_edge_move(&mut self, coord: CellCoord) {
+        if !self.edge_moves.contains(&coord) {
+            self.edge_moves.push(coord);
+        }
+    }
+
     pub fn reset(&mut self) {
         self.winning_moves.clear();
         self.x_potential_forks.clear();
@@ -470,7 +616,7 @@ impl BoardMetadata {
 
     #[allow(dead_code)]
     pub fn print(&self) {
-        for i in vec![Marker::X, Marker::O] {
+        for i in [Marker::X, Marker::O] {
             if let Some(winning_moves) = self.winning_moves.get(&i) {
                 if !winning_moves.is_empty() {
                     println!("Winning moves for {:?}:", i);
@@ -510,6 +656,8 @@ mod tests {
     // that is this file. So, to use the Board implementation code, we need
     // to bring that parent module into scope for our test module.
     use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
 
     #[test]
     fn checks_row_win() {
@@ -520,7 +668,7 @@ mod tests {
         board.place_marker(CellCoord::new(0, 2), marker);
         assert_eq!(
             board.check_board_state(CellCoord::new(0, 2), marker),
-            BoardState::Win
+            BoardState::Win(SetType::Row(0, 0))
         );
     }
 
@@ -533,7 +681,7 @@ mod tests {
         board.place_marker(CellCoord::new(2, 0), marker);
         assert_eq!(
             board.check_board_state(CellCoord::new(2, 0), marker),
-            BoardState::Win
+            BoardState::Win(SetType::Column(0, 0))
         );
     }
 
@@ -594,7 +742,7 @@ mod tests {
         board.place_marker(CellCoord::new(2, 2), marker);
         assert_eq!(
             board.check_board_state(CellCoord::new(2, 2), marker),
-            BoardState::Win
+            BoardState::Win(SetType::Diag1(0, 0))
         );
 
         let mut board = Board::new();
@@ -611,7 +759,7 @@ mod tests {
         board.place_marker(CellCoord::new(2, 0), marker);
         assert_eq!(
             board.check_board_state(CellCoord::new(2, 0), marker),
-            BoardState::Win
+            BoardState::Win(SetType::Diag2(0, 2))
         );
     }
 
@@ -704,9 +852,10 @@ mod tests {
 
     #[test]
     fn gets_corner_moves() {
+        let mut rng = StdRng::seed_from_u64(0);
         let mut board = Board::new();
         let mut corners = Vec::new();
-        while let Some(coord) = board.get_corner_move() {
+        while let Some(coord) = board.get_corner_move(&mut rng) {
             corners.push(coord);
             board.place_marker(coord, Marker::X);
         }
@@ -725,9 +874,10 @@ mod tests {
 
     #[test]
     fn gets_edge_moves() {
+        let mut rng = StdRng::seed_from_u64(0);
         let mut board = Board::new();
         let mut edges = Vec::new();
-        while let Some(coord) = board.get_edge_move() {
+        while let Some(coord) = board.get_edge_move(&mut rng) {
             edges.push(coord);
             board.place_marker(coord, Marker::X);
         }
@@ -743,4 +893,206 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn checks_win_length_on_larger_board() {
+        let marker = Marker::X;
+        let mut board = Board::with_size(5, 5, 4);
+        for column in 1..4 {
+            board.place_marker(CellCoord::new(2, column), marker);
+            assert_eq!(
+                board.check_board_state(CellCoord::new(2, column), marker),
+                BoardState::Playing
+            );
+        }
+        board.place_marker(CellCoord::new(2, 4), marker);
+        assert_eq!(
+            board.check_board_state(CellCoord::new(2, 4), marker),
+            BoardState::Win(SetType::Row(2, 1))
+        );
+    }
+
+    #[test]
+    fn checks_off_center_diag_win() {
+        let marker = Marker::O;
+        let mut board = Board::with_size(5, 4, 3);
+        board.place_marker(CellCoord::new(1, 4), marker);
+        board.place_marker(CellCoord::new(2, 3), marker);
+        board.place_marker(CellCoord::new(3, 2), marker);
+        assert_eq!(
+            board.check_board_state(CellCoord::new(2, 3), marker),
+            BoardState::Win(SetType::Diag2(1, 4))
+        );
+    }
+
+    #[test]
+    fn checks_tie_on_larger_board() {
+        // Alternating pairs of columns never line up three of a kind.
+        let mut board = Board::with_size(4, 4, 3);
+        let mut last_move = CellCoord::new(0, 0);
+        for (row, column) in itertools::iproduct!(0..4, 0..4) {
+            let marker = if (column / 2 + row) % 2 == 0 {
+                Marker::X
+            } else {
+                Marker::O
+            };
+            last_move = CellCoord::new(row, column);
+            board.place_marker(last_move, marker);
+            assert!(!matches!(
+                board.check_board_state(last_move, marker),
+                BoardState::Win(_)
+            ));
+        }
+        assert_eq!(
+            board.check_board_state(last_move, Marker::O),
+            BoardState::Tie
+        );
+    }
+
+    #[test]
+    fn validates_out_of_bounds_move_on_rectangular_board() {
+        let board = Board::with_size(7, 6, 4);
+        assert_eq!(board.validate_move(CellCoord::new(5, 6)), Move::Valid);
+        assert_eq!(board.validate_move(CellCoord::new(6, 0)), Move::OutOfBounds);
+        assert_eq!(board.validate_move(CellCoord::new(0, 7)), Move::OutOfBounds);
+    }
+
+    #[test]
+    fn counts_sets() {
+        // 3x3 with three in a row has the familiar 8 lines.
+        assert_eq!(Board::new().get_sets().len(), 8);
+        // 4x4 with three in a row: 8 rows, 8 columns, 4 of each diagonal.
+        assert_eq!(Board::with_size(4, 4, 3).get_sets().len(), 24);
+        // The 4x4x4 cube has 10 lines in each of its 4 layers, 16 straight
+        // through the layers, 16 diagonals across them and 4 space diagonals.
+        assert_eq!(Board::cube(4).get_sets().len(), 76);
+    }
+
+    #[test]
+    fn checks_win_across_layers() {
+        // From the top left corner of the first layer to the bottom right
+        // corner of the last. The layers sit side by side, 4 columns apart.
+        let marker = Marker::X;
+        let mut board = Board::cube(4);
+        assert_eq!(board.get_width(), 16);
+        assert_eq!(board.get_layers(), 4);
+        for layer in 0..3 {
+            board.place_marker(CellCoord::new(layer, layer * 5), marker);
+        }
+        assert_eq!(board.get_winning_move(marker), Some(CellCoord::new(3, 15)));
+        board.place_marker(CellCoord::new(3, 15), marker);
+        assert_eq!(
+            board.check_board_state(CellCoord::new(3, 15), marker),
+            BoardState::Win(SetType::Across(0, 0, 1, 1))
+        );
+
+        // A row doesn't carry on from one layer into the next.
+        let mut board = Board::cube(4);
+        for column in 2..6 {
+            board.place_marker(CellCoord::new(0, column), marker);
+        }
+        assert_eq!(
+            board.check_board_state(CellCoord::new(0, 5), marker),
+            BoardState::Playing
+        );
+    }
+
+    #[test]
+    fn drops_markers_with_gravity() {
+        let marker = Marker::X;
+        let mut board = Board::with_gravity(7, 6, 4);
+        assert_eq!(board.get_drop_cell(3), Some(CellCoord::new(5, 3)));
+        assert_eq!(board.validate_move(CellCoord::new(4, 3)), Move::Floating);
+        assert_eq!(board.validate_move(CellCoord::new(5, 3)), Move::Valid);
+        // Only the bottom cell of each column can be played.
+        assert_eq!(board.get_valid_moves().len(), 7);
+
+        // X is one short of a row above the bottom, but the cell it needs is
+        // still floating, so it isn't a winning move yet.
+        for column in 0..3 {
+            board.place_marker(CellCoord::new(5, column), Marker::O);
+            board.place_marker(CellCoord::new(4, column), marker);
+        }
+        assert_eq!(board.get_winning_move(marker), None);
+        board.place_marker(CellCoord::new(5, 3), Marker::O);
+        assert_eq!(board.get_winning_move(marker), Some(CellCoord::new(4, 3)));
+
+        for row in 0..6 {
+            board.place_marker(CellCoord::new(row, 6), marker);
+        }
+        assert_eq!(board.get_drop_cell(6), None);
+        assert_eq!(board.validate_move(CellCoord::new(0, 6)), Move::AlreadyUsed);
+    }
+
+    #[test]
+    fn gets_winning_move_on_larger_board() {
+        let mut board = Board::with_size(4, 4, 4);
+        board.place_marker(CellCoord::new(0, 3), Marker::O);
+        board.place_marker(CellCoord::new(1, 2), Marker::O);
+        board.place_marker(CellCoord::new(3, 0), Marker::O);
+        assert_eq!(
+            board.get_winning_move(Marker::O),
+            Some(CellCoord::new(2, 1))
+        );
+        assert_eq!(board.get_winning_move(Marker::X), None);
+    }
+
+    #[test]
+    fn ignores_overlapping_sets_as_forks() {
+        // X _ X _ X with four in a row. Both sets are waiting on columns 1
+        // and 3, so whichever one X takes, O blocks both sets with the other.
+        let mut board = Board::with_size(5, 1, 4);
+        board.place_marker(CellCoord::new(0, 0), Marker::X);
+        board.place_marker(CellCoord::new(0, 2), Marker::X);
+        board.place_marker(CellCoord::new(0, 4), Marker::X);
+        let forking_moves = board.get_forking_move(Marker::X);
+        assert!(forking_moves.is_empty());
+    }
+
+    #[test]
+    fn removes_marker() {
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(0, 0), Marker::X);
+        board.place_marker(CellCoord::new(0, 1), Marker::X);
+        board.place_marker(CellCoord::new(0, 2), Marker::O);
+        assert_eq!(board.get_winning_move(Marker::X), None);
+
+        board.remove_marker(CellCoord::new(0, 2));
+        assert_eq!(board.validate_move(CellCoord::new(0, 2)), Move::Valid);
+        assert_eq!(
+            board.get_winning_move(Marker::X),
+            Some(CellCoord::new(0, 2))
+        );
+
+        board.remove_marker(CellCoord::new(0, 1));
+        assert_eq!(board.get_winning_move(Marker::X), None);
+        assert_eq!(
+            board.check_board_state(CellCoord::new(0, 0), Marker::X),
+            BoardState::Playing
+        );
+    }
+
+    #[test]
+    fn restores_corner_and_edge_moves() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(0, 0), Marker::X);
+        board.place_marker(CellCoord::new(0, 1), Marker::O);
+        board.remove_marker(CellCoord::new(0, 0));
+        board.remove_marker(CellCoord::new(0, 1));
+
+        let mut corners = Vec::new();
+        while let Some(coord) = board.get_corner_move(&mut rng) {
+            corners.push(coord);
+            board.place_marker(coord, Marker::X);
+        }
+        assert_eq!(corners.len(), 4);
+
+        let mut edges = Vec::new();
+        while let Some(coord) = board.get_edge_move(&mut rng) {
+            edges.push(coord);
+            board.place_marker(coord, Marker::O);
+        }
+        assert_eq!(edges.len(), 4);
+    }
 }
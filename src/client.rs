@@ -0,0 +1,270 @@
+// The client side of the protocol described in server.rs. A background
+// thread reads the server's lines and turns them into ServerMessages, so the
+// TUI can check for news from the server without blocking on the socket.
+use crate::common::{CellCoord, Marker};
+use crate::save;
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum BoardStatus {
+    Turn(Marker),
+    Waiting,
+    // None for a tie.
+    Over(Option<Marker>),
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct RemoteBoard {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<char>,
+    pub status: BoardStatus,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ServerMessage {
+    Welcome,
+    // Each open game's id, and the marker we'd play if we joined it.
+    Games(Vec<(u32, Marker)>),
+    Joined(u32, Marker),
+    Board(RemoteBoard),
+    OpponentLeft,
+    Error(String),
+    // Not sent by the server. The reader thread adds it when the
+    // connection closes.
+    Disconnected,
+}
+
+impl ServerMessage {
+    pub fn parse(line: &str) -> Result<ServerMessage, String> {
+        let bad_message = || format!("Bad message from server: {}", line);
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            ["WELCOME", ..] => Ok(ServerMessage::Welcome),
+            ["GAMES", games @ ..] => games
+                .iter()
+                .map(|game| {
+                    let (id, marker) = game.split_once(':').ok_or_else(bad_message)?;
+                    Ok((
+                        id.parse().map_err(|_| bad_message())?,
+                        parse_marker(marker).ok_or_else(bad_message)?,
+                    ))
+                })
+                .collect::<Result<_, String>>()
+                .map(ServerMessage::Games),
+            ["JOINED", id, marker] => Ok(ServerMessage::Joined(
+                id.parse().map_err(|_| bad_message())?,
+                parse_marker(marker).ok_or_else(bad_message)?,
+            )),
+            ["BOARD", size, cells, status @ ..] => {
+                let (width, height) = size.split_once('x').ok_or_else(bad_message)?;
+                let width: usize = width.parse().map_err(|_| bad_message())?;
+                let height: usize = height.parse().map_err(|_| bad_message())?;
+                let cells: Vec<char> = cells.chars().collect();
+                if cells.len() != width * height {
+                    return Err(bad_message());
+                }
+                let status = match status {
+                    ["TURN", marker] => {
+                        BoardStatus::Turn(parse_marker(marker).ok_or_else(bad_message)?)
+                    }
+                    ["WAITING"] => BoardStatus::Waiting,
+                    ["OVER", "TIE"] => BoardStatus::Over(None),
+                    ["OVER", marker] => {
+                        BoardStatus::Over(Some(parse_marker(marker).ok_or_else(bad_message)?))
+                    }
+                    _ => return Err(bad_message()),
+                };
+                Ok(ServerMessage::Board(RemoteBoard {
+                    width,
+                    height,
+                    cells,
+                    status,
+                }))
+            }
+            ["OPPONENT_LEFT"] => Ok(ServerMessage::OpponentLeft),
+            ["ERROR", ..] => Ok(ServerMessage::Error(
+                line.trim_start()
+                    .trim_start_matches("ERROR")
+                    .trim()
+                    .to_string(),
+            )),
+            _ => Err(bad_message()),
+        }
+    }
+}
+
+fn parse_marker(text: &str) -> Option<Marker> {
+    match text {
+        "X" => Some(Marker::X),
+        "O" => Some(Marker::O),
+        _ => None,
+    }
+}
+
+pub struct ServerConnection {
+    writer: TcpStream,
+    messages: Receiver<ServerMessage>,
+}
+
+impl ServerConnection {
+    pub fn connect(address: &str) -> io::Result<ServerConnection> {
+        let stream = TcpStream::connect(address)?;
+        stream.set_nodelay(true)?;
+        let reader = BufReader::new(stream.try_clone()?);
+
+        let (sender, messages) = mpsc::channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let message = match line {
+                    Ok(line) => ServerMessage::parse(&line).unwrap_or_else(ServerMessage::Error),
+                    Err(_) => break,
+                };
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+            let _ = sender.send(ServerMessage::Disconnected);
+        });
+
+        Ok(ServerConnection {
+            writer: stream,
+            messages,
+        })
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.writer, "{}", command)
+    }
+
+    pub fn list_games(&mut self) -> io::Result<()> {
+        self.send("LIST")
+    }
+
+    // `opponent` is a player kind, like "Human" or "Optimal".
+    pub fn create_game(&mut self, opponent: &str, marker: Marker) -> io::Result<()> {
+        self.send(&format!("CREATE {} {:?}", opponent, marker))
+    }
+
+    pub fn join_game(&mut self, id: u32) -> io::Result<()> {
+        self.send(&format!("JOIN {}", id))
+    }
+
+    pub fn make_move(&mut self, player_move: CellCoord) -> io::Result<()> {
+        self.send(&format!("MOVE {}", save::cell_to_notation(player_move)))
+    }
+
+    pub fn leave_game(&mut self) -> io::Result<()> {
+        self.send("LEAVE")
+    }
+
+    // Returns the next message from the server if one has arrived, without
+    // waiting for one.
+    pub fn try_receive(&self) -> Option<ServerMessage> {
+        match self.messages.try_recv() {
+            Ok(message) => Some(message),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(ServerMessage::Disconnected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server;
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_messages() {
+        assert_eq!(
+            ServerMessage::parse("GAMES 2:O 5:X"),
+            Ok(ServerMessage::Games(vec![(2, Marker::O), (5, Marker::X)]))
+        );
+        assert_eq!(
+            ServerMessage::parse("GAMES"),
+            Ok(ServerMessage::Games(Vec::new()))
+        );
+        assert_eq!(
+            ServerMessage::parse("JOINED 3 O"),
+            Ok(ServerMessage::Joined(3, Marker::O))
+        );
+        assert_eq!(
+            ServerMessage::parse("ERROR It's not your turn"),
+            Ok(ServerMessage::Error(String::from("It's not your turn")))
+        );
+
+        let board = match ServerMessage::parse("BOARD 3x3 X___O____ TURN X") {
+            Ok(ServerMessage::Board(board)) => board,
+            message => panic!("Expected a board, got {:?}", message),
+        };
+        assert_eq!((board.width, board.height), (3, 3));
+        assert_eq!(board.cells[4], 'O');
+        assert_eq!(board.status, BoardStatus::Turn(Marker::X));
+
+        match ServerMessage::parse("BOARD 3x3 XXXOO____ OVER TIE") {
+            Ok(ServerMessage::Board(board)) => assert_eq!(board.status, BoardStatus::Over(None)),
+            message => panic!("Expected a board, got {:?}", message),
+        }
+    }
+
+    #[test]
+    fn rejects_bad_messages() {
+        assert!(ServerMessage::parse("HELLO").is_err());
+        assert!(ServerMessage::parse("GAMES 2").is_err());
+        assert!(ServerMessage::parse("BOARD 3x3 X__ TURN X").is_err());
+        assert!(ServerMessage::parse("BOARD 3x3 _________ TURN Z").is_err());
+    }
+
+    fn receive(connection: &ServerConnection) -> ServerMessage {
+        for _ in 0..500 {
+            if let Some(message) = connection.try_receive() {
+                return message;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("The server didn't answer");
+    }
+
+    #[test]
+    fn plays_against_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || server::serve(listener));
+
+        let mut connection = ServerConnection::connect(&address).unwrap();
+        assert_eq!(receive(&connection), ServerMessage::Welcome);
+        connection.create_game("Human", Marker::O).unwrap();
+        assert_eq!(receive(&connection), ServerMessage::Joined(1, Marker::O));
+        receive(&connection);
+
+        let mut opponent = ServerConnection::connect(&address).unwrap();
+        receive(&opponent);
+        opponent.list_games().unwrap();
+        assert_eq!(
+            receive(&opponent),
+            ServerMessage::Games(vec![(1, Marker::X)])
+        );
+        opponent.join_game(1).unwrap();
+        assert_eq!(receive(&opponent), ServerMessage::Joined(1, Marker::X));
+
+        opponent.make_move(CellCoord::new(1, 1)).unwrap();
+        let board = loop {
+            if let ServerMessage::Board(board) = receive(&connection) {
+                if board.status == BoardStatus::Turn(Marker::O) {
+                    break board;
+                }
+            }
+        };
+        assert_eq!(board.cells[4], 'X');
+
+        opponent.leave_game().unwrap();
+        assert_eq!(receive(&connection), ServerMessage::OpponentLeft);
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt;
+
 #[derive(Debug, PartialEq, Copy, Clone, Eq, Hash)]
 pub enum Marker {
     X,
@@ -24,37 +26,93 @@ pub enum CellState {
 pub enum CellType {
     Corner,
     Edge,
-    Center
+    Center,
 }
 
+// A cell's coordinate doesn't know anything about the board it belongs to.
+// Whether a coordinate is in bounds, or is a corner or an edge, depends on
+// the board's dimensions, so the `Board` answers those questions.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct CellCoord {
     pub row: usize,
     pub column: usize,
-    // An index associated with the row and column cell. Used for
-    // accessing flat arrays.
-    index: usize,
-    cell_type: CellType,
 }
 
 impl CellCoord {
     pub fn new(row: usize, column: usize) -> CellCoord {
-        let cell_type = match (row, column) {
-            (0, 0) | (0, 2) | (2, 2) | (2, 0) => CellType::Corner,
-            (0, 1) | (1, 2) | (2, 1) | (1, 0) => CellType::Edge,
-            _ => CellType::Center,
+        CellCoord { row, column }
+    }
+}
+
+// How big a board is: `width` x `height` cells, stacked `layers` deep. Only
+// a cube has more than one layer. A board keeps a cube's layers side by
+// side, but that's its own business. Everything else goes by this size.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+    pub layers: usize,
+}
+
+impl BoardSize {
+    pub fn new(width: usize, height: usize, layers: usize) -> BoardSize {
+        BoardSize {
+            width,
+            height,
+            layers,
+        }
+    }
+
+    pub fn get_cell_count(self) -> usize {
+        self.width * self.height * self.layers
+    }
+
+    // The columns cell coordinates count across, which run on through a
+    // cube's layers from left to right. That's how the notation names
+    // cells, so a 4x4x4 cube's columns are lettered a to p.
+    pub fn get_columns(self) -> usize {
+        self.width * self.layers
+    }
+
+    // Reads a size like "7x6", or "4x4x4" for a cube. The columns have to
+    // fit in the letters of the notation.
+    pub fn from_text(text: &str) -> Option<BoardSize> {
+        let numbers: Vec<usize> = text
+            .split('x')
+            .map(|number| number.parse().ok())
+            .collect::<Option<_>>()?;
+        let size = match *numbers.as_slice() {
+            [width, height] => BoardSize::new(width, height, 1),
+            [width, height, layers] => BoardSize::new(width, height, layers),
+            _ => return None,
         };
+        if size.get_cell_count() == 0 || size.get_columns() > 26 {
+            return None;
+        }
+        Some(size)
+    }
+}
 
-        CellCoord {
-            row,
-            column,
-            index: row * 3 + column,
-            cell_type,
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.layers {
+            1 => write!(f, "{}x{}", self.width, self.height),
+            layers => write!(f, "{}x{}x{}", self.width, self.height, layers),
         }
     }
+}
+
+// In wild tic-tac-toe, a player picks which marker to place as well as
+// where, so a move needs both.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WildMove {
+    pub cell: CellCoord,
+    pub marker: Marker,
+}
 
-    pub fn get_cell_type(&self) -> CellType {
-        self.cell_type
+impl WildMove {
+    pub fn new(cell: CellCoord, marker: Marker) -> WildMove {
+        WildMove { cell, marker }
     }
 }
 
@@ -63,4 +121,9 @@ pub enum Move {
     Valid,
     AlreadyUsed,
     OutOfBounds,
+    // In ultimate tic-tac-toe, a move outside the board the last move sent
+    // you to.
+    WrongBoard,
+    // With gravity, an empty cell that still has an empty cell below it.
+    Floating,
 }
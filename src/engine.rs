@@ -0,0 +1,265 @@
+// The engine protocol lets programs play miktactoe over stdin and stdout, a
+// bit like UCI does for chess engines. A controller (like ExternalPlayer)
+// starts the engine and sends it one command per line:
+//
+//     newgame [<w>x<h> <k>]   start a new game, on a 3x3 board with three in
+//                             a row unless a size is given
+//     position [<moves>]      the moves played so far, from an empty board,
+//                             starting with X, like `position b2 a1`
+//     go                      ask for a move for whoever's turn it is
+//     isready                 check the engine is done with earlier commands
+//     quit                    stop the engine
+//
+// The engine answers `go` with `bestmove <cell>` and `isready` with
+// `readyok`. Cells use the same notation as saved games, so a1 is the top
+// left corner. A command the engine can't follow gets `error <reason>`.
+// Controllers ignore any other lines, so engines are free to print notes.
+//
+// `miktactoe engine --ai optimal` answers the protocol with one of the
+// built-in computers, which is handy for testing other controllers.
+use crate::board::{Board, BoardState};
+use crate::common::{CellCoord, Marker, Move};
+use crate::player::{ai_basic, ai_forking, ai_minimax, ai_optimal, ai_random, Player};
+use crate::save;
+use crate::PlayerTypeEntry;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::io::{self, BufRead, Write};
+
+const USAGE: &str = "usage: miktactoe engine [--ai <player>] [--seed <seed>]
+players: random, basic, forking, optimal, minimax";
+
+struct Engine {
+    ai: PlayerTypeEntry,
+    board: Board,
+    move_count: usize,
+    // Whether the last move of the position won or filled the board.
+    game_over: bool,
+    rng: StdRng,
+}
+
+impl Engine {
+    fn new(ai: PlayerTypeEntry, seed: u64) -> Engine {
+        Engine {
+            ai,
+            board: Board::new(),
+            move_count: 0,
+            game_over: false,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // Returns the engine's answer to `line`, if it has one.
+    fn handle_command(&mut self, line: &str) -> Result<Option<String>, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => Ok(None),
+            ["newgame"] => {
+                self.board = Board::new();
+                self.move_count = 0;
+                self.game_over = false;
+                Ok(None)
+            }
+            ["newgame", size, win_length] => {
+                self.board = parse_board(size, win_length)?;
+                self.move_count = 0;
+                self.game_over = false;
+                Ok(None)
+            }
+            ["position", moves @ ..] => {
+                self.set_position(moves)?;
+                Ok(None)
+            }
+            ["go"] => {
+                let player_move = self.get_best_move()?;
+                Ok(Some(format!(
+                    "bestmove {}",
+                    save::cell_to_notation(player_move)
+                )))
+            }
+            ["isready"] => Ok(Some(String::from("readyok"))),
+            _ => Err(format!("Unknown command: {}", line)),
+        }
+    }
+
+    fn set_position(&mut self, moves: &[&str]) -> Result<(), String> {
+        let mut board = Board::with_size(
+            self.board.get_width(),
+            self.board.get_height(),
+            self.board.get_win_length(),
+        );
+        let mut game_over = false;
+        for (move_number, notation) in moves.iter().enumerate() {
+            let player_move = save::notation_to_cell(notation)?;
+            if game_over {
+                return Err(format!("{} comes after the game ended", notation));
+            }
+            if board.validate_move(player_move) != Move::Valid {
+                return Err(format!("{} isn't a legal move", notation));
+            }
+            let marker = get_move_marker(move_number);
+            board.place_marker(player_move, marker);
+            game_over = board.check_board_state(player_move, marker) != BoardState::Playing;
+        }
+        self.board = board;
+        self.move_count = moves.len();
+        self.game_over = game_over;
+        Ok(())
+    }
+
+    fn get_best_move(&mut self) -> Result<CellCoord, String> {
+        if self.game_over {
+            return Err(String::from("The game is over"));
+        }
+        let marker = get_move_marker(self.move_count);
+        let seed = self.rng.gen();
+        let name = self.ai.get_name();
+        let board = &self.board;
+        let player_move = match self.ai {
+            PlayerTypeEntry::RandomComp => {
+                ai_random::RandomAI::with_seed(name, marker, seed).get_valid_move(board)
+            }
+            PlayerTypeEntry::BasicComp => {
+                ai_basic::BasicAI::with_seed(name, marker, seed).get_valid_move(board)
+            }
+            PlayerTypeEntry::ForkingComp => {
+                ai_forking::ForkingAI::with_seed(name, marker, seed).get_valid_move(board)
+            }
+            PlayerTypeEntry::OptimalComp => {
+                ai_optimal::OptimalAI::with_seed(name, marker, seed).get_valid_move(board)
+            }
+            PlayerTypeEntry::MinimaxComp => {
+                ai_minimax::MinimaxAI::with_seed(name, marker, seed).get_valid_move(board)
+            }
+            PlayerTypeEntry::Human | PlayerTypeEntry::External { .. } => {
+                return Err(format!("{} can't play as an engine", name))
+            }
+        };
+        Ok(player_move)
+    }
+}
+
+// X always moves first.
+fn get_move_marker(move_number: usize) -> Marker {
+    match move_number % 2 {
+        0 => Marker::X,
+        _ => Marker::O,
+    }
+}
+
+fn parse_board(size: &str, win_length: &str) -> Result<Board, String> {
+    let bad_size = || format!("Bad board size: {} {}", size, win_length);
+    let (width, height) = size.split_once('x').ok_or_else(bad_size)?;
+    let width: usize = width.parse().map_err(|_| bad_size())?;
+    let height: usize = height.parse().map_err(|_| bad_size())?;
+    let win_length: usize = win_length.parse().map_err(|_| bad_size())?;
+    if width == 0
+        || height == 0
+        || width > 26
+        || win_length == 0
+        || (win_length > width && win_length > height)
+    {
+        return Err(bad_size());
+    }
+    Ok(Board::with_size(width, height, win_length))
+}
+
+pub fn run_engine(args: &[String]) -> Result<(), String> {
+    let mut ai = PlayerTypeEntry::OptimalComp;
+    let mut seed = rand::random();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))?;
+        match arg.as_str() {
+            "--ai" => {
+                ai = match PlayerTypeEntry::from_kind(value) {
+                    Some(PlayerTypeEntry::Human) | None => {
+                        return Err(format!("Unknown player: {}\n{}", value, USAGE))
+                    }
+                    Some(ai) => ai,
+                }
+            }
+            "--seed" => {
+                seed = value
+                    .parse()
+                    .map_err(|_| format!("--seed should be a number, not {}", value))?
+            }
+            _ => return Err(format!("Unknown option: {}\n{}", arg, USAGE)),
+        }
+    }
+
+    let mut engine = Engine::new(ai, seed);
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    for line in io::stdin().lock().lines() {
+        let line = line.map_err(|error| error.to_string())?;
+        if line.trim() == "quit" {
+            break;
+        }
+        let reply = match engine.handle_command(&line) {
+            Ok(Some(reply)) => reply,
+            Ok(None) => continue,
+            Err(reason) => format!("error {}", reason),
+        };
+        // Controllers wait on every answer, so don't let it sit in a buffer.
+        writeln!(stdout, "{}", reply)
+            .and_then(|_| stdout.flush())
+            .map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answers_go_with_best_move() {
+        let mut engine = Engine::new(PlayerTypeEntry::OptimalComp, 1);
+        assert_eq!(engine.handle_command("newgame"), Ok(None));
+        assert_eq!(engine.handle_command("position a1 b1 b2 c1"), Ok(None));
+        // X to move, and c3 completes the diagonal.
+        assert_eq!(
+            engine.handle_command("go"),
+            Ok(Some(String::from("bestmove c3")))
+        );
+        assert_eq!(
+            engine.handle_command("isready"),
+            Ok(Some(String::from("readyok")))
+        );
+    }
+
+    #[test]
+    fn plays_on_other_board_sizes() {
+        let mut engine = Engine::new(PlayerTypeEntry::BasicComp, 1);
+        engine.handle_command("newgame 5x4 4").unwrap();
+        engine.handle_command("position a4 e1 b4 e2 c4").unwrap();
+        // O has to block the fourth X on the bottom row.
+        assert_eq!(
+            engine.handle_command("go"),
+            Ok(Some(String::from("bestmove d4")))
+        );
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        let mut engine = Engine::new(PlayerTypeEntry::RandomComp, 1);
+        assert!(engine.handle_command("position a1 a1").is_err());
+        assert!(engine.handle_command("position z9").is_err());
+        assert!(engine.handle_command("newgame 3x3 4").is_err());
+        assert!(engine.handle_command("jump").is_err());
+
+        engine
+            .handle_command("position a1 b1 c1 a2 b2 c2 b3 a3 c3")
+            .unwrap();
+        assert!(engine.handle_command("go").is_err());
+
+        // X's a1 b2 c3 diagonal ends the game, so there's nothing after it
+        // to play, or to ask for.
+        engine.handle_command("position a1 b1 b2 c1 c3").unwrap();
+        assert!(engine.handle_command("go").is_err());
+        assert!(engine.handle_command("position a1 b1 b2 c1 c3 a3").is_err());
+    }
+}
@@ -1,21 +1,129 @@
 use crate::board::{Board, BoardState};
-use crate::common::{CellCoord, Marker, Move};
-use crate::player::Player;
+use crate::common::{BoardSize, CellCoord, Marker, Move};
+use crate::player::ai_optimal::{MoveReason, OptimalAI};
+use crate::player::{Misere, MiserePlayer, Player};
+use crate::save::{self, SavedGame};
+use crate::ultimate::UltimateBoard;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum GameState {
     Player1Turn,
     Player2Turn,
     Done,
 }
 
+// The kinds of game there are to play. Classic is tic-tac-toe on one board,
+// of any size. Misère is the same game, except completing a line loses.
+// Qubic is four in a row on a 4x4x4 cube. Gravity is played on a board where
+// markers drop to the bottom of their column, like Connect Four. Ultimate is
+// played on a 3x3 grid of boards, and lives in ultimate.rs. In Wild, either
+// player can place either marker, and it lives in wild.rs.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Variant {
+    Classic,
+    Misere,
+    Qubic,
+    Gravity,
+    Ultimate,
+    Wild,
+}
+
+impl Variant {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Variant::Classic => "Classic",
+            Variant::Misere => "Misere",
+            Variant::Qubic => "Qubic",
+            Variant::Gravity => "Gravity",
+            Variant::Ultimate => "Ultimate",
+            Variant::Wild => "Wild",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Variant> {
+        [
+            Variant::Classic,
+            Variant::Misere,
+            Variant::Qubic,
+            Variant::Gravity,
+            Variant::Ultimate,
+            Variant::Wild,
+        ]
+        .iter()
+        .copied()
+        .find(|variant| variant.get_name() == name)
+    }
+}
+
+// Both seats' seeds are drawn from the game's seed, so a single number
+// reproduces every random choice the computers make.
+pub fn get_seat_seeds(seed: u64) -> (u64, u64) {
+    let mut seeds = StdRng::seed_from_u64(seed);
+    (seeds.gen(), seeds.gen())
+}
+
+// Who's sitting in one of the game's seats, for showing in the UI.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct SeatInfo<'n> {
+    pub name: &'n str,
+    // The kind of player, like "Human" or "Optimal".
+    pub kind: &'static str,
+    pub marker: Marker,
+}
+
 pub trait Game {
     fn run(&mut self) -> GameState;
     fn make_human_move(&mut self, player_move: CellCoord) -> GameState;
+    fn validate_move(&self, player_move: CellCoord) -> Move;
+    fn undo(&mut self) -> GameState;
+    fn redo(&mut self) -> GameState;
     fn get_game_state(&self) -> GameState;
+    fn is_human_turn(&self) -> bool;
+    fn get_turn_marker(&self) -> Option<Marker>;
+    // The marker the next move will place. That's the marker of the side
+    // to move, except in wild tic-tac-toe, where a human picks it.
+    fn get_placing_marker(&self) -> Option<Marker> {
+        self.get_turn_marker()
+    }
+    // In wild tic-tac-toe, switches the marker a human will place next.
+    // Everywhere else, players always place their own marker.
+    fn switch_marker(&mut self) {}
     fn get_winner(&self) -> Winner;
+    // Why the loser forfeited, if the game ended that way instead of on the
+    // board.
+    fn get_forfeit(&self) -> Option<&str> {
+        None
+    }
+    fn get_move_history(&self) -> &[CellCoord];
+    // The cells of the line that decided the game, or nothing if nobody has
+    // won. In misère, that's the loser's line.
+    fn get_winning_line(&self) -> Vec<CellCoord>;
+    // Player 1's seat, then player 2's.
+    fn get_seats(&self) -> [SeatInfo<'_>; 2];
+    // The move OptimalAI would make for whoever's turn it is, and why.
+    fn get_hint(&self) -> Option<(CellCoord, MoveReason)>;
+    fn get_variant(&self) -> Variant;
+    // The grid of boards, for drawing an ultimate game. Other games don't
+    // have one.
+    fn get_ultimate_board(&self) -> Option<&UltimateBoard> {
+        None
+    }
     fn get_cellstate_char(&self, cell_index: usize) -> char;
+    fn get_board_size(&self) -> BoardSize;
+    // The cell a marker dropped into `column` would land in, on a board
+    // with gravity. None if the column is full, or nothing falls.
+    fn get_drop_cell(&self, _column: usize) -> Option<CellCoord> {
+        None
+    }
     fn reset(&mut self);
+    fn to_saved_game(&self) -> SavedGame;
+    fn load_moves(&mut self, moves: &[CellCoord]) -> Result<GameState, String>;
+    // Plays a saved game's moves. Only wild games need more than the cells,
+    // because there the marker that went with each move was up to the player.
+    fn load_saved_game(&mut self, saved_game: &SavedGame) -> Result<GameState, String> {
+        self.load_moves(&saved_game.moves)
+    }
 }
 
 pub struct TicTacToe<'a, P1, P2> {
@@ -24,59 +132,172 @@ pub struct TicTacToe<'a, P1, P2> {
     board: Board,
     record: Record<'a>,
     state: GameState,
+    // Classic, Misere, Qubic or Gravity. They're all played the same way.
+    // Misère changes who wins with a completed line, Qubic is played on a
+    // cube, and the board only takes Gravity's moves at the bottom of a column.
+    variant: Variant,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Debug, PartialEq, Copy, Clone)]
 pub enum Winner {
     Player1,
     Player2,
     None,
 }
 
-struct Record<'a> {
-    player1: &'a str,
-    player2: &'a str,
+// Ultimate tic-tac-toe keeps its history the same way, so this is shared
+// with ultimate.rs.
+pub(crate) struct Record<'a> {
+    pub player1: &'a str,
+    pub player2: &'a str,
+    // The seed the seats' seeds were drawn from.
+    pub seed: u64,
     pub winner: Winner,
-    move_history: Vec<CellCoord>,
+    // Why the loser forfeited, when a player couldn't make a move.
+    pub forfeit: Option<String>,
+    pub move_history: Vec<CellCoord>,
+    // Moves taken back with undo, most recent last, so they can be redone.
+    undone_moves: Vec<CellCoord>,
 }
 
 impl<'a, P1: Player<'a>, P2: Player<'a>> TicTacToe<'a, P1, P2> {
     pub fn new(player1: P1, player2: P2) -> TicTacToe<'a, P1, P2> {
+        TicTacToe::with_board(player1, player2, Board::new())
+    }
+
+    // Plays on the given board instead of the standard 3x3 one. The board
+    // should be empty. A board with layers is a cube, which makes it Qubic,
+    // and a board with gravity makes it Gravity.
+    pub fn with_board(player1: P1, player2: P2, board: Board) -> TicTacToe<'a, P1, P2> {
         TicTacToe {
-            record: Record::new(player1.get_name(), player2.get_name()),
+            record: Record::new(player1.get_name(), player2.get_name(), rand::random()),
             player1,
             player2,
-            board: Board::new(),
+            variant: match board.get_layers() {
+                _ if board.has_gravity() => Variant::Gravity,
+                1 => Variant::Classic,
+                _ => Variant::Qubic,
+            },
+            board,
             state: GameState::Player1Turn,
         }
     }
+
+    // Sets the seed the seats were made from with `get_seat_seeds()`. Saved
+    // games keep it, and playing again goes on from it, so every round can
+    // be reproduced.
+    pub fn seeded(mut self, seed: u64) -> Self {
+        self.record.seed = seed;
+        self
+    }
+
+    // Records a new move and plays it for the player whose turn it is.
+    // The move must already be valid.
+    fn apply_move(&mut self, player_move: CellCoord) {
+        self.record.record_move(player_move);
+        self.play_move(player_move);
+    }
+
+    // Ends the game without a move, because the player whose turn it was
+    // couldn't make one.
+    fn forfeit(&mut self, winner: Winner, reason: String) {
+        self.state = GameState::Done;
+        self.record.record_outcome(winner);
+        self.record.forfeit = Some(reason);
+    }
+
+    // Places a marker for the player whose turn it is and advances the game
+    // state. Recording the move is left to the caller, since a redone move is
+    // already in the record.
+    fn play_move(&mut self, player_move: CellCoord) {
+        let (marker, mover, other, next_state) = match self.state {
+            GameState::Player1Turn => (
+                self.player1.get_marker(),
+                Winner::Player1,
+                Winner::Player2,
+                GameState::Player2Turn,
+            ),
+            GameState::Player2Turn => (
+                self.player2.get_marker(),
+                Winner::Player2,
+                Winner::Player1,
+                GameState::Player1Turn,
+            ),
+            GameState::Done => return,
+        };
+
+        self.board.place_marker(player_move, marker);
+        match self.board.check_board_state(player_move, marker) {
+            BoardState::Win(_) => {
+                self.state = GameState::Done;
+                // In misère, completing a line hands the game to the other
+                // player.
+                let winner = match self.variant {
+                    Variant::Misere => other,
+                    _ => mover,
+                };
+                self.record.record_outcome(winner);
+            }
+            BoardState::Tie => {
+                self.state = GameState::Done;
+                self.record.record_outcome(Winner::None);
+            }
+            BoardState::Playing => {
+                self.state = next_state;
+            }
+        }
+    }
+
+    // Player 1 always moves first, so even numbered moves (counting from
+    // zero) belong to player 1.
+    fn get_move_turn(move_number: usize) -> GameState {
+        match move_number % 2 {
+            0 => GameState::Player1Turn,
+            _ => GameState::Player2Turn,
+        }
+    }
+
+    fn is_human_move(&self, move_number: usize) -> bool {
+        self.is_human_seat(Self::get_move_turn(move_number))
+    }
+
+    fn is_human_seat(&self, state: GameState) -> bool {
+        match state {
+            GameState::Player1Turn => self.player1.is_human(),
+            GameState::Player2Turn => self.player2.is_human(),
+            GameState::Done => false,
+        }
+    }
 }
 
-impl<'a, P1: Player<'a>, P2: Player<'a>> Game for TicTacToe<'a, P1, P2> {
+// Misère tic-tac-toe is played on the given board, but whoever completes a
+// line loses. The players have to know that, so they sit in `Misere` seats
+// that ask them for their misère moves.
+impl<'a, M1: MiserePlayer<'a>, M2: MiserePlayer<'a>> TicTacToe<'a, Misere<M1>, Misere<M2>> {
+    pub fn misere(player1: M1, player2: M2, board: Board) -> Self {
+        TicTacToe {
+            variant: Variant::Misere,
+            ..TicTacToe::with_board(Misere(player1), Misere(player2), board)
+        }
+    }
+}
 
+impl<'a, P1: Player<'a>, P2: Player<'a>> Game for TicTacToe<'a, P1, P2> {
     fn run(&mut self) -> GameState {
         match self.state {
-            GameState::Player1Turn => GameState::Player1Turn,
+            GameState::Player1Turn if self.player1.is_human() => GameState::Player1Turn,
+            GameState::Player1Turn => {
+                match self.player1.try_get_move(&self.board) {
+                    Ok(comp_move) => self.apply_move(comp_move),
+                    Err(reason) => self.forfeit(Winner::Player2, reason),
+                }
+                self.state
+            }
+            GameState::Player2Turn if self.player2.is_human() => GameState::Player2Turn,
             GameState::Player2Turn => {
-                let comp_move = self.player2.get_valid_move(&self.board);
-                self.board
-                    .place_marker(comp_move, self.player2.get_marker());
-                self.record.record_move(comp_move);
-                match self
-                    .board
-                    .check_board_state(comp_move, self.player2.get_marker())
-                {
-                    BoardState::Win => {
-                        self.state = GameState::Done;
-                        self.record.record_outcome(Winner::Player2);
-                    }
-                    BoardState::Tie => {
-                        self.state = GameState::Done;
-                        self.record.record_outcome(Winner::None);
-                    }
-                    BoardState::Playing => {
-                        self.state = GameState::Player1Turn;
-                    }
+                match self.player2.try_get_move(&self.board) {
+                    Ok(comp_move) => self.apply_move(comp_move),
+                    Err(reason) => self.forfeit(Winner::Player1, reason),
                 }
                 self.state
             }
@@ -84,69 +305,264 @@ impl<'a, P1: Player<'a>, P2: Player<'a>> Game for TicTacToe<'a, P1, P2> {
         }
     }
 
+    // Places a marker for whichever human player's turn it is. Moves made
+    // while it's a computer's turn, or to invalid cells, are ignored.
     fn make_human_move(&mut self, player_move: CellCoord) -> GameState {
-        if self.state == GameState::Player1Turn {
-            if self.board.validate_move(player_move) == Move::Valid {
-                self.board
-                    .place_marker(player_move, self.player1.get_marker());
-                self.record.record_move(player_move);
-                match self
-                    .board
-                    .check_board_state(player_move, self.player1.get_marker())
-                {
-                    BoardState::Win => {
-                        self.state = GameState::Done;
-                        self.record.record_outcome(Winner::Player1);
-                    }
-                    BoardState::Tie => {
-                        self.state = GameState::Done;
-                        self.record.record_outcome(Winner::None);
-                    }
-                    BoardState::Playing => {
-                        self.state = GameState::Player2Turn;
-                    }
-                }
+        if self.is_human_turn() && self.board.validate_move(player_move) == Move::Valid {
+            self.apply_move(player_move);
+        }
+        self.state
+    }
+
+    fn validate_move(&self, player_move: CellCoord) -> Move {
+        self.board.validate_move(player_move)
+    }
+
+    // Takes back moves until a human's move has been taken back, so in a game
+    // against the computer, its reply is undone along with the human's move.
+    // Games without a human move to take back are left alone.
+    fn undo(&mut self) -> GameState {
+        let move_count = self.record.move_history.len();
+        if !(0..move_count).any(|move_number| self.is_human_move(move_number)) {
+            return self.state;
+        }
+
+        while let Some(player_move) = self.record.undo_move() {
+            let move_number = self.record.move_history.len();
+            self.board.remove_marker(player_move);
+            self.record.record_outcome(Winner::None);
+            self.state = Self::get_move_turn(move_number);
+            if self.is_human_move(move_number) {
+                break;
+            }
+        }
+        self.state
+    }
+
+    // Replays undone moves until it's a human's turn again, so the computer's
+    // reply comes back along with the human's move.
+    fn redo(&mut self) -> GameState {
+        while self.state != GameState::Done {
+            match self.record.redo_move() {
+                Some(player_move) => self.play_move(player_move),
+                None => break,
+            }
+            if self.is_human_seat(self.state) {
+                break;
             }
         }
         self.state
     }
 
+    fn get_variant(&self) -> Variant {
+        self.variant
+    }
+
     fn get_cellstate_char(&self, cell_index: usize) -> char {
         self.board.get_cellstate_char(cell_index)
     }
 
+    fn get_board_size(&self) -> BoardSize {
+        self.board.get_size()
+    }
+
+    fn get_drop_cell(&self, column: usize) -> Option<CellCoord> {
+        if self.board.has_gravity() {
+            self.board.get_drop_cell(column)
+        } else {
+            None
+        }
+    }
+
     fn get_game_state(&self) -> GameState {
         self.state
     }
 
+    fn is_human_turn(&self) -> bool {
+        self.is_human_seat(self.state)
+    }
+
+    fn get_turn_marker(&self) -> Option<Marker> {
+        match self.state {
+            GameState::Player1Turn => Some(self.player1.get_marker()),
+            GameState::Player2Turn => Some(self.player2.get_marker()),
+            GameState::Done => None,
+        }
+    }
+
     fn get_winner(&self) -> Winner {
         self.record.winner
     }
 
+    fn get_forfeit(&self) -> Option<&str> {
+        self.record.forfeit.as_deref()
+    }
+
+    fn get_move_history(&self) -> &[CellCoord] {
+        &self.record.move_history
+    }
+
+    // Worked out again from the board when it's needed, so there's nothing
+    // to keep up to date through undo and redo. The line always belongs to
+    // whoever made the last move, even in misère where they lost.
+    fn get_winning_line(&self) -> Vec<CellCoord> {
+        if self.record.winner == Winner::None || self.record.forfeit.is_some() {
+            return Vec::new();
+        }
+        let move_count = self.record.move_history.len();
+        let marker = match Self::get_move_turn(move_count.saturating_sub(1)) {
+            GameState::Player1Turn => self.player1.get_marker(),
+            _ => self.player2.get_marker(),
+        };
+        match self.record.move_history.last() {
+            Some(&last_move) => match self.board.check_board_state(last_move, marker) {
+                BoardState::Win(set_type) => self.board.get_set_coords(set_type),
+                _ => Vec::new(),
+            },
+            None => Vec::new(),
+        }
+    }
+
+    // OptimalAI's seed only breaks ties between equally good corners or
+    // edges, so a fixed one keeps the hint from changing each time it's
+    // asked for.
+    fn get_hint(&self) -> Option<(CellCoord, MoveReason)> {
+        let marker = self.get_turn_marker()?;
+        let mut optimal_ai = OptimalAI::with_seed("Hint", marker, 0);
+        match self.variant {
+            Variant::Misere => Some(optimal_ai.get_misere_move_with_reason(&self.board)),
+            // OptimalAI's rules are for a single layer where any empty cell
+            // can be played, so it has nothing useful to say about a cube or
+            // a board with gravity.
+            Variant::Qubic | Variant::Gravity => None,
+            _ => Some(optimal_ai.get_move_with_reason(&self.board)),
+        }
+    }
+
+    fn get_seats(&self) -> [SeatInfo<'_>; 2] {
+        [
+            SeatInfo {
+                name: self.player1.get_name(),
+                kind: self.player1.get_kind(),
+                marker: self.player1.get_marker(),
+            },
+            SeatInfo {
+                name: self.player2.get_name(),
+                kind: self.player2.get_kind(),
+                marker: self.player2.get_marker(),
+            },
+        ]
+    }
+
     fn reset(&mut self) {
-        self.player1 = P1::new(self.player1.get_name(), Marker::X);
-        self.player2 = P2::new(self.player2.get_name(), Marker::O);
-        self.board = Board::new();
-        self.record = Record::new(self.player1.get_name(), self.player2.get_name());
+        let (player1_seed, player2_seed) = self.record.next_round();
+        self.player1 = self.player1.reseeded(player1_seed);
+        self.player2 = self.player2.reseeded(player2_seed);
+        self.board = match self.variant {
+            Variant::Gravity => Board::with_gravity(
+                self.board.get_width(),
+                self.board.get_height(),
+                self.board.get_win_length(),
+            ),
+            _ => Board::with_layers(
+                self.board.get_layer_width(),
+                self.board.get_height(),
+                self.board.get_layers(),
+                self.board.get_win_length(),
+            ),
+        };
         self.state = GameState::Player1Turn;
     }
+
+    fn to_saved_game(&self) -> SavedGame {
+        SavedGame {
+            date: save::get_today(),
+            variant: self.variant,
+            size: self.board.get_size(),
+            win_length: self.board.get_win_length(),
+            player1: String::from(self.record.player1),
+            player1_kind: String::from(self.player1.get_kind()),
+            player2: String::from(self.record.player2),
+            player2_kind: String::from(self.player2.get_kind()),
+            result: match self.state {
+                GameState::Done => Some(self.record.winner),
+                _ => None,
+            },
+            moves: self.record.move_history.clone(),
+            markers: Vec::new(),
+            seed: Some(self.record.seed),
+        }
+    }
+
+    // Plays a saved list of moves on top of the current position, for either
+    // seat, so a loaded game picks up right where it was saved. Stops at the
+    // first move that can't be played.
+    fn load_moves(&mut self, moves: &[CellCoord]) -> Result<GameState, String> {
+        for (move_number, &player_move) in moves.iter().enumerate() {
+            let notation = save::cell_to_notation(player_move);
+            if self.state == GameState::Done {
+                return Err(format!(
+                    "Move {} ({}) comes after the game ended",
+                    move_number + 1,
+                    notation
+                ));
+            }
+            if self.board.validate_move(player_move) != Move::Valid {
+                return Err(format!(
+                    "Move {} ({}) isn't a legal move",
+                    move_number + 1,
+                    notation
+                ));
+            }
+            self.apply_move(player_move);
+        }
+        Ok(self.state)
+    }
 }
 
 impl<'a> Record<'a> {
-    fn new(player1: &'a str, player2: &'a str) -> Record<'a> {
+    pub fn new(player1: &'a str, player2: &'a str, seed: u64) -> Record<'a> {
         Record {
             player1,
             player2,
+            seed,
             winner: Winner::None,
+            forfeit: None,
             move_history: Vec::new(),
+            undone_moves: Vec::new(),
         }
     }
 
-    fn record_move(&mut self, player_move: CellCoord) {
+    // Starts over for another round, and returns the seats' seeds for it.
+    // The round's seed follows on from the last one, so each round plays
+    // differently, but any of them can be played again from its own seed.
+    pub fn next_round(&mut self) -> (u64, u64) {
+        *self = Record::new(self.player1, self.player2, self.seed.wrapping_add(1));
+        get_seat_seeds(self.seed)
+    }
+
+    // A new move starts a different line of play, so anything that was
+    // undone can't be redone anymore.
+    pub fn record_move(&mut self, player_move: CellCoord) {
         self.move_history.push(player_move);
+        self.undone_moves.clear();
     }
 
-    fn record_outcome(&mut self, winner: Winner) {
+    // A forfeit is taken back along with the move before it.
+    pub fn undo_move(&mut self) -> Option<CellCoord> {
+        self.forfeit = None;
+        let player_move = self.move_history.pop()?;
+        self.undone_moves.push(player_move);
+        Some(player_move)
+    }
+
+    pub fn redo_move(&mut self) -> Option<CellCoord> {
+        let player_move = self.undone_moves.pop()?;
+        self.move_history.push(player_move);
+        Some(player_move)
+    }
+
+    pub fn record_outcome(&mut self, winner: Winner) {
         self.winner = winner;
     }
 
@@ -172,3 +588,337 @@ impl<'a> Record<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::ai_minimax::MinimaxAI;
+    use crate::player::ai_random::RandomAI;
+    use crate::player::human::Human;
+
+    #[test]
+    fn hotseat_players_alternate() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        assert!(game.is_human_turn());
+        assert_eq!(game.get_turn_marker(), Some(Marker::X));
+        assert_eq!(
+            game.get_seats()[1],
+            SeatInfo {
+                name: "Player 2",
+                kind: "Human",
+                marker: Marker::O
+            }
+        );
+
+        let state = game.make_human_move(CellCoord::new(0, 0));
+        assert_eq!(state, GameState::Player2Turn);
+        assert!(game.is_human_turn());
+        assert_eq!(game.get_turn_marker(), Some(Marker::O));
+        // The hint is for O, who should answer a corner in the center.
+        assert_eq!(
+            game.get_hint(),
+            Some((CellCoord::new(1, 1), MoveReason::Center))
+        );
+        // run() waits for the second human instead of moving for them.
+        assert_eq!(game.run(), GameState::Player2Turn);
+
+        game.make_human_move(CellCoord::new(1, 1));
+        assert_eq!(game.get_cellstate_char(0), 'X');
+        assert_eq!(game.get_cellstate_char(4), 'O');
+        assert_eq!(game.get_game_state(), GameState::Player1Turn);
+    }
+
+    #[test]
+    fn ignores_move_to_used_cell() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        game.make_human_move(CellCoord::new(0, 0));
+        let state = game.make_human_move(CellCoord::new(0, 0));
+        assert_eq!(state, GameState::Player2Turn);
+    }
+
+    #[test]
+    fn computer_opens_as_player1() {
+        let mut game = TicTacToe::new(
+            MinimaxAI::new("Minimax", Marker::X),
+            Human::new("Human", Marker::O),
+        );
+        assert!(!game.is_human_turn());
+        assert_eq!(
+            game.make_human_move(CellCoord::new(1, 1)),
+            GameState::Player1Turn
+        );
+
+        assert_eq!(game.run(), GameState::Player2Turn);
+        assert!(game.is_human_turn());
+        assert_eq!(game.get_turn_marker(), Some(Marker::O));
+        let placed = (0..9)
+            .filter(|&i| game.get_cellstate_char(i) == 'X')
+            .count();
+        assert_eq!(placed, 1);
+    }
+
+    #[test]
+    fn reset_keeps_seats() {
+        let mut game = TicTacToe::new(
+            MinimaxAI::new("Minimax", Marker::X),
+            Human::new("Human", Marker::O),
+        );
+        game.run();
+        game.reset();
+        assert_eq!(game.get_game_state(), GameState::Player1Turn);
+        assert!(!game.is_human_turn());
+        assert_eq!(game.get_turn_marker(), Some(Marker::X));
+    }
+
+    #[test]
+    fn hotseat_undo_takes_back_one_move() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        game.make_human_move(CellCoord::new(0, 0));
+        game.make_human_move(CellCoord::new(1, 1));
+
+        assert_eq!(game.undo(), GameState::Player2Turn);
+        assert_eq!(game.get_cellstate_char(4), '_');
+        assert_eq!(game.get_cellstate_char(0), 'X');
+
+        assert_eq!(game.redo(), GameState::Player1Turn);
+        assert_eq!(game.get_cellstate_char(4), 'O');
+        // Nothing left to redo.
+        assert_eq!(game.redo(), GameState::Player1Turn);
+    }
+
+    #[test]
+    fn undo_takes_back_computer_reply() {
+        let mut game = TicTacToe::new(
+            Human::new("Human", Marker::X),
+            MinimaxAI::new("Minimax", Marker::O),
+        );
+        game.make_human_move(CellCoord::new(0, 0));
+        game.run();
+        let reply = (0..9).find(|&i| game.get_cellstate_char(i) == 'O').unwrap();
+
+        assert_eq!(game.undo(), GameState::Player1Turn);
+        assert!((0..9).all(|i| game.get_cellstate_char(i) == '_'));
+
+        assert_eq!(game.redo(), GameState::Player1Turn);
+        assert_eq!(game.get_cellstate_char(0), 'X');
+        assert_eq!(game.get_cellstate_char(reply), 'O');
+    }
+
+    #[test]
+    fn undo_reopens_finished_game() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        for (row, column) in [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2)] {
+            game.make_human_move(CellCoord::new(row, column));
+        }
+        assert_eq!(game.get_game_state(), GameState::Done);
+        let top_row = vec![
+            CellCoord::new(0, 0),
+            CellCoord::new(0, 1),
+            CellCoord::new(0, 2),
+        ];
+        assert_eq!(game.get_winning_line(), top_row);
+
+        assert_eq!(game.undo(), GameState::Player1Turn);
+        assert!(matches!(game.get_winner(), Winner::None));
+        assert!(game.get_winning_line().is_empty());
+        assert_eq!(game.redo(), GameState::Done);
+        assert!(matches!(game.get_winner(), Winner::Player1));
+        assert_eq!(game.get_winning_line(), top_row);
+    }
+
+    #[test]
+    fn new_move_clears_redo() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        game.make_human_move(CellCoord::new(0, 0));
+        game.undo();
+        game.make_human_move(CellCoord::new(2, 2));
+        assert_eq!(game.redo(), GameState::Player2Turn);
+        assert_eq!(game.get_cellstate_char(0), '_');
+    }
+
+    #[test]
+    fn loads_saved_game_mid_game() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            MinimaxAI::new("Minimax", Marker::O),
+        );
+        game.make_human_move(CellCoord::new(1, 1));
+        game.run();
+        let saved_game = game.to_saved_game();
+        assert_eq!(saved_game.result, None);
+        assert_eq!(saved_game.player2_kind, "Minimax");
+
+        let mut loaded = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            MinimaxAI::new("Minimax", Marker::O),
+        );
+        assert_eq!(
+            loaded.load_moves(&saved_game.moves),
+            Ok(GameState::Player1Turn)
+        );
+        assert_eq!(loaded.to_saved_game().moves, saved_game.moves);
+        // The loaded game carries on, and can be undone past the load.
+        assert_eq!(loaded.undo(), GameState::Player1Turn);
+        assert_eq!(loaded.get_cellstate_char(4), '_');
+    }
+
+    #[test]
+    fn loads_finished_game() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        let moves = [
+            CellCoord::new(0, 0),
+            CellCoord::new(1, 0),
+            CellCoord::new(0, 1),
+            CellCoord::new(1, 1),
+            CellCoord::new(0, 2),
+        ];
+        assert_eq!(game.load_moves(&moves), Ok(GameState::Done));
+        assert_eq!(game.get_winner(), Winner::Player1);
+        assert_eq!(game.to_saved_game().result, Some(Winner::Player1));
+    }
+
+    #[test]
+    fn completing_a_misere_line_loses() {
+        let mut game = TicTacToe::misere(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+            Board::new(),
+        );
+        let moves = [
+            CellCoord::new(0, 0),
+            CellCoord::new(1, 0),
+            CellCoord::new(0, 1),
+            CellCoord::new(1, 1),
+        ];
+        assert_eq!(game.load_moves(&moves), Ok(GameState::Player1Turn));
+        // OptimalAI's misère rules keep X out of the top row.
+        let (hint, _) = game.get_hint().unwrap();
+        assert_ne!(hint, CellCoord::new(0, 2));
+
+        game.make_human_move(CellCoord::new(0, 2));
+        assert_eq!(game.get_winner(), Winner::Player2);
+        assert_eq!(game.get_winning_line().len(), 3);
+        let saved_game = game.to_saved_game();
+        assert_eq!(saved_game.variant, Variant::Misere);
+        assert_eq!(saved_game.result, Some(Winner::Player2));
+
+        game.reset();
+        assert_eq!(game.get_variant(), Variant::Misere);
+    }
+
+    #[test]
+    fn plays_qubic_on_a_cube() {
+        let mut game = TicTacToe::with_board(
+            RandomAI::with_seed("Random 1", Marker::X, 1),
+            RandomAI::with_seed("Random 2", Marker::O, 2),
+            Board::cube(4),
+        );
+        assert_eq!(game.get_variant(), Variant::Qubic);
+        assert_eq!(game.get_board_size(), BoardSize::new(4, 4, 4));
+        while game.run() != GameState::Done {}
+        if game.get_winner() != Winner::None {
+            assert_eq!(game.get_winning_line().len(), 4);
+        }
+        let saved_game = game.to_saved_game();
+        assert_eq!(saved_game.size, BoardSize::new(4, 4, 4));
+
+        game.reset();
+        assert_eq!(game.get_board_size(), BoardSize::new(4, 4, 4));
+        assert_eq!(game.get_move_history().len(), 0);
+    }
+
+    #[test]
+    fn plays_gravity_from_the_bottom_up() {
+        let mut game = TicTacToe::with_board(
+            RandomAI::with_seed("Random", Marker::X, 1),
+            MinimaxAI::new("Minimax", Marker::O),
+            Board::with_gravity(7, 6, 4),
+        );
+        assert_eq!(game.get_variant(), Variant::Gravity);
+        assert_eq!(game.get_hint(), None);
+        while game.run() != GameState::Done {}
+        assert_eq!(game.get_winner(), Winner::Player2);
+
+        // Every move landed on the bottom row or on top of another marker.
+        let moves = game.get_move_history().to_vec();
+        for (index, cell) in moves.iter().enumerate() {
+            let below = CellCoord::new(cell.row + 1, cell.column);
+            assert!(cell.row == 5 || moves[..index].contains(&below));
+        }
+
+        game.reset();
+        assert_eq!(game.validate_move(CellCoord::new(0, 0)), Move::Floating);
+        assert_eq!(game.get_drop_cell(0), Some(CellCoord::new(5, 0)));
+    }
+
+    #[test]
+    fn rejects_illegal_saved_moves() {
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        let moves = [CellCoord::new(0, 0), CellCoord::new(0, 0)];
+        assert!(game.load_moves(&moves).is_err());
+
+        let mut game = TicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        assert!(game.load_moves(&[CellCoord::new(3, 0)]).is_err());
+    }
+
+    #[test]
+    fn same_seeds_play_same_game() {
+        let play = |seed: u64| {
+            let mut game = TicTacToe::new(
+                RandomAI::with_seed("Random 1", Marker::X, seed),
+                RandomAI::with_seed("Random 2", Marker::O, seed + 1),
+            );
+            while game.run() != GameState::Done {}
+            game.get_move_history().to_vec()
+        };
+        assert_eq!(play(7), play(7));
+        assert!((0..10).any(|seed| play(seed) != play(7)));
+    }
+
+    #[test]
+    fn playing_again_follows_from_the_seed() {
+        let new_game = |seed: u64| {
+            let (player1_seed, player2_seed) = get_seat_seeds(seed);
+            TicTacToe::new(
+                RandomAI::with_seed("Random 1", Marker::X, player1_seed),
+                RandomAI::with_seed("Random 2", Marker::O, player2_seed),
+            )
+            .seeded(seed)
+        };
+        let play = |game: &mut dyn Game| {
+            while game.run() != GameState::Done {}
+            game.to_saved_game()
+        };
+
+        let mut game = new_game(7);
+        while game.run() != GameState::Done {}
+        game.reset();
+        let second_round = play(&mut game);
+        assert_eq!(second_round.seed, Some(8));
+        assert_eq!(second_round.moves, play(&mut new_game(8)).moves);
+    }
+}
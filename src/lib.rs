@@ -3,27 +3,55 @@
 // has a corresponding <module_name>.rs file in the same directory
 // as this lib.rs file.
 mod board;
+mod client;
 mod common;
+mod engine;
 mod game;
 mod player;
+mod replay;
+mod save;
+mod server;
+mod simulate;
+mod stats;
+mod tournament;
+mod ultimate;
+mod wild;
 
-use std::{fmt, io};
+use std::{
+    fmt, io,
+    time::{Duration, Instant},
+};
 
-use common::{CellCoord, Marker};
-use game::{Game, GameState, TicTacToe, Winner};
+use board::Board;
+use client::{BoardStatus, RemoteBoard, ServerConnection, ServerMessage};
+use common::{BoardSize, CellCoord, Marker};
+use game::{Game, GameState, SeatInfo, TicTacToe, Variant, Winner};
+use player::ai_optimal::MoveReason;
 use player::*;
+use replay::Replay;
+use stats::{GameRecord, GameResult, RecordSummary};
+use ultimate::{UltimateBoard, UltimateTicTacToe};
+use wild::WildTicTacToe;
+
+pub use engine::run_engine;
+pub use server::run_server;
+pub use simulate::run_simulation;
+pub use tournament::run_tournament;
 
 use tui::{
     backend::{Backend, CrosstermBackend},
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    text::Span,
-    widgets::{Block, Borders, List, ListItem, ListState},
+    text::{Span, Spans},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
     Frame, Terminal,
 };
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, MouseButton, MouseEvent,
+        MouseEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -36,16 +64,45 @@ struct MenuList<T> {
 #[derive(Debug, PartialEq, Copy, Clone)]
 enum MainMenuEntry {
     Play,
+    Watch,
+    Online,
+    Load,
+    Replay,
+    Stats,
     Exit,
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
 enum PlayerTypeEntry {
-    #[allow(dead_code)]
     Human,
     RandomComp,
     BasicComp,
+    ForkingComp,
     OptimalComp,
+    MinimaxComp,
+    // A program speaking the engine protocol, started with `command`. The
+    // name is what it's called in results.
+    External {
+        name: &'static str,
+        command: &'static str,
+    },
+}
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum SideEntry {
+    PlayX,
+    PlayO,
+    Random,
+}
+
+// The online lobby lists games to join along with ways to start new ones.
+// The side for a new game is picked after its opponent.
+#[derive(Debug, PartialEq, Copy, Clone)]
+enum LobbyEntry {
+    Create(PlayerTypeEntry),
+    Join(u32, Marker),
+    Refresh,
+    Back,
 }
 
 #[derive(Debug, PartialEq, Copy, Clone)]
@@ -54,21 +111,73 @@ enum EndMenuEntry {
     Exit,
 }
 
+// What the user did with a menu: picked one of its items, or backed out.
+#[derive(Debug, PartialEq)]
+enum MenuChoice<T> {
+    Picked(T),
+    Back,
+}
+
 // We need to implement display here so we can convert the enum
 // into a string.
 impl fmt::Display for MainMenuEntry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?}", self)
+        match self {
+            MainMenuEntry::Watch => write!(f, "Watch Computers"),
+            MainMenuEntry::Online => write!(f, "Play Online"),
+            MainMenuEntry::Load => write!(f, "Load Game"),
+            MainMenuEntry::Replay => write!(f, "Replay Game"),
+            MainMenuEntry::Stats => write!(f, "Statistics"),
+            _ => write!(f, "{:?}", self),
+        }
     }
 }
 
 impl fmt::Display for PlayerTypeEntry {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            PlayerTypeEntry::Human => write!(f, "Human"),
+            PlayerTypeEntry::Human => write!(f, "Human (Hotseat)"),
             PlayerTypeEntry::RandomComp => write!(f, "Random Computer"),
             PlayerTypeEntry::BasicComp => write!(f, "Basic Computer"),
+            PlayerTypeEntry::ForkingComp => write!(f, "Forking Computer"),
             PlayerTypeEntry::OptimalComp => write!(f, "Optimal Computer"),
+            PlayerTypeEntry::MinimaxComp => write!(f, "Minimax Computer"),
+            PlayerTypeEntry::External { name, .. } => write!(f, "Engine ({})", name),
+        }
+    }
+}
+
+impl fmt::Display for Variant {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Variant::Classic => write!(f, "Classic Tic-Tac-Toe"),
+            Variant::Misere => write!(f, "Misère Tic-Tac-Toe (a line loses)"),
+            Variant::Qubic => write!(f, "Qubic (4x4x4 cube)"),
+            Variant::Gravity => write!(f, "Gravity (drop into 7x6)"),
+            Variant::Ultimate => write!(f, "Ultimate Tic-Tac-Toe (3x3 of boards)"),
+            Variant::Wild => write!(f, "Wild Tic-Tac-Toe (place X or O)"),
+        }
+    }
+}
+
+impl fmt::Display for SideEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SideEntry::PlayX => write!(f, "Play X (move first)"),
+            SideEntry::PlayO => write!(f, "Play O (move second)"),
+            SideEntry::Random => write!(f, "Random Side"),
+        }
+    }
+}
+
+impl fmt::Display for LobbyEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LobbyEntry::Create(PlayerTypeEntry::Human) => write!(f, "New game vs a person"),
+            LobbyEntry::Create(opponent) => write!(f, "New game vs {}", opponent),
+            LobbyEntry::Join(id, marker) => write!(f, "Join game {} (play {:?})", id, marker),
+            LobbyEntry::Refresh => write!(f, "Refresh"),
+            LobbyEntry::Back => write!(f, "Back"),
         }
     }
 }
@@ -82,6 +191,48 @@ impl fmt::Display for EndMenuEntry {
     }
 }
 
+impl SideEntry {
+    fn get_marker(self) -> Marker {
+        match self {
+            SideEntry::PlayX => Marker::X,
+            SideEntry::PlayO => Marker::O,
+            SideEntry::Random if rand::random() => Marker::X,
+            SideEntry::Random => Marker::O,
+        }
+    }
+}
+
+impl PlayerTypeEntry {
+    fn get_name(&self) -> &'static str {
+        match self {
+            PlayerTypeEntry::Human => "Human",
+            PlayerTypeEntry::RandomComp => "Random",
+            PlayerTypeEntry::BasicComp => "Basic",
+            PlayerTypeEntry::ForkingComp => "Forking",
+            PlayerTypeEntry::OptimalComp => "Optimal",
+            PlayerTypeEntry::MinimaxComp => "Minimax",
+            PlayerTypeEntry::External { name, .. } => name,
+        }
+    }
+
+    // Saved games and the simulate command name players by their kind, which
+    // is the same as the default names above. Case doesn't matter, so
+    // "optimal" works on the command line.
+    fn from_kind(kind: &str) -> Option<PlayerTypeEntry> {
+        [
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::RandomComp,
+            PlayerTypeEntry::BasicComp,
+            PlayerTypeEntry::ForkingComp,
+            PlayerTypeEntry::OptimalComp,
+            PlayerTypeEntry::MinimaxComp,
+        ]
+        .iter()
+        .copied()
+        .find(|entry| entry.get_name().eq_ignore_ascii_case(kind))
+    }
+}
+
 impl<T> MenuList<T> {
     fn with_items(items: Vec<T>) -> MenuList<T> {
         let mut list = MenuList {
@@ -118,28 +269,234 @@ impl<T> MenuList<T> {
         };
         self.state.select(Some(i));
     }
+
+    // Selects the item drawn at (column, row), for a menu drawn as a list
+    // with borders in `area`. Returns whether there was an item there.
+    fn select_at(&mut self, area: Rect, column: u16, row: u16) -> bool {
+        match get_list_item_at(area, self.items.len(), column, row) {
+            Some(i) => {
+                self.state.select(Some(i));
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl<T: Clone> MenuList<T> {
+    fn get_selected(&self) -> Option<T> {
+        self.state.selected().map(|i| self.items[i].clone())
+    }
+
+    // Every menu works the same way, wherever it's drawn: Up and Down move
+    // the selection, Enter or a click in `area` picks an item, and Esc backs
+    // out. Anything else is left to the caller.
+    fn handle_event(&mut self, event: &Event, area: Rect) -> Option<MenuChoice<T>> {
+        if let Some((column, row)) = get_click(event) {
+            if self.select_at(area, column, row) {
+                return self.get_selected().map(MenuChoice::Picked);
+            }
+        }
+        if let Event::Key(key) = event {
+            match key.code {
+                KeyCode::Down => self.next(),
+                KeyCode::Up => self.previous(),
+                KeyCode::Esc => return Some(MenuChoice::Back),
+                KeyCode::Enter => return self.get_selected().map(MenuChoice::Picked),
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+// Returns where the left mouse button was pressed, if that's what `event`
+// is. A click picks things the same way Enter does.
+fn get_click(event: &Event) -> Option<(u16, u16)> {
+    match event {
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            ..
+        }) => Some((*column, *row)),
+        _ => None,
+    }
+}
+
+fn is_inside(area: Rect, column: u16, row: u16) -> bool {
+    column >= area.left() && column < area.right() && row >= area.top() && row < area.bottom()
+}
+
+// Lists are drawn inside a border, one item per line. Our lists always fit
+// in their boxes, so they never scroll and the first item is on the first
+// line.
+fn get_list_item_at(area: Rect, item_count: usize, column: u16, row: u16) -> Option<usize> {
+    let inner = Rect::new(
+        area.x + 1,
+        area.y + 1,
+        area.width.saturating_sub(2),
+        area.height.saturating_sub(2),
+    );
+    if !is_inside(inner, column, row) {
+        return None;
+    }
+    let index = (row - inner.y) as usize;
+    match index < item_count {
+        true => Some(index),
+        false => None,
+    }
+}
+
+// The delays a spectator can step through with the speed keys.
+const MOVE_DELAYS: [Duration; 6] = [
+    Duration::from_millis(0),
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(4),
+];
+
+// Computers decide on a move almost instantly. MovePacing holds them back so
+// a person can follow the game, and lets a spectator pause and step through it.
+struct MovePacing {
+    delay_index: usize,
+    paused: bool,
+    step_requested: bool,
+    turn_started: Instant,
+}
+
+impl MovePacing {
+    fn new() -> MovePacing {
+        MovePacing {
+            delay_index: 3,
+            paused: false,
+            step_requested: false,
+            turn_started: Instant::now(),
+        }
+    }
+
+    fn get_delay(&self) -> Duration {
+        MOVE_DELAYS[self.delay_index]
+    }
+
+    // Called whenever a new turn begins, so the computer's delay is counted
+    // from when the previous move landed.
+    fn start_turn(&mut self) {
+        self.turn_started = Instant::now();
+        self.step_requested = false;
+    }
+
+    // How long to wait for input before checking whether the computer
+    // can move. While paused, we just keep listening for keys.
+    fn get_wait_time(&self) -> Duration {
+        if self.step_requested {
+            Duration::from_millis(0)
+        } else if self.paused {
+            Duration::from_millis(250)
+        } else {
+            self.get_delay().saturating_sub(self.turn_started.elapsed())
+        }
+    }
+
+    fn is_computer_ready(&self) -> bool {
+        self.step_requested || (!self.paused && self.turn_started.elapsed() >= self.get_delay())
+    }
+
+    fn handle_key(&mut self, key: &KeyCode) {
+        match key {
+            KeyCode::Char(' ') => self.paused = !self.paused,
+            KeyCode::Char('n') if self.paused => self.step_requested = true,
+            KeyCode::Char('+') if self.delay_index > 0 => self.delay_index -= 1,
+            KeyCode::Char('-') if self.delay_index < MOVE_DELAYS.len() - 1 => self.delay_index += 1,
+            _ => {}
+        }
+    }
 }
 
 struct App {
     main_menu: MenuList<MainMenuEntry>,
+    variant_select_menu: MenuList<Variant>,
     player_select_menu: MenuList<PlayerTypeEntry>,
+    side_select_menu: MenuList<SideEntry>,
+    computer_select_menu: MenuList<PlayerTypeEntry>,
     end_menu: MenuList<EndMenuEntry>,
-    selected_cell: u8,
+    selected_cell: usize,
+    variant: Variant,
+    opponent: PlayerTypeEntry,
+    side: SideEntry,
+    // When spectating, both seats are computers picked from this pair and
+    // `opponent` and `side` are ignored.
+    spectating: bool,
+    spectator_seats: (PlayerTypeEntry, PlayerTypeEntry),
+    pacing: MovePacing,
+    // A one line message for the bottom of the screen, like where a game
+    // was saved.
+    status_message: Option<String>,
+    // The last server we played online on.
+    server_address: String,
+    // The names people typed in. Against a computer the person is always
+    // `player_names[0]`, whichever side they're on. Two people at one
+    // terminal are X and O in turn.
+    player_names: [String; 2],
+    // Every name a game has been started with, leaked once each. See
+    // `get_player_name()`.
+    leaked_names: Vec<&'static str>,
     game: Box<dyn Game>,
+    // The last hint asked for, along with the moves that had been played
+    // then. It's only shown while the game is still at that position.
+    hint: Option<(Vec<CellCoord>, CellCoord, MoveReason)>,
+    // Whether the current game's result has gone into the statistics, so
+    // undoing and redoing the last move doesn't count it twice.
+    game_recorded: bool,
 }
 
 impl App {
     fn new() -> App {
         App {
-            main_menu: MenuList::with_items(vec![MainMenuEntry::Play, MainMenuEntry::Exit]),
-            player_select_menu: MenuList::with_items(vec![
-                //PlayerTypeEntry::Human,
+            main_menu: MenuList::with_items(vec![
+                MainMenuEntry::Play,
+                MainMenuEntry::Watch,
+                MainMenuEntry::Online,
+                MainMenuEntry::Load,
+                MainMenuEntry::Replay,
+                MainMenuEntry::Stats,
+                MainMenuEntry::Exit,
+            ]),
+            variant_select_menu: MenuList::with_items(vec![
+                Variant::Classic,
+                Variant::Misere,
+                Variant::Qubic,
+                Variant::Gravity,
+                Variant::Ultimate,
+                Variant::Wild,
+            ]),
+            player_select_menu: MenuList::with_items(get_opponents(Variant::Classic)),
+            side_select_menu: MenuList::with_items(vec![
+                SideEntry::PlayX,
+                SideEntry::PlayO,
+                SideEntry::Random,
+            ]),
+            computer_select_menu: MenuList::with_items(vec![
                 PlayerTypeEntry::RandomComp,
                 PlayerTypeEntry::BasicComp,
+                PlayerTypeEntry::ForkingComp,
                 PlayerTypeEntry::OptimalComp,
+                PlayerTypeEntry::MinimaxComp,
             ]),
             end_menu: MenuList::with_items(vec![EndMenuEntry::PlayAgain, EndMenuEntry::Exit]),
             selected_cell: 0,
+            variant: Variant::Classic,
+            opponent: PlayerTypeEntry::OptimalComp,
+            side: SideEntry::PlayX,
+            spectating: false,
+            spectator_seats: (PlayerTypeEntry::BasicComp, PlayerTypeEntry::OptimalComp),
+            pacing: MovePacing::new(),
+            status_message: None,
+            server_address: String::from("127.0.0.1:7878"),
+            player_names: [String::from("Player 1"), String::from("Player 2")],
+            leaked_names: Vec::new(),
             // We don't want to create th Game object when we start the App, because the user
             // hasn't selected their opponent yet. We can't have an uninitialized Box, so
             // we should use Option here.
@@ -150,73 +507,638 @@ impl App {
                 human::Human::new("Human", Marker::X),
                 ai_optimal::OptimalAI::new("Optimal", Marker::O),
             )),
+            hint: None,
+            game_recorded: false,
         }
     }
 
-    fn handle_main_menu_enter(&self) -> MainMenuEntry {
-        match self.main_menu.state.selected() {
-            Some(i) => self.main_menu.items[i],
-            None => MainMenuEntry::Exit,
+    // Each kind of game has its own list of opponents, so picking a
+    // different one swaps the opponent menu's items.
+    fn set_variant(&mut self, variant: Variant) {
+        let opponents = get_opponents(variant);
+        if self.player_select_menu.items != opponents {
+            self.player_select_menu = MenuList::with_items(opponents);
         }
+        self.variant = variant;
     }
 
-    fn handle_player_select_menu_enter(&mut self) {
-        match self.player_select_menu.state.selected() {
-            Some(i) => match self.player_select_menu.items[i] {
-                PlayerTypeEntry::Human => {
-                    todo!("Human vs Human is not yet supported");
-                }
-                PlayerTypeEntry::RandomComp => {
-                    self.game = Box::new(TicTacToe::new(
-                        human::Human::new("Human", Marker::X),
-                        ai_random::RandomAI::new("Random", Marker::O),
-                    ));
-                }
-                PlayerTypeEntry::BasicComp => {
-                    self.game = Box::new(TicTacToe::new(
-                        human::Human::new("Human", Marker::X),
-                        ai_basic::BasicAI::new("Basic", Marker::O),
-                    ));
-                }
-                PlayerTypeEntry::OptimalComp => {
-                    self.game = Box::new(TicTacToe::new(
-                        human::Human::new("Human", Marker::X),
-                        ai_optimal::OptimalAI::new("Optimal", Marker::O),
-                    ));
-                }
-            },
-            None => {}
-        };
+    fn set_player_name(&mut self, index: usize, name: &str) {
+        self.player_names[index] = name.to_string();
     }
 
-    fn handle_end_menu_enter(&self) -> EndMenuEntry {
-        match self.end_menu.state.selected() {
-            Some(i) => self.end_menu.items[i],
-            None => EndMenuEntry::Exit,
+    // Players borrow their names for as long as the game lives, and the game
+    // lives in a `Box<dyn Game>` that can't borrow from `App`. So names are
+    // leaked to get a 'static one. I keep the ones I've leaked, so playing
+    // again, or typing in a name that's been used before, doesn't leak it
+    // again.
+    fn get_player_name(&mut self, index: usize) -> &'static str {
+        let name = &self.player_names[index];
+        match self.leaked_names.iter().find(|&&leaked| leaked == name) {
+            Some(&leaked) => leaked,
+            None => {
+                let leaked: &'static str = Box::leak(name.clone().into_boxed_str());
+                self.leaked_names.push(leaked);
+                leaked
+            }
         }
     }
 
-    fn update_selected_cell(&mut self, key: &KeyCode) {
-        match key {
-            KeyCode::Right => {
-                if self.selected_cell != 2 && self.selected_cell != 5 && self.selected_cell != 8 {
-                    self.selected_cell += 1;
+    fn start_game(&mut self) {
+        let mut names = [None, None];
+        let seats = if self.spectating {
+            self.spectator_seats
+        } else {
+            let human_marker = self.side.get_marker();
+            // X always moves first, so the marker the human picked decides
+            // whether they sit in the player 1 or player 2 seat.
+            match (self.opponent, human_marker) {
+                (PlayerTypeEntry::Human, _) => {
+                    names = [Some(self.get_player_name(0)), Some(self.get_player_name(1))];
                 }
+                (_, Marker::X) => names[0] = Some(self.get_player_name(0)),
+                (_, Marker::O) => names[1] = Some(self.get_player_name(0)),
             }
-            KeyCode::Left => {
-                if self.selected_cell != 0 && self.selected_cell != 3 && self.selected_cell != 6 {
-                    self.selected_cell -= 1;
-                }
+            match human_marker {
+                Marker::X => (PlayerTypeEntry::Human, self.opponent),
+                Marker::O => (self.opponent, PlayerTypeEntry::Human),
+            }
+        };
+
+        self.game = match self.variant {
+            Variant::Classic => {
+                new_named_game(seats.0, seats.1, names, Board::new(), rand::random())
+            }
+            Variant::Misere => {
+                new_misere_game(seats.0, seats.1, names, Board::new(), rand::random())
+                    .expect("the opponent menu only offers players that know misère")
+            }
+            Variant::Qubic => new_qubic_game(seats.0, seats.1, names, rand::random())
+                .expect("the opponent menu only offers players that can play Qubic"),
+            Variant::Gravity => new_gravity_game(
+                seats.0,
+                seats.1,
+                names,
+                Board::with_gravity(GRAVITY_WIDTH, GRAVITY_HEIGHT, GRAVITY_WIN_LENGTH),
+                rand::random(),
+            )
+            .expect("the opponent menu only offers players that can play Gravity"),
+            Variant::Ultimate => new_ultimate_game(seats.0, seats.1, names, rand::random())
+                .expect("the opponent menu only offers players that know Ultimate"),
+            Variant::Wild => new_wild_game(seats.0, seats.1, names, rand::random())
+                .expect("the opponent menu only offers players that know Wild"),
+        };
+        self.game_recorded = false;
+        self.selected_cell = 0;
+        self.status_message = None;
+        self.pacing.start_turn();
+    }
+
+    fn save_game(&mut self) {
+        let message = match save::save_game(&self.game.to_saved_game()) {
+            Ok(path) => format!("Saved to {}", path.display()),
+            Err(error) => format!("Couldn't save the game: {}", error),
+        };
+        self.status_message = Some(message);
+    }
+
+    // Sets up the seats the saved game was played with, and replays its
+    // moves. Loading a game with a human in it puts us back in the seat
+    // they had, so playing again keeps the same side.
+    fn load_game(&mut self, file_name: &str) -> Result<(), String> {
+        let path = save::get_save_dir().join(file_name);
+        let saved_game = save::load_game(&path).map_err(|error| error.to_string())?;
+        let get_seat = |kind: &str| {
+            PlayerTypeEntry::from_kind(kind).ok_or_else(|| format!("Unknown player type: {}", kind))
+        };
+        let seats = (
+            get_seat(&saved_game.player1_kind)?,
+            get_seat(&saved_game.player2_kind)?,
+        );
+
+        // People keep the names they saved the game with.
+        let mut names = [None, None];
+        match seats {
+            (PlayerTypeEntry::Human, PlayerTypeEntry::Human) => {
+                self.set_player_name(0, &saved_game.player1);
+                self.set_player_name(1, &saved_game.player2);
+                names = [Some(self.get_player_name(0)), Some(self.get_player_name(1))];
+            }
+            (PlayerTypeEntry::Human, _) => {
+                self.set_player_name(0, &saved_game.player1);
+                names[0] = Some(self.get_player_name(0));
+            }
+            (_, PlayerTypeEntry::Human) => {
+                self.set_player_name(0, &saved_game.player2);
+                names[1] = Some(self.get_player_name(0));
             }
-            KeyCode::Down if self.selected_cell < 6 => self.selected_cell += 3,
-            KeyCode::Up if self.selected_cell > 2 => self.selected_cell -= 3,
             _ => {}
         }
+
+        // Playing on with the saved seed keeps the rounds after this one
+        // reproducible from the file.
+        let seed = saved_game.seed.unwrap_or_else(rand::random);
+        let mut game = match saved_game.variant {
+            Variant::Classic => {
+                new_named_game(seats.0, seats.1, names, saved_game.new_board(), seed)
+            }
+            Variant::Misere => {
+                new_misere_game(seats.0, seats.1, names, saved_game.new_board(), seed)?
+            }
+            Variant::Qubic => new_qubic_game(seats.0, seats.1, names, seed)?,
+            Variant::Gravity => {
+                new_gravity_game(seats.0, seats.1, names, saved_game.new_board(), seed)?
+            }
+            Variant::Ultimate => new_ultimate_game(seats.0, seats.1, names, seed)?,
+            Variant::Wild => new_wild_game(seats.0, seats.1, names, seed)?,
+        };
+        // A game that was already over when it was saved was recorded
+        // back when it finished.
+        let game_state = game.load_saved_game(&saved_game)?;
+        self.game_recorded = game_state == GameState::Done;
+
+        self.spectating = false;
+        self.set_variant(saved_game.variant);
+        match seats {
+            (PlayerTypeEntry::Human, opponent) => {
+                self.opponent = opponent;
+                self.side = SideEntry::PlayX;
+            }
+            (opponent, PlayerTypeEntry::Human) => {
+                self.opponent = opponent;
+                self.side = SideEntry::PlayO;
+            }
+            _ => {
+                self.spectating = true;
+                self.spectator_seats = seats;
+            }
+        }
+        self.game = game;
+        self.selected_cell = 0;
+        self.status_message = None;
+        self.pacing.start_turn();
+        Ok(())
+    }
+
+    // Adds a finished game between a person and a computer to the
+    // statistics. Games between two people or two computers aren't anyone's
+    // record, so they're left out.
+    fn record_finished_game(&mut self) {
+        if self.game_recorded || self.game.get_game_state() != GameState::Done {
+            return;
+        }
+        self.game_recorded = true;
+
+        let saved_game = self.game.to_saved_game();
+        let (marker, opponent) = match (
+            saved_game.player1_kind == "Human",
+            saved_game.player2_kind == "Human",
+        ) {
+            (true, false) => (Marker::X, saved_game.player2_kind),
+            (false, true) => (Marker::O, saved_game.player1_kind),
+            _ => return,
+        };
+        // Beating a computer at Ultimate is a different record from beating
+        // it at classic tic-tac-toe.
+        let opponent = match saved_game.variant {
+            Variant::Classic => opponent,
+            variant => format!("{} {}", variant.get_name(), opponent),
+        };
+        let result = match (self.game.get_winner(), marker) {
+            (Winner::None, _) => GameResult::Draw,
+            (Winner::Player1, Marker::X) | (Winner::Player2, Marker::O) => GameResult::Win,
+            _ => GameResult::Loss,
+        };
+        let record = GameRecord {
+            date: saved_game.date,
+            opponent,
+            marker,
+            result,
+            size: saved_game.size,
+            moves: saved_game.moves,
+        };
+        if let Err(error) = stats::record_game(&record) {
+            self.status_message = Some(format!("Couldn't record the game: {}", error));
+        }
+    }
+
+    fn show_hint(&mut self) {
+        if let Some((cell, reason)) = self.game.get_hint() {
+            self.hint = Some((self.game.get_move_history().to_vec(), cell, reason));
+        }
+    }
+
+    fn get_current_hint(&self) -> Option<(CellCoord, MoveReason)> {
+        match &self.hint {
+            Some((moves, cell, reason)) if moves == self.game.get_move_history() => {
+                Some((*cell, *reason))
+            }
+            _ => None,
+        }
+    }
+
+    fn update_selected_cell(&mut self, key: &KeyCode) {
+        let size = self.game.get_board_size();
+        let (columns, layer_width) = (size.get_columns(), size.width);
+        let column = self.selected_cell % columns;
+        self.selected_cell = match key {
+            // The arrows carry on from one layer of a cube into the next,
+            // and Tab jumps straight to the same cell in the next layer.
+            KeyCode::Tab if column + layer_width < columns => self.selected_cell + layer_width,
+            KeyCode::BackTab if column >= layer_width => self.selected_cell - layer_width,
+            // With gravity, the cursor only picks a column.
+            KeyCode::Up | KeyCode::Down if self.game.get_variant() == Variant::Gravity => {
+                self.selected_cell
+            }
+            _ => move_cursor(self.selected_cell, columns, size.height, key),
+        };
+    }
+
+    // With gravity, the marker lands in the lowest empty cell of the
+    // selected column, wherever in the column the cursor is. A full column
+    // has no such cell, so its top cell is picked, and the game turns the
+    // move down.
+    fn get_selected_coord(&self) -> CellCoord {
+        let columns = self.game.get_board_size().get_columns();
+        let column = self.selected_cell % columns;
+        match self.game.get_variant() {
+            Variant::Gravity => self
+                .game
+                .get_drop_cell(column)
+                .unwrap_or_else(|| CellCoord::new(0, column)),
+            _ => CellCoord::new(self.selected_cell / columns, column),
+        }
+    }
+
+    // Undo and redo work the same way no matter whose turn it is, and can
+    // even take a finished game back a move.
+    fn handle_history_key(&mut self, key: &KeyCode) -> Option<GameState> {
+        let game_state = match key {
+            KeyCode::Char('u') => self.game.undo(),
+            KeyCode::Char('r') => self.game.redo(),
+            _ => return None,
+        };
+        self.pacing.start_turn();
+        Some(game_state)
     }
 
     fn restart_game(&mut self) {
-        self.game.reset();
+        // A random side gets picked again for every game, which means the
+        // players may need to swap seats.
+        if self.side == SideEntry::Random && !self.spectating {
+            self.start_game();
+        } else {
+            self.game.reset();
+            self.game_recorded = false;
+            self.pacing.start_turn();
+        }
+    }
+}
+
+// Moves the cursor over a board's cells with the arrow keys, stopping at
+// the edges.
+fn move_cursor(cell: usize, width: usize, height: usize, key: &KeyCode) -> usize {
+    let column = cell % width;
+    match key {
+        KeyCode::Right if column < width - 1 => cell + 1,
+        KeyCode::Left if column > 0 => cell - 1,
+        KeyCode::Down if cell + width < width * height => cell + width,
+        KeyCode::Up if cell >= width => cell - width,
+        _ => cell,
+    }
+}
+
+// The opponents on offer for each kind of game. Only the computers that
+// implement MiserePlayer, UltimatePlayer or WildPlayer know how to play
+// those games.
+// Qubic's cube is too big for MinimaxAI to search, and OptimalAI's rules are
+// for a flat board, so it gets the computers that just look at lines.
+// Gravity's board is too big for OptimalAI's rules too, but MinimaxAI
+// searches it a few moves deep.
+fn get_opponents(variant: Variant) -> Vec<PlayerTypeEntry> {
+    match variant {
+        Variant::Classic => vec![
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::RandomComp,
+            PlayerTypeEntry::BasicComp,
+            PlayerTypeEntry::ForkingComp,
+            PlayerTypeEntry::OptimalComp,
+            PlayerTypeEntry::MinimaxComp,
+        ],
+        Variant::Misere => vec![
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::RandomComp,
+            PlayerTypeEntry::BasicComp,
+            PlayerTypeEntry::OptimalComp,
+            PlayerTypeEntry::MinimaxComp,
+        ],
+        Variant::Qubic => vec![
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::RandomComp,
+            PlayerTypeEntry::BasicComp,
+            PlayerTypeEntry::ForkingComp,
+        ],
+        Variant::Gravity => vec![
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::RandomComp,
+            PlayerTypeEntry::BasicComp,
+            PlayerTypeEntry::ForkingComp,
+            PlayerTypeEntry::MinimaxComp,
+        ],
+        Variant::Ultimate => vec![
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::RandomComp,
+            PlayerTypeEntry::MinimaxComp,
+        ],
+        Variant::Wild => vec![
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::RandomComp,
+            PlayerTypeEntry::BasicComp,
+            PlayerTypeEntry::MinimaxComp,
+        ],
+    }
+}
+
+// Seats without a name of their own are named after their kind, except
+// that two humans at one terminal need names that tell them apart.
+fn get_seat_names(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    names: [Option<&'static str>; 2],
+) -> [&'static str; 2] {
+    let hotseat = (player1, player2) == (PlayerTypeEntry::Human, PlayerTypeEntry::Human);
+    let get_name = |name: Option<&'static str>, player: PlayerTypeEntry, hotseat_name| match name {
+        Some(name) => name,
+        None if hotseat => hotseat_name,
+        None => player.get_name(),
+    };
+    [
+        get_name(names[0], player1, "Player 1"),
+        get_name(names[1], player2, "Player 2"),
+    ]
+}
+
+// Rust needs to know a player's concrete type to build a game with it, so
+// every kind of game matches on its seats' PlayerTypeEntry values. This does
+// that for one seat: it finds the entry in `kinds`, makes the player with the
+// function listed for it, and goes on to `then` with the player bound to
+// `player`. Giving a variant means entries that aren't in `kinds` can't play
+// it, and are an error.
+macro_rules! seat {
+    (
+        $entry:expr, $name:expr, $marker:expr, $seed:expr,
+        [$($kind:pat => $make:expr),+ $(,)?],
+        |$player:ident| $then:expr
+        $(, or $variant:expr)?
+    ) => {
+        match $entry {
+            $($kind => {
+                let $player = $make($name, $marker, $seed);
+                $then
+            })+
+            $(other => Err(format!(
+                "{} can't play {}",
+                other.get_name(),
+                $variant.get_name()
+            )),)?
+        }
+    };
+}
+
+// Fills both seats with `seat!`, player 1 as X and player 2 as O, and builds
+// the game from them with `game`. Each player's seed is drawn from the game's
+// `seed`, so a single number reproduces every random choice the computers
+// make.
+macro_rules! seats {
+    (
+        $player1:expr, $player2:expr, $names:expr, $seed:expr, $kinds:tt,
+        |$first:ident, $second:ident| $game:expr
+        $(, or $variant:expr)?
+    ) => {{
+        let (player1_seed, player2_seed) = game::get_seat_seeds($seed);
+        let names = get_seat_names($player1, $player2, $names);
+        seat!($player1, names[0], Marker::X, player1_seed, $kinds, |$first| {
+            seat!($player2, names[1], Marker::O, player2_seed, $kinds, |$second| {
+                $game
+            } $(, or $variant)?)
+        } $(, or $variant)?)
+    }};
+}
+
+fn new_game(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    board: Board,
+    seed: u64,
+) -> Box<dyn Game> {
+    new_named_game(player1, player2, [None, None], board, seed)
+}
+
+// Like `new_game()`, but a seat can be given a name of its own, like the
+// name a person typed in. Seats without one get a name from their kind.
+fn new_named_game(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    names: [Option<&'static str>; 2],
+    board: Board,
+    seed: u64,
+) -> Box<dyn Game> {
+    seats!(
+        player1,
+        player2,
+        names,
+        seed,
+        [
+            PlayerTypeEntry::Human => human::Human::with_seed,
+            PlayerTypeEntry::RandomComp => ai_random::RandomAI::with_seed,
+            PlayerTypeEntry::BasicComp => ai_basic::BasicAI::with_seed,
+            PlayerTypeEntry::ForkingComp => ai_forking::ForkingAI::with_seed,
+            PlayerTypeEntry::OptimalComp => ai_optimal::OptimalAI::with_seed,
+            PlayerTypeEntry::MinimaxComp => ai_minimax::MinimaxAI::with_seed,
+            PlayerTypeEntry::External { command, .. } => |name, marker, _| {
+                external::ExternalPlayer::with_command(name, command, marker)
+            },
+        ],
+        |player1, player2| {
+            Box::new(TicTacToe::with_board(player1, player2, board).seeded(seed))
+        }
+    )
+}
+
+// Qubic and Gravity are ordinary TicTacToe games on a cube or a board with
+// gravity, so any player could sit down to them. This only checks that
+// they're one of the players the menu offers, which a saved game could
+// still ask for.
+fn check_seats(
+    variant: Variant,
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+) -> Result<(), String> {
+    let opponents = get_opponents(variant);
+    for player in [&player1, &player2] {
+        if !opponents.contains(player) {
+            return Err(format!(
+                "{} can't play {}",
+                player.get_name(),
+                variant.get_name()
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn new_qubic_game(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    names: [Option<&'static str>; 2],
+    seed: u64,
+) -> Result<Box<dyn Game>, String> {
+    check_seats(Variant::Qubic, player1, player2)?;
+    Ok(new_named_game(
+        player1,
+        player2,
+        names,
+        Board::cube(QUBIC_SIZE),
+        seed,
+    ))
+}
+
+// The board should have gravity. A saved game can ask for any size, so the
+// board is passed in rather than always being the menu's 7x6.
+fn new_gravity_game(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    names: [Option<&'static str>; 2],
+    board: Board,
+    seed: u64,
+) -> Result<Box<dyn Game>, String> {
+    check_seats(Variant::Gravity, player1, player2)?;
+    Ok(new_named_game(player1, player2, names, board, seed))
+}
+
+// Only humans and the players that implement MiserePlayer can play misère
+// tic-tac-toe.
+fn new_misere_game(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    names: [Option<&'static str>; 2],
+    board: Board,
+    seed: u64,
+) -> Result<Box<dyn Game>, String> {
+    seats!(
+        player1,
+        player2,
+        names,
+        seed,
+        [
+            PlayerTypeEntry::Human => human::Human::with_seed,
+            PlayerTypeEntry::RandomComp => ai_random::RandomAI::with_seed,
+            PlayerTypeEntry::BasicComp => ai_basic::BasicAI::with_seed,
+            PlayerTypeEntry::OptimalComp => ai_optimal::OptimalAI::with_seed,
+            PlayerTypeEntry::MinimaxComp => ai_minimax::MinimaxAI::with_seed,
+        ],
+        |player1, player2| {
+            Ok(Box::new(TicTacToe::misere(player1, player2, board).seeded(seed)))
+        },
+        or Variant::Misere
+    )
+}
+
+// Only humans and the players that implement UltimatePlayer can play
+// ultimate tic-tac-toe, which a saved game could still ask for.
+fn new_ultimate_game(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    names: [Option<&'static str>; 2],
+    seed: u64,
+) -> Result<Box<dyn Game>, String> {
+    seats!(
+        player1,
+        player2,
+        names,
+        seed,
+        [
+            PlayerTypeEntry::Human => human::Human::with_seed,
+            PlayerTypeEntry::RandomComp => ai_random::RandomAI::with_seed,
+            PlayerTypeEntry::MinimaxComp => ai_minimax::MinimaxAI::with_seed,
+        ],
+        |player1, player2| {
+            Ok(Box::new(UltimateTicTacToe::new(player1, player2).seeded(seed)))
+        },
+        or Variant::Ultimate
+    )
+}
+
+// Only humans and the players that implement WildPlayer can play wild
+// tic-tac-toe.
+fn new_wild_game(
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    names: [Option<&'static str>; 2],
+    seed: u64,
+) -> Result<Box<dyn Game>, String> {
+    seats!(
+        player1,
+        player2,
+        names,
+        seed,
+        [
+            PlayerTypeEntry::Human => human::Human::with_seed,
+            PlayerTypeEntry::RandomComp => ai_random::RandomAI::with_seed,
+            PlayerTypeEntry::BasicComp => ai_basic::BasicAI::with_seed,
+            PlayerTypeEntry::MinimaxComp => ai_minimax::MinimaxAI::with_seed,
+        ],
+        |player1, player2| {
+            Ok(Box::new(WildTicTacToe::new(player1, player2).seeded(seed)))
+        },
+        or Variant::Wild
+    )
+}
+
+// Shows a full screen menu until the user picks one of its entries, or
+// backs out with Esc. A status message goes along the bottom of the screen.
+fn select_from_menu<B: Backend, T: Clone + fmt::Display>(
+    terminal: &mut Terminal<B>,
+    menu: &mut MenuList<T>,
+    title: &str,
+    status: Option<&str>,
+) -> io::Result<Option<T>> {
+    loop {
+        terminal.draw(|f| {
+            centered_menu_ui(f, menu, title);
+            status_line_ui(f, status);
+        })?;
+
+        let event = event::read()?;
+        match menu.handle_event(&event, centered_menu_area(terminal.size()?)) {
+            Some(MenuChoice::Picked(item)) => return Ok(Some(item)),
+            Some(MenuChoice::Back) => return Ok(None),
+            None => {}
+        }
+    }
+}
+
+// Lets the user pick one of the saved games by its file name. If there
+// aren't any, the main menu says so instead.
+fn select_saved_game<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    title: &str,
+) -> io::Result<Option<String>> {
+    let file_names: Vec<String> = save::list_saved_games()?
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+    if file_names.is_empty() {
+        app.status_message = Some(format!(
+            "No saved games in {}",
+            save::get_save_dir().display()
+        ));
+        return Ok(None);
     }
+
+    let mut menu = MenuList::with_items(file_names);
+    select_from_menu(terminal, &mut menu, title, None)
 }
 
 pub fn run_app() -> io::Result<()> {
@@ -228,78 +1150,142 @@ pub fn run_app() -> io::Result<()> {
 
     let mut app = App::new();
 
-    let choice = loop {
-        terminal.draw(|f| menu_ui(f, &mut app))?;
-
-        if let Event::Key(key) = event::read()? {
-            match key.code {
-                KeyCode::Down => app.main_menu.next(),
-                KeyCode::Up => app.main_menu.previous(),
-                KeyCode::Enter => break app.handle_main_menu_enter(),
-                _ => {}
-            };
-        }
-    };
+    // Leaving a game comes back here, so the main menu runs until the user
+    // picks Exit.
+    loop {
+        let choice = select_from_menu(
+            &mut terminal,
+            &mut app.main_menu,
+            "Main Menu",
+            app.status_message.as_deref(),
+        )?;
+        let choice = match choice {
+            Some(choice) => choice,
+            None => continue,
+        };
+        app.status_message = None;
 
-    if choice == MainMenuEntry::Play {
-        loop {
-            terminal.draw(|f| player_select_ui(f, &mut app))?;
+        match choice {
+            MainMenuEntry::Play => {
+                let variant = select_from_menu(
+                    &mut terminal,
+                    &mut app.variant_select_menu,
+                    "Select Game",
+                    None,
+                )?;
+                match variant {
+                    Some(variant) => app.set_variant(variant),
+                    None => continue,
+                }
+                let opponent = select_from_menu(
+                    &mut terminal,
+                    &mut app.player_select_menu,
+                    "Select Opponent",
+                    None,
+                )?;
+                match opponent {
+                    Some(opponent) => app.opponent = opponent,
+                    None => continue,
+                }
 
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Down => app.player_select_menu.next(),
-                    KeyCode::Up => app.player_select_menu.previous(),
-                    KeyCode::Enter => break app.handle_player_select_menu_enter(),
-                    _ => {}
+                // Two humans sharing a terminal just take X and O in turn, so there's
+                // no side to pick.
+                if app.opponent != PlayerTypeEntry::Human {
+                    let side = select_from_menu(
+                        &mut terminal,
+                        &mut app.side_select_menu,
+                        "Select Side",
+                        None,
+                    )?;
+                    match side {
+                        Some(side) => app.side = side,
+                        None => continue,
+                    }
                 }
+                let hotseat = app.opponent == PlayerTypeEntry::Human;
+                if !prompt_for_names(&mut terminal, &mut app, hotseat)? {
+                    continue;
+                }
+                app.spectating = false;
+                app.start_game();
+                run_game(&mut terminal, &mut app)?;
             }
-        }
-
-        loop {
-            let mut game_state = app.game.run();
-            let choice = loop {
-                terminal.draw(|f| board_ui(f, &mut app))?;
-
-                match game_state {
-                    GameState::Player1Turn => {
-                        if let Event::Key(key) = event::read()? {
-                            match key.code {
-                                KeyCode::Char('q') => break EndMenuEntry::Exit,
-                                KeyCode::Up | KeyCode::Down | KeyCode::Left | KeyCode::Right => {
-                                    app.update_selected_cell(&key.code);
-                                }
-                                KeyCode::Enter => {
-                                    let player_move = CellCoord::new(
-                                        usize::from(app.selected_cell / 3),
-                                        usize::from(app.selected_cell % 3),
-                                    );
-                                    game_state = app.game.make_human_move(player_move);
-                                    continue;
-                                }
-                                _ => {}
-                            }
-                        }
+            MainMenuEntry::Watch => {
+                let player1 = select_from_menu(
+                    &mut terminal,
+                    &mut app.computer_select_menu,
+                    "Select Player 1 (X)",
+                    None,
+                )?;
+                let player2 = match player1 {
+                    Some(_) => select_from_menu(
+                        &mut terminal,
+                        &mut app.computer_select_menu,
+                        "Select Player 2 (O)",
+                        None,
+                    )?,
+                    None => None,
+                };
+                if let (Some(player1), Some(player2)) = (player1, player2) {
+                    app.set_variant(Variant::Classic);
+                    app.spectating = true;
+                    app.spectator_seats = (player1, player2);
+                    app.start_game();
+                    run_game(&mut terminal, &mut app)?;
+                }
+            }
+            MainMenuEntry::Online => {
+                let address =
+                    prompt_for_text(&mut terminal, "Server Address", &app.server_address)?;
+                if let Some(address) = address {
+                    app.server_address = address;
+                    // A dropped connection just sends us back to the main menu.
+                    if let Err(error) = run_lobby(&mut terminal, &mut app) {
+                        app.status_message = Some(format!(
+                            "Lost the connection to {}: {}",
+                            app.server_address, error
+                        ));
                     }
-                    GameState::Player2Turn => {
-                        game_state = app.game.run();
+                }
+            }
+            MainMenuEntry::Load => {
+                if let Some(file_name) = select_saved_game(&mut terminal, &mut app, "Load Game")? {
+                    match app.load_game(&file_name) {
+                        Ok(()) => run_game(&mut terminal, &mut app)?,
+                        Err(message) => {
+                            app.status_message =
+                                Some(format!("Couldn't load {}: {}", file_name, message))
+                        }
                     }
-                    GameState::Done => {
-                        if let Event::Key(key) = event::read()? {
-                            match key.code {
-                                KeyCode::Down => app.end_menu.next(),
-                                KeyCode::Up => app.end_menu.previous(),
-                                KeyCode::Enter => break app.handle_end_menu_enter(),
-                                _ => {}
-                            }
+                }
+            }
+            MainMenuEntry::Replay => {
+                if let Some(file_name) = select_saved_game(&mut terminal, &mut app, "Replay Game")?
+                {
+                    let path = save::get_save_dir().join(&file_name);
+                    let replay = save::load_game(&path)
+                        .map_err(|error| error.to_string())
+                        .and_then(Replay::new);
+                    match replay {
+                        Ok(mut replay) => run_replay(&mut terminal, &mut replay, &file_name)?,
+                        Err(message) => {
+                            app.status_message =
+                                Some(format!("Couldn't load {}: {}", file_name, message))
                         }
                     }
                 }
-            };
-
-            match choice {
-                EndMenuEntry::PlayAgain => app.restart_game(),
-                EndMenuEntry::Exit => break,
             }
+            MainMenuEntry::Stats => match stats::load_history() {
+                Ok(history) => run_statistics(&mut terminal, &history)?,
+                Err(error) => {
+                    app.status_message = Some(format!(
+                        "Couldn't read {}: {}",
+                        stats::get_stats_path().display(),
+                        error
+                    ))
+                }
+            },
+            MainMenuEntry::Exit => break,
         }
     }
 
@@ -313,46 +1299,582 @@ pub fn run_app() -> io::Result<()> {
     Ok(())
 }
 
-fn menu_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
-    let chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(
-            [
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
-            ]
-            .as_ref(),
-        )
-        .split(f.size());
+// Asks the user to type something in, starting from `text`. Returns None if
+// they back out with Esc.
+fn prompt_for_text<B: Backend>(
+    terminal: &mut Terminal<B>,
+    title: &str,
+    text: &str,
+) -> io::Result<Option<String>> {
+    let mut text = String::from(text);
+    loop {
+        terminal.draw(|f| text_prompt_ui(f, title, &text))?;
 
-    let center_chunks = Layout::default()
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Enter if !text.trim().is_empty() => return Ok(Some(text.trim().into())),
+                KeyCode::Esc => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+// Asks the people about to play for their names, starting from the names
+// they used last time. Returns false if they back out with Esc.
+fn prompt_for_names<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    hotseat: bool,
+) -> io::Result<bool> {
+    let titles: &[&str] = if hotseat {
+        &["Player 1 (X) Name", "Player 2 (O) Name"]
+    } else {
+        &["Your Name"]
+    };
+    for (index, title) in titles.iter().enumerate() {
+        match prompt_for_text(terminal, title, &app.player_names[index])? {
+            Some(name) => app.set_player_name(index, &name),
+            None => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+// How long the online screens wait for a key before checking for messages
+// from the server. Moves from the other player can arrive at any time, so
+// these screens can't block on `event::read()` like the others.
+const ONLINE_POLL_TIME: Duration = Duration::from_millis(50);
+
+fn get_lobby_entries(open_games: &[(u32, Marker)]) -> Vec<LobbyEntry> {
+    let mut entries: Vec<LobbyEntry> = open_games
+        .iter()
+        .map(|&(id, marker)| LobbyEntry::Join(id, marker))
+        .collect();
+    // The server plays classic games, against anyone the menus offer.
+    for opponent in get_opponents(Variant::Classic) {
+        entries.push(LobbyEntry::Create(opponent));
+    }
+    entries.push(LobbyEntry::Refresh);
+    entries.push(LobbyEntry::Back);
+    entries
+}
+
+// Connects to `app.server_address` and shows its open games until the user
+// goes back to the main menu.
+fn run_lobby<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    let mut connection = ServerConnection::connect(&app.server_address)?;
+    connection.list_games()?;
+    let mut lobby_menu = MenuList::with_items(get_lobby_entries(&[]));
+    let title = format!("Online: {}", app.server_address);
+
+    loop {
+        terminal.draw(|f| {
+            centered_menu_ui(f, &mut lobby_menu, &title);
+            status_line_ui(f, app.status_message.as_deref());
+        })?;
+
+        while let Some(message) = connection.try_receive() {
+            match message {
+                ServerMessage::Games(open_games) => {
+                    lobby_menu = MenuList::with_items(get_lobby_entries(&open_games));
+                }
+                ServerMessage::Joined(id, marker) => {
+                    app.status_message = None;
+                    run_online_game(terminal, &mut connection, id, marker)?;
+                    connection.list_games()?;
+                }
+                ServerMessage::Error(reason) => app.status_message = Some(reason),
+                ServerMessage::Disconnected => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "the server closed it",
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        if event::poll(ONLINE_POLL_TIME)? {
+            let event = event::read()?;
+            if let Event::Key(event::KeyEvent {
+                code: KeyCode::Char('q'),
+                ..
+            }) = event
+            {
+                return Ok(());
+            }
+            let entry = match lobby_menu.handle_event(&event, centered_menu_area(terminal.size()?))
+            {
+                Some(MenuChoice::Picked(entry)) => entry,
+                Some(MenuChoice::Back) => return Ok(()),
+                None => continue,
+            };
+            match entry {
+                LobbyEntry::Create(opponent) => {
+                    let side =
+                        select_from_menu(terminal, &mut app.side_select_menu, "Select Side", None)?;
+                    if let Some(side) = side {
+                        connection.create_game(opponent.get_name(), side.get_marker())?
+                    }
+                }
+                LobbyEntry::Join(id, _) => connection.join_game(id)?,
+                LobbyEntry::Refresh => connection.list_games()?,
+                LobbyEntry::Back => return Ok(()),
+            }
+        }
+    }
+}
+
+// What we know about the online game we're sitting at. The server owns the
+// game itself, and sends us the board whenever it changes.
+struct OnlineGame {
+    id: u32,
+    marker: Marker,
+    board: Option<RemoteBoard>,
+    selected_cell: usize,
+    // The server only sends whole boards, so the last move is whichever
+    // cell filled up since the board before.
+    last_move: Option<usize>,
+    opponent_left: bool,
+    // The server's answer to a move it didn't accept.
+    error: Option<String>,
+}
+
+impl OnlineGame {
+    fn is_over(&self) -> bool {
+        self.opponent_left
+            || matches!(
+                self.board,
+                Some(RemoteBoard {
+                    status: BoardStatus::Over(_),
+                    ..
+                })
+            )
+    }
+
+    fn get_status_text(&self) -> String {
+        let status = self.board.as_ref().map(|board| &board.status);
+        match status {
+            _ if self.opponent_left => String::from("Your opponent left"),
+            None | Some(BoardStatus::Waiting) => String::from("Waiting for an opponent"),
+            Some(BoardStatus::Turn(marker)) if *marker == self.marker => String::from("Your turn"),
+            Some(BoardStatus::Turn(_)) => String::from("Opponent's turn"),
+            Some(BoardStatus::Over(Some(marker))) if *marker == self.marker => {
+                String::from("You won!")
+            }
+            Some(BoardStatus::Over(Some(_))) => String::from("You lost!"),
+            Some(BoardStatus::Over(None)) => String::from("The game was a tie!"),
+        }
+    }
+}
+
+// Plays a game on the server until it's over or the user leaves it.
+fn run_online_game<B: Backend>(
+    terminal: &mut Terminal<B>,
+    connection: &mut ServerConnection,
+    id: u32,
+    marker: Marker,
+) -> io::Result<()> {
+    let mut game = OnlineGame {
+        id,
+        marker,
+        board: None,
+        selected_cell: 0,
+        last_move: None,
+        opponent_left: false,
+        error: None,
+    };
+
+    loop {
+        terminal.draw(|f| online_game_ui(f, &game))?;
+
+        while let Some(message) = connection.try_receive() {
+            match message {
+                ServerMessage::Board(board) => {
+                    if let Some(old_board) = &game.board {
+                        // A board can bring our move and the reply to it at
+                        // once, and the reply is the one worth pointing out.
+                        let our_char = match game.marker {
+                            Marker::X => 'X',
+                            Marker::O => 'O',
+                        };
+                        let changed_cells = (0..board.cells.len()).filter(|&index| {
+                            old_board.cells.get(index) == Some(&'_') && board.cells[index] != '_'
+                        });
+                        if let Some(cell) =
+                            changed_cells.max_by_key(|&index| board.cells[index] != our_char)
+                        {
+                            game.last_move = Some(cell);
+                        }
+                    }
+                    game.board = Some(board);
+                    game.error = None;
+                }
+                ServerMessage::OpponentLeft => game.opponent_left = true,
+                ServerMessage::Error(reason) => game.error = Some(reason),
+                ServerMessage::Disconnected => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "the server closed it",
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        if !event::poll(ONLINE_POLL_TIME)? {
+            continue;
+        }
+        let event = event::read()?;
+        if let (Some((column, row)), Some(board)) = (get_click(&event), &game.board) {
+            let (_, board_area) = board_screen_layout(terminal.size()?);
+            if let Some(cell) = get_cell_at(board_area, board.width, board.height, column, row) {
+                game.selected_cell = cell;
+                connection.make_move(CellCoord::new(cell / board.width, cell % board.width))?;
+            }
+        }
+        if let Event::Key(key) = event {
+            match (key.code, &game.board) {
+                (KeyCode::Esc, _) | (KeyCode::Char('q'), _) => {
+                    if !game.is_over() {
+                        connection.leave_game()?;
+                    }
+                    return Ok(());
+                }
+                (KeyCode::Enter, _) if game.is_over() => return Ok(()),
+                (KeyCode::Enter, Some(board)) => {
+                    let coord = CellCoord::new(
+                        game.selected_cell / board.width,
+                        game.selected_cell % board.width,
+                    );
+                    connection.make_move(coord)?;
+                }
+                (code, Some(board)) => {
+                    game.selected_cell =
+                        move_cursor(game.selected_cell, board.width, board.height, &code);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// Runs games until the player chooses to exit from the end menu, which
+// goes back to the main menu.
+fn run_game<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
+    loop {
+        // Draw the board before anything else, so a computer playing X
+        // makes its first move on screen.
+        let mut game_state = app.game.get_game_state();
+        let choice = loop {
+            app.record_finished_game();
+            terminal.draw(|f| board_ui(f, app))?;
+
+            match game_state {
+                GameState::Player1Turn | GameState::Player2Turn if app.game.is_human_turn() => {
+                    let event = event::read()?;
+                    if let Some((column, row)) = get_click(&event) {
+                        let cell =
+                            get_game_cell_at(app.game.as_ref(), terminal.size()?, column, row);
+                        if let Some(cell) = cell {
+                            app.selected_cell = cell;
+                            game_state = app.game.make_human_move(app.get_selected_coord());
+                            app.pacing.start_turn();
+                            continue;
+                        }
+                    }
+                    if let Event::Key(key) = event {
+                        match key.code {
+                            KeyCode::Char('q') => break EndMenuEntry::Exit,
+                            KeyCode::Char('s') => app.save_game(),
+                            KeyCode::Char('h') => app.show_hint(),
+                            KeyCode::Char('m') => app.game.switch_marker(),
+                            KeyCode::Up
+                            | KeyCode::Down
+                            | KeyCode::Left
+                            | KeyCode::Right
+                            | KeyCode::Tab
+                            | KeyCode::BackTab => {
+                                app.update_selected_cell(&key.code);
+                            }
+                            KeyCode::Enter => {
+                                let player_move = app.get_selected_coord();
+                                game_state = app.game.make_human_move(player_move);
+                                app.pacing.start_turn();
+                                continue;
+                            }
+                            code => {
+                                if let Some(state) = app.handle_history_key(&code) {
+                                    game_state = state;
+                                }
+                            }
+                        }
+                    }
+                }
+                GameState::Player1Turn | GameState::Player2Turn => {
+                    // Keep listening for keys while the computer waits its turn,
+                    // so the game can be paused, stepped, or sped up.
+                    if event::poll(app.pacing.get_wait_time())? {
+                        if let Event::Key(key) = event::read()? {
+                            match key.code {
+                                KeyCode::Char('q') => break EndMenuEntry::Exit,
+                                KeyCode::Char('s') => app.save_game(),
+                                code => match app.handle_history_key(&code) {
+                                    Some(state) => game_state = state,
+                                    None => app.pacing.handle_key(&code),
+                                },
+                            }
+                        }
+                    } else if app.pacing.is_computer_ready() {
+                        game_state = app.game.run();
+                        app.pacing.start_turn();
+                    }
+                }
+                GameState::Done => {
+                    let event = event::read()?;
+                    let (left_chunks, _) = board_screen_layout(terminal.size()?);
+                    if let Some(MenuChoice::Picked(entry)) =
+                        app.end_menu.handle_event(&event, left_chunks[1])
+                    {
+                        break entry;
+                    }
+                    if let Event::Key(key) = event {
+                        match key.code {
+                            KeyCode::Char('s') => app.save_game(),
+                            code => {
+                                if let Some(state) = app.handle_history_key(&code) {
+                                    game_state = state;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        };
+
+        match choice {
+            EndMenuEntry::PlayAgain => app.restart_game(),
+            EndMenuEntry::Exit => return Ok(()),
+        }
+    }
+}
+
+// Shows the statistics until the user leaves for the main menu.
+fn run_statistics<B: Backend>(
+    terminal: &mut Terminal<B>,
+    history: &[GameRecord],
+) -> io::Result<()> {
+    loop {
+        terminal.draw(|f| statistics_ui(f, history))?;
+
+        if let Event::Key(key) = event::read()? {
+            if let KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter = key.code {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// Steps through a saved game until the user leaves for the main menu.
+fn run_replay<B: Backend>(
+    terminal: &mut Terminal<B>,
+    replay: &mut Replay,
+    file_name: &str,
+) -> io::Result<()> {
+    loop {
+        terminal.draw(|f| replay_ui(f, replay, file_name))?;
+
+        if let Event::Key(key) = event::read()? {
+            match key.code {
+                KeyCode::Right | KeyCode::Down => replay.step_forward(),
+                KeyCode::Left | KeyCode::Up => replay.step_back(),
+                KeyCode::Home => replay.go_to_start(),
+                KeyCode::End => replay.go_to_end(),
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                _ => {}
+            }
+        }
+    }
+}
+
+// A single line of text input in the middle of the screen.
+fn text_prompt_ui<B: Backend>(f: &mut Frame<B>, title: &str, text: &str) {
+    let size = f.size();
+    let width = size.width.min(50);
+    let area = Rect::new(
+        size.x + (size.width - width) / 2,
+        size.y + size.height.saturating_sub(3) / 2,
+        width,
+        size.height.min(3),
+    );
+    let prompt = Paragraph::new(format!("{}_", text))
+        .block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(prompt, area);
+}
+
+fn get_summary_line(name: &str, summary: &RecordSummary) -> String {
+    let streak = match summary.streak {
+        Some((GameResult::Win, count)) => format!("W{}", count),
+        Some((GameResult::Loss, count)) => format!("L{}", count),
+        Some((GameResult::Draw, count)) => format!("D{}", count),
+        None => String::from("-"),
+    };
+    format!(
+        "{:<18}{:>6}{:>8}{:>7}{:>8}{:>13}",
+        name, summary.wins, summary.losses, summary.draws, streak, summary.best_win_streak
+    )
+}
+
+// The player's record against each computer, and their latest games.
+fn statistics_ui<B: Backend>(f: &mut Frame<B>, history: &[GameRecord]) {
+    let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
-                Constraint::Percentage(33),
-            ]
-            .as_ref(),
-        )
-        .split(chunks[1]);
+        .margin(1)
+        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .split(f.size());
+
+    let mut lines = vec![format!(
+        "{:<18}{:>6}{:>8}{:>7}{:>8}{:>13}",
+        "Opponent", "Wins", "Losses", "Draws", "Streak", "Best streak"
+    )];
+    for (opponent, summary) in stats::summarize_by_opponent(history) {
+        lines.push(get_summary_line(&opponent, &summary));
+    }
+    lines.push(String::new());
+    lines.push(get_summary_line("All", &stats::summarize(history.iter())));
+    let record: Vec<ListItem> = lines
+        .into_iter()
+        .map(|line| ListItem::new(Span::raw(line)))
+        .collect();
+    let record = List::new(record).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Record (q: back)"),
+    );
+    f.render_widget(record, chunks[0]);
+
+    let games: Vec<ListItem> = if history.is_empty() {
+        vec![ListItem::new(Span::raw(
+            "No games yet. Finish a game against a computer to start your record.",
+        ))]
+    } else {
+        history
+            .iter()
+            .rev()
+            .map(|game| {
+                let result = match game.result {
+                    GameResult::Win => "Won",
+                    GameResult::Loss => "Lost",
+                    GameResult::Draw => "Drew",
+                };
+                ListItem::new(Span::raw(format!(
+                    "{}  {} as {:?} vs {}, {}, {} moves",
+                    game.date,
+                    result,
+                    game.marker,
+                    game.opponent,
+                    game.size,
+                    game.moves.len()
+                )))
+            })
+            .collect()
+    };
+    let games =
+        List::new(games).block(Block::default().borders(Borders::ALL).title("Recent Games"));
+    f.render_widget(games, chunks[1]);
+}
+
+fn online_game_ui<B: Backend>(f: &mut Frame<B>, game: &OnlineGame) {
+    let (left_chunks, center_box) = board_screen_layout(f.size());
 
-    let items: Vec<ListItem> = app
-        .main_menu
+    let lines: Vec<ListItem> = [
+        format!("You play {:?}", game.marker),
+        game.get_status_text(),
+        String::new(),
+        game.error.clone().unwrap_or_default(),
+    ]
+    .iter()
+    .map(|line| ListItem::new(Span::raw(line.clone())))
+    .collect();
+    let info = List::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Online Game {}", game.id)),
+    );
+    f.render_widget(info, left_chunks[0]);
+
+    let leave = if game.is_over() {
+        "q / enter: back to lobby"
+    } else {
+        "q: leave game"
+    };
+    let controls: Vec<ListItem> = ["arrows: move cursor", "enter: place marker", leave]
+        .iter()
+        .map(|&line| ListItem::new(Span::raw(line)))
+        .collect();
+    let controls =
+        List::new(controls).block(Block::default().borders(Borders::ALL).title("Controls"));
+    f.render_widget(controls, left_chunks[1]);
+
+    if let Some(board) = &game.board {
+        let highlights = BoardHighlights {
+            cursor: Some(game.selected_cell),
+            last_move: game.last_move,
+            winning_cells: Vec::new(),
+            hint: None,
+        };
+        board_grid_ui(
+            f,
+            center_box,
+            board.width,
+            board.height,
+            &highlights,
+            |cell_index| board.cells[cell_index],
+        );
+    }
+}
+
+// Draws the app's status message, if it has one, along the bottom line of
+// the screen.
+fn status_line_ui<B: Backend>(f: &mut Frame<B>, status: Option<&str>) {
+    if let Some(message) = status {
+        let size = f.size();
+        if size.height == 0 {
+            return;
+        }
+        let line = Rect::new(size.x, size.bottom() - 1, size.width, 1);
+        f.render_widget(Paragraph::new(message), line);
+    }
+}
+
+// All of the full screen menus are drawn the same way, as a list in the
+// middle of a 3x3 grid of boxes.
+fn centered_menu_ui<B: Backend, T: fmt::Display>(
+    f: &mut Frame<B>,
+    menu: &mut MenuList<T>,
+    title: &str,
+) {
+    let items: Vec<ListItem> = menu
         .items
         .iter()
-        .map(|&i| ListItem::new(Span::raw(i.to_string())).style(Style::default().fg(Color::White)))
+        .map(|i| ListItem::new(Span::raw(i.to_string())).style(Style::default().fg(Color::White)))
         .collect();
 
     let items = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Main Menu"))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(Style::default().add_modifier(Modifier::BOLD))
         .highlight_symbol(">> ");
-    f.render_stateful_widget(items, center_chunks[1], &mut app.main_menu.state)
+    f.render_stateful_widget(items, centered_menu_area(f.size()), &mut menu.state)
 }
 
-fn player_select_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+// The middle box of the menus' 3x3 grid. Clicks are checked against the
+// same box the menu is drawn in.
+fn centered_menu_area(area: Rect) -> Rect {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
         .constraints(
@@ -363,7 +1885,7 @@ fn player_select_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             ]
             .as_ref(),
         )
-        .split(f.size());
+        .split(area);
 
     let center_chunks = Layout::default()
         .direction(Direction::Vertical)
@@ -376,26 +1898,13 @@ fn player_select_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             .as_ref(),
         )
         .split(chunks[1]);
-
-    let items: Vec<ListItem> = app
-        .player_select_menu
-        .items
-        .iter()
-        .map(|&i| ListItem::new(Span::raw(i.to_string())).style(Style::default().fg(Color::White)))
-        .collect();
-
-    let items = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Select Opponent"),
-        )
-        .highlight_style(Style::default().add_modifier(Modifier::BOLD))
-        .highlight_symbol(">> ");
-    f.render_stateful_widget(items, center_chunks[1], &mut app.player_select_menu.state)
+    center_chunks[1]
 }
 
-fn board_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+// The game and replay screens share a layout: a panel of text on the left
+// and the board in the middle of the screen. Returns the left panel split
+// into top and bottom halves, and the board's box.
+fn board_screen_layout(area: Rect) -> (Vec<Rect>, Rect) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -406,7 +1915,7 @@ fn board_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             ]
             .as_ref(),
         )
-        .split(f.size());
+        .split(area);
 
     let center_chunks = Layout::default()
         .direction(Direction::Horizontal)
@@ -420,28 +1929,53 @@ fn board_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
         )
         .split(chunks[1]);
 
-    // If the Game is done, show the result, and allow user
-    // to select whether to play again.
-    if app.game.get_game_state() == GameState::Done {
-        let left_box = center_chunks[0];
-        let left_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
-            .split(left_box);
-
-        let text = match app.game.get_winner() {
-            // TODO: Once we figure out how to use names, we should indicate who won
-            // by name. This is correct, but pretty generic.
-            Winner::Player1 => "Player 1 Won!",
-            Winner::Player2 => "Player 2 Won!",
-            Winner::None => "The game was a tie!",
-        };
-        let end_prompt = List::new([ListItem::new(Span::raw(text))]).block(
-            Block::default()
-                .borders(Borders::ALL),
-        );
-        f.render_widget(end_prompt, left_chunks[0]);
+    let left_box = center_chunks[0];
+    let left_chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(left_box);
+
+    (left_chunks, center_chunks[1])
+}
+
+fn board_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    status_line_ui(f, app.status_message.as_deref());
+    let (left_chunks, center_box) = board_screen_layout(f.size());
+
+    // Both seats are always shown, with whose turn it is while the game is
+    // going, and how it went once it's done.
+    let seats = app.game.get_seats();
+    let turn_marker = app.game.get_turn_marker();
+    let status = match (app.game.get_game_state(), app.game.get_winner()) {
+        (GameState::Done, Winner::Player1) => format!("{} won!", seats[0].name),
+        (GameState::Done, Winner::Player2) => format!("{} won!", seats[1].name),
+        (GameState::Done, Winner::None) => String::from("The game was a tie!"),
+        _ => match seats.iter().find(|seat| Some(seat.marker) == turn_marker) {
+            Some(seat) => format!("{} ({:?}) to move", seat.name, seat.marker),
+            None => String::new(),
+        },
+    };
+    let mut lines: Vec<ListItem> = seats
+        .iter()
+        .map(|seat| {
+            let line = get_seat_line(seat, Some(seat.marker) == turn_marker);
+            ListItem::new(Span::raw(line))
+        })
+        .collect();
+    lines.push(ListItem::new(Span::raw("")));
+    lines.push(ListItem::new(Span::raw(status)));
+    // The board looks just like a classic one, so misère and wild need
+    // saying.
+    let title = match app.game.get_variant() {
+        Variant::Misere => "Players (misère: a line loses)",
+        Variant::Wild => "Players (wild: any line wins)",
+        _ => "Players",
+    };
+    let players = List::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(players, left_chunks[0]);
 
+    // If the Game is done, allow user to select whether to play again.
+    if app.game.get_game_state() == GameState::Done {
         let items: Vec<ListItem> = app
             .end_menu
             .items
@@ -452,64 +1986,826 @@ fn board_ui<B: Backend>(f: &mut Frame<B>, app: &mut App) {
             .collect();
 
         let items = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("Menu"))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Menu (s: save game)"),
+            )
             .highlight_style(Style::default().add_modifier(Modifier::BOLD))
             .highlight_symbol(">> ");
         f.render_stateful_widget(items, left_chunks[1], &mut app.end_menu.state)
+    } else if app.spectating {
+        let status = if app.pacing.paused {
+            String::from("Paused")
+        } else {
+            format!("{}ms per move", app.pacing.get_delay().as_millis())
+        };
+        let controls: Vec<ListItem> = [
+            status.as_str(),
+            "",
+            "space: pause / resume",
+            "n: step while paused",
+            "+ / -: faster / slower",
+            "s: save game",
+            "q: quit",
+        ]
+        .iter()
+        .map(|&line| ListItem::new(Span::raw(line)))
+        .collect();
+        let controls =
+            List::new(controls).block(Block::default().borders(Borders::ALL).title("Spectating"));
+        f.render_widget(controls, left_chunks[1]);
+    } else {
+        let mut lines = Vec::new();
+        if let Some((cell, reason)) = app.get_current_hint() {
+            lines.push(format!(
+                "Hint: {} ({})",
+                save::cell_to_notation(cell),
+                reason
+            ));
+            lines.push(format!("  {}", reason.get_description()));
+        }
+        // OptimalAI only knows how to play on a single flat board.
+        match app.game.get_variant() {
+            Variant::Classic | Variant::Misere => lines.push(String::from("h: hint")),
+            Variant::Qubic => lines.push(String::from("Tab: next layer")),
+            Variant::Gravity => lines.push(String::from("Left / Right: column")),
+            Variant::Wild => {
+                if let Some(marker) = app.game.get_placing_marker() {
+                    lines.push(format!("m: switch marker (placing {:?})", marker));
+                }
+            }
+            Variant::Ultimate => {}
+        }
+        lines.push(String::from("u / r: undo / redo"));
+        lines.push(String::from("s: save game"));
+        let controls: Vec<ListItem> = lines
+            .into_iter()
+            .map(|line| ListItem::new(Span::raw(line)))
+            .collect();
+        let controls =
+            List::new(controls).block(Block::default().borders(Borders::ALL).title("Controls"));
+        f.render_widget(controls, left_chunks[1]);
+    }
+
+    let game = &app.game;
+    let size = game.get_board_size();
+    let get_index = |cell: &CellCoord| cell.row * size.get_columns() + cell.column;
+    let highlights = BoardHighlights {
+        cursor: Some(get_index(&app.get_selected_coord())),
+        last_move: game.get_move_history().last().map(get_index),
+        winning_cells: game.get_winning_line().iter().map(get_index).collect(),
+        hint: app.get_current_hint().map(|(cell, _)| get_index(&cell)),
+    };
+    match game.get_ultimate_board() {
+        Some(board) => {
+            let playing = game.get_game_state() != GameState::Done;
+            let area = get_ultimate_grid_area(f.size());
+            ultimate_grid_ui(f, area, board, playing, &highlights);
+        }
+        None if size.layers > 1 || game.get_variant() == Variant::Gravity => {
+            let area = get_layers_area(f.size(), size);
+            layers_ui(f, area, size, &highlights, |cell_index| {
+                game.get_cellstate_char(cell_index)
+            });
+        }
+        None => board_grid_ui(
+            f,
+            center_box,
+            size.width,
+            size.height,
+            &highlights,
+            |cell_index| game.get_cellstate_char(cell_index),
+        ),
+    }
+}
+
+// The cells to pick out when drawing a board, by index.
+struct BoardHighlights {
+    // Where the player's cursor is.
+    cursor: Option<usize>,
+    // The most recent move, so it's easy to see what the other player did.
+    last_move: Option<usize>,
+    // The line that won the game, if it's over.
+    winning_cells: Vec<usize>,
+    // The cell a hint suggested playing in.
+    hint: Option<usize>,
+}
+
+// Draws a board as a grid of boxes, one per cell. The cursor's border is
+// cyan, the winning line's is green and a hinted cell's is magenta. The last
+// move's marker is yellow, and the winning line's markers are green. A hinted
+// cell is empty, so it gets a magenta * to make it stand out.
+fn board_grid_ui<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    width: usize,
+    height: usize,
+    highlights: &BoardHighlights,
+    get_cellstate_char: impl Fn(usize) -> char,
+) {
+    for (cell_index, cell_area) in get_cell_areas(area, width, height).into_iter().enumerate() {
+        let is_winning_cell = highlights.winning_cells.contains(&cell_index);
+        let border_style = if highlights.cursor == Some(cell_index) {
+            Style::default().fg(Color::Cyan)
+        } else if is_winning_cell {
+            Style::default().fg(Color::Green)
+        } else if highlights.hint == Some(cell_index) {
+            Style::default().fg(Color::Magenta)
+        } else {
+            Style::default().fg(Color::Red)
+        };
+        let marker_style = if is_winning_cell {
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD)
+        } else if highlights.last_move == Some(cell_index) {
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+
+        let (cell_char, marker_style) = match get_cellstate_char(cell_index) {
+            '_' if highlights.hint == Some(cell_index) => {
+                ('*', Style::default().fg(Color::Magenta))
+            }
+            cell_char => (cell_char, marker_style),
+        };
+        let marker = List::new([ListItem::new(Span::styled(
+            format!("{:^3}", cell_char),
+            marker_style,
+        ))])
+        .block(
+            Block::default()
+                .border_style(border_style)
+                .borders(Borders::ALL),
+        );
+
+        f.render_widget(marker, cell_area);
     }
+}
 
+// The box each cell is drawn in, in the same order as the board's cells.
+fn get_cell_areas(area: Rect, width: usize, height: usize) -> Vec<Rect> {
     // The block layout with TUI is a bit weird. The last block
     // will try to fill the remaining space in the parent block.
-    // As a result, I added a fourth block for which I don't draw
-    // a border. That way, only the 3x3 board is displayed.
-    let center_box = center_chunks[1];
+    // As a result, I added an extra block for which I don't draw
+    // a border. That way, only the board's cells are displayed.
     let rows = Layout::default()
         .direction(Direction::Vertical)
-        .constraints(
-            [
-                Constraint::Length(3),
-                Constraint::Length(3),
-                Constraint::Length(3),
-                Constraint::Length(3),
-            ]
-            .as_ref(),
-        )
-        .split(center_box);
+        .constraints(vec![Constraint::Length(3); height + 1])
+        .split(area);
 
-    let mut cell_index = 0;
-    for row in &rows[0..3] {
+    let mut cell_areas = Vec::new();
+    for row in &rows[0..height] {
         let columns = Layout::default()
             .direction(Direction::Horizontal)
-            .constraints(
-                [
-                    Constraint::Length(5),
-                    Constraint::Length(5),
-                    Constraint::Length(5),
-                    Constraint::Length(5),
-                ]
-                .as_ref(),
-            )
+            .constraints(vec![Constraint::Length(5); width + 1])
             .split(*row);
+        cell_areas.extend_from_slice(&columns[0..width]);
+    }
+    cell_areas
+}
 
-        for (index, _) in columns[0..3].iter().enumerate() {
-            let border_style = if cell_index == app.selected_cell {
-                Style::default().fg(Color::Cyan)
-            } else {
-                Style::default().fg(Color::Red)
-            };
+// Returns the cell drawn at (column, row), if there is one. A cell that
+// didn't fit on the screen gets squashed to nothing, so it can't be clicked.
+fn get_cell_at(area: Rect, width: usize, height: usize, column: u16, row: u16) -> Option<usize> {
+    get_cell_areas(area, width, height)
+        .iter()
+        .position(|&cell_area| is_inside(cell_area, column, row))
+}
 
-            let marker = List::new([ListItem::new(Span::raw(format!(
-                "{:^3}",
-                app.game.get_cellstate_char(usize::from(cell_index))
-            )))])
-            .block(
-                Block::default()
-                    .border_style(border_style)
-                    .borders(Borders::ALL),
-            );
+// Returns the cell of `game` drawn at (column, row), on a screen of the
+// given size.
+fn get_game_cell_at(game: &dyn Game, screen: Rect, column: u16, row: u16) -> Option<usize> {
+    let size = game.get_board_size();
+    match game.get_ultimate_board() {
+        Some(_) => get_ultimate_cell_areas(get_ultimate_grid_area(screen))
+            .iter()
+            .position(|&cell_area| is_inside(cell_area, column, row)),
+        None if size.layers > 1 || game.get_variant() == Variant::Gravity => {
+            let area = get_layers_area(screen, size);
+            get_layer_cell_areas(area, size)
+                .iter()
+                .position(|&cell_area| is_inside(cell_area, column, row))
+        }
+        None => get_cell_at(
+            board_screen_layout(screen).1,
+            size.width,
+            size.height,
+            column,
+            row,
+        ),
+    }
+}
+
+// Qubic is played on a cube this many cells on a side.
+const QUBIC_SIZE: usize = 4;
 
-            f.render_widget(marker, columns[index]);
-            cell_index += 1;
+// Gravity is played on Connect Four's board, with four in a row to win.
+const GRAVITY_WIDTH: usize = 7;
+const GRAVITY_HEIGHT: usize = 6;
+const GRAVITY_WIN_LENGTH: usize = 4;
+
+// An ultimate game has 81 cells, which won't fit on a terminal as a box
+// each. So each small board gets a box instead, with its cells inside as
+// one character each, two columns apart.
+const SMALL_BOARD_WIDTH: u16 = 9;
+const SMALL_BOARD_HEIGHT: u16 = 5;
+
+// The grid of small boards goes in the middle column of the screen. It's
+// taller than the box a classic board gets, so it uses the column's full
+// height.
+fn get_ultimate_grid_area(screen: Rect) -> Rect {
+    let (_, center_box) = board_screen_layout(screen);
+    let width = (3 * SMALL_BOARD_WIDTH).min(center_box.width);
+    let height = (3 * SMALL_BOARD_HEIGHT).min(screen.height);
+    Rect::new(
+        center_box.x + (center_box.width - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// The box each small board is drawn in, cut down to what fits in `area`.
+fn get_small_board_areas(area: Rect) -> Vec<Rect> {
+    (0..9)
+        .map(|board_index| {
+            Rect::new(
+                area.x + (board_index % 3) * SMALL_BOARD_WIDTH,
+                area.y + (board_index / 3) * SMALL_BOARD_HEIGHT,
+                SMALL_BOARD_WIDTH,
+                SMALL_BOARD_HEIGHT,
+            )
+            .intersection(area)
+        })
+        .collect()
+}
+
+// Where each cell is drawn, in the same order as the 9x9 grid's cells. A
+// cell covers its character and the space before it, to be easier to click.
+fn get_ultimate_cell_areas(area: Rect) -> Vec<Rect> {
+    let board_areas = get_small_board_areas(area);
+    itertools::iproduct!(0..9u16, 0..9u16)
+        .map(|(row, column)| {
+            let board_area = board_areas[(row / 3 * 3 + column / 3) as usize];
+            Rect::new(
+                board_area.x + 1 + (column % 3) * 2,
+                board_area.y + 1 + row % 3,
+                2,
+                1,
+            )
+            .intersection(area)
+        })
+        .collect()
+}
+
+// Draws an ultimate game. The boards the next move can go in have yellow
+// borders, and the boards that won the game have green ones. A won board
+// shows its winner in its title, with the markers that didn't matter
+// greyed out. Cells are picked out the same way as on a classic board.
+fn ultimate_grid_ui<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    board: &UltimateBoard,
+    playing: bool,
+    highlights: &BoardHighlights,
+) {
+    let open_boards = match playing {
+        true => board.get_open_boards(),
+        false => Vec::new(),
+    };
+    for (board_index, board_area) in get_small_board_areas(area).into_iter().enumerate() {
+        let winner = board.get_board_winner(board_index);
+        let get_cell_index = |row: usize, column: usize| {
+            (board_index / 3 * 3 + row) * 9 + board_index % 3 * 3 + column
+        };
+
+        let lines: Vec<Spans> = (0..3)
+            .map(|row| {
+                let mut spans = Vec::new();
+                for column in 0..3 {
+                    let cell_index = get_cell_index(row, column);
+                    let style = if highlights.cursor == Some(cell_index) {
+                        Style::default().fg(Color::Black).bg(Color::Cyan)
+                    } else if highlights.winning_cells.contains(&cell_index) {
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD)
+                    } else if highlights.last_move == Some(cell_index) {
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD)
+                    } else if winner != '_' {
+                        Style::default().fg(Color::DarkGray)
+                    } else {
+                        Style::default()
+                    };
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(
+                        board.get_cellstate_char(cell_index).to_string(),
+                        style,
+                    ));
+                }
+                Spans::from(spans)
+            })
+            .collect();
+
+        let is_winning_board = (0..9).any(|cell| {
+            highlights
+                .winning_cells
+                .contains(&get_cell_index(cell / 3, cell % 3))
+        });
+        let border_style = if is_winning_board {
+            Style::default().fg(Color::Green)
+        } else if open_boards.contains(&board_index) {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default().fg(Color::Red)
+        };
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style);
+        if winner != '_' {
+            block = block.title(Span::styled(
+                format!(" {} ", winner),
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
         }
+        f.render_widget(Paragraph::new(lines).block(block), board_area);
+    }
+}
+
+// A cube's layers are drawn side by side, with a box each and the cells
+// inside as one character each, two columns apart, the same as ultimate's
+// small boards. That's wider than the middle column, so the layers spill
+// over into the empty column on the right of the screen.
+//
+// Gravity's board is too tall for a box per cell, so it's drawn the same
+// way, as a single layer. That fits in the middle column, so it stays there.
+fn get_layers_area(screen: Rect, size: BoardSize) -> Rect {
+    let (_, center_box) = board_screen_layout(screen);
+    let right = screen.x + screen.width;
+    let width = (size.layers * get_layer_box_width(size.width)) as u16;
+    let space = match width <= center_box.width {
+        true => center_box.width,
+        false => right - center_box.x,
+    };
+    let width = width.min(space);
+    let height = ((size.height + 2) as u16).min(screen.height);
+    Rect::new(
+        center_box.x + (space - width) / 2,
+        screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+// Two columns for each cell, one for the space after the last cell, and
+// two for the borders.
+fn get_layer_box_width(layer_width: usize) -> usize {
+    layer_width * 2 + 3
+}
+
+// The box each layer is drawn in, cut down to what fits in `area`.
+fn get_layer_areas(area: Rect, size: BoardSize) -> Vec<Rect> {
+    let box_width = get_layer_box_width(size.width) as u16;
+    (0..size.layers as u16)
+        .map(|layer| {
+            Rect::new(area.x + layer * box_width, area.y, box_width, area.height).intersection(area)
+        })
+        .collect()
+}
+
+// Where each cell is drawn, in the same order as the board's cells. Like on
+// an ultimate board, a cell covers its character and the space before it.
+fn get_layer_cell_areas(area: Rect, size: BoardSize) -> Vec<Rect> {
+    let layer_areas = get_layer_areas(area, size);
+    itertools::iproduct!(0..size.height, 0..size.get_columns())
+        .map(|(row, column)| {
+            let layer_area = layer_areas[column / size.width];
+            Rect::new(
+                layer_area.x + 1 + (column % size.width * 2) as u16,
+                layer_area.y + 1 + row as u16,
+                2,
+                1,
+            )
+            .intersection(area)
+        })
+        .collect()
+}
+
+// Draws a cube as its layers, numbered in their titles. A board without
+// layers is drawn as a single untitled box. Cells are picked out
+// the same way as on an ultimate board, and the layers a winning line runs
+// through get green borders.
+fn layers_ui<B: Backend>(
+    f: &mut Frame<B>,
+    area: Rect,
+    size: BoardSize,
+    highlights: &BoardHighlights,
+    get_cellstate_char: impl Fn(usize) -> char,
+) {
+    let columns = size.get_columns();
+    let layer_areas = get_layer_areas(area, size);
+    for (layer, layer_area) in layer_areas.into_iter().enumerate() {
+        let first_column = layer * size.width;
+        let lines: Vec<Spans> = (0..size.height)
+            .map(|row| {
+                let mut spans = Vec::new();
+                for column in first_column..first_column + size.width {
+                    let cell_index = row * columns + column;
+                    let style = if highlights.cursor == Some(cell_index) {
+                        Style::default().fg(Color::Black).bg(Color::Cyan)
+                    } else if highlights.winning_cells.contains(&cell_index) {
+                        Style::default()
+                            .fg(Color::Green)
+                            .add_modifier(Modifier::BOLD)
+                    } else if highlights.last_move == Some(cell_index) {
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    };
+                    spans.push(Span::raw(" "));
+                    spans.push(Span::styled(
+                        get_cellstate_char(cell_index).to_string(),
+                        style,
+                    ));
+                }
+                Spans::from(spans)
+            })
+            .collect();
+
+        let has_winning_cell = highlights
+            .winning_cells
+            .iter()
+            .any(|cell_index| cell_index % columns / size.width == layer);
+        let border_style = match has_winning_cell {
+            true => Style::default().fg(Color::Green),
+            false => Style::default().fg(Color::Red),
+        };
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(border_style);
+        if size.layers > 1 {
+            block = block.title(format!("Layer {}", layer + 1));
+        }
+        f.render_widget(Paragraph::new(lines).block(block), layer_area);
+    }
+}
+
+// A line for the Players box, like "> X  Alice (Human)", where the arrow
+// marks whose turn it is. Computers are named after their kind unless
+// they've been given a name, so their kind is only shown once.
+fn get_seat_line(seat: &SeatInfo, to_move: bool) -> String {
+    let arrow = if to_move { ">" } else { " " };
+    let kind = match seat.kind {
+        "Human" => String::from("Human"),
+        "External" => String::from("Engine"),
+        kind => format!("{} AI", kind),
+    };
+    match seat.name == seat.kind {
+        true => format!("{} {:?}  {}", arrow, seat.marker, kind),
+        false => format!("{} {:?}  {} ({})", arrow, seat.marker, seat.name, kind),
+    }
+}
+
+fn replay_ui<B: Backend>(f: &mut Frame<B>, replay: &Replay, file_name: &str) {
+    let (left_chunks, center_box) = board_screen_layout(f.size());
+    let saved_game = replay.get_saved_game();
+
+    let last_move = match replay.get_last_move() {
+        Some(player_move) => save::cell_to_notation(player_move),
+        None => String::from("-"),
+    };
+    let to_move = match replay.get_turn_marker() {
+        Some(Marker::X) => format!("{} (X) to move", saved_game.player1),
+        Some(Marker::O) => format!("{} (O) to move", saved_game.player2),
+        None => String::from("Game over"),
+    };
+    let result = match replay.get_result() {
+        Some(Winner::Player1) => format!("Result: {} (X) won", saved_game.player1),
+        Some(Winner::Player2) => format!("Result: {} (O) won", saved_game.player2),
+        Some(Winner::None) => String::from("Result: tie"),
+        None => String::from("Result: unfinished"),
+    };
+    let lines: Vec<ListItem> = [
+        format!(
+            "Move {} of {}",
+            replay.get_position(),
+            replay.get_move_count()
+        ),
+        format!("Last move: {}", last_move),
+        to_move,
+        String::new(),
+        result,
+    ]
+    .iter()
+    .map(|line| ListItem::new(Span::raw(line.clone())))
+    .collect();
+    let info = List::new(lines).block(Block::default().borders(Borders::ALL).title(file_name));
+    f.render_widget(info, left_chunks[0]);
+
+    let controls: Vec<ListItem> = [
+        "left / right: back / forward",
+        "home / end: first / last move",
+        "q: back to menu",
+    ]
+    .iter()
+    .map(|&line| ListItem::new(Span::raw(line)))
+    .collect();
+    let controls =
+        List::new(controls).block(Block::default().borders(Borders::ALL).title("Replay"));
+    f.render_widget(controls, left_chunks[1]);
+
+    // There's no cursor to move in a replay, so the last move played gets
+    // the cursor's border too. That makes it easy to see what changed.
+    let size = replay.get_board_size();
+    let get_index = |cell: &CellCoord| cell.row * size.get_columns() + cell.column;
+    let last_move = replay.get_last_move().as_ref().map(get_index);
+    let highlights = BoardHighlights {
+        cursor: last_move,
+        last_move,
+        winning_cells: replay.get_winning_line().iter().map(get_index).collect(),
+        hint: None,
+    };
+    if size.layers > 1 || saved_game.variant == Variant::Gravity {
+        let area = get_layers_area(f.size(), size);
+        layers_ui(f, area, size, &highlights, |cell_index| {
+            replay.get_cellstate_char(cell_index)
+        });
+    } else {
+        board_grid_ui(
+            f,
+            center_box,
+            size.width,
+            size.height,
+            &highlights,
+            |cell_index| replay.get_cellstate_char(cell_index),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_clicked_list_items() {
+        // Items are on the lines between the top and bottom borders.
+        let area = Rect::new(10, 5, 20, 6);
+        assert_eq!(get_list_item_at(area, 3, 12, 6), Some(0));
+        assert_eq!(get_list_item_at(area, 3, 28, 8), Some(2));
+        assert_eq!(get_list_item_at(area, 3, 12, 9), None);
+        assert_eq!(get_list_item_at(area, 3, 12, 5), None);
+        assert_eq!(get_list_item_at(area, 3, 10, 6), None);
+        assert_eq!(get_list_item_at(area, 3, 29, 6), None);
+    }
+
+    #[test]
+    fn menus_take_keys_and_clicks() {
+        let key = |code| Event::Key(event::KeyEvent::new(code, event::KeyModifiers::NONE));
+        let area = Rect::new(10, 5, 20, 6);
+        let mut menu = MenuList::with_items(vec![EndMenuEntry::PlayAgain, EndMenuEntry::Exit]);
+
+        assert_eq!(menu.handle_event(&key(KeyCode::Down), area), None);
+        assert_eq!(
+            menu.handle_event(&key(KeyCode::Enter), area),
+            Some(MenuChoice::Picked(EndMenuEntry::Exit))
+        );
+        assert_eq!(menu.handle_event(&key(KeyCode::Char('s')), area), None);
+        assert_eq!(
+            menu.handle_event(&key(KeyCode::Esc), area),
+            Some(MenuChoice::Back)
+        );
+
+        let click = Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 12,
+            row: 6,
+            modifiers: event::KeyModifiers::NONE,
+        });
+        assert_eq!(
+            menu.handle_event(&click, area),
+            Some(MenuChoice::Picked(EndMenuEntry::PlayAgain))
+        );
+    }
+
+    #[test]
+    fn names_are_only_leaked_once() {
+        let mut app = App::new();
+        app.set_player_name(0, "Alice");
+        let first = app.get_player_name(0);
+        app.set_player_name(0, "Bob");
+        app.set_player_name(0, "Alice");
+        assert!(std::ptr::eq(first, app.get_player_name(0)));
+        assert_eq!(app.get_player_name(1), "Player 2");
+        assert_eq!(app.leaked_names, vec!["Alice", "Player 2"]);
+    }
+
+    #[test]
+    fn lobby_offers_every_opponent_the_server_takes() {
+        let entries = get_lobby_entries(&[(7, Marker::O)]);
+        assert_eq!(entries[0], LobbyEntry::Join(7, Marker::O));
+        let opponents: Vec<PlayerTypeEntry> = entries
+            .iter()
+            .filter_map(|entry| match entry {
+                LobbyEntry::Create(opponent) => Some(*opponent),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(opponents.len(), 6);
+        for opponent in opponents {
+            assert_eq!(
+                PlayerTypeEntry::from_kind(opponent.get_name()),
+                Some(opponent)
+            );
+        }
+    }
+
+    #[test]
+    fn finds_clicked_cells() {
+        // Cells are 5 columns wide and 3 rows tall.
+        let area = Rect::new(40, 10, 40, 12);
+        assert_eq!(get_cell_at(area, 3, 3, 40, 10), Some(0));
+        assert_eq!(get_cell_at(area, 3, 3, 46, 12), Some(1));
+        assert_eq!(get_cell_at(area, 3, 3, 52, 18), Some(8));
+        assert_eq!(get_cell_at(area, 3, 3, 55, 18), None);
+        assert_eq!(get_cell_at(area, 3, 3, 40, 19), None);
+        assert_eq!(get_cell_at(area, 3, 3, 39, 10), None);
+    }
+
+    #[test]
+    fn only_left_presses_are_clicks() {
+        let mouse_event = |kind| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column: 3,
+                row: 4,
+                modifiers: event::KeyModifiers::NONE,
+            })
+        };
+        assert_eq!(
+            get_click(&mouse_event(MouseEventKind::Down(MouseButton::Left))),
+            Some((3, 4))
+        );
+        assert_eq!(
+            get_click(&mouse_event(MouseEventKind::Up(MouseButton::Left))),
+            None
+        );
+        assert_eq!(
+            get_click(&mouse_event(MouseEventKind::Down(MouseButton::Right))),
+            None
+        );
+        assert_eq!(get_click(&Event::Resize(80, 24)), None);
+    }
+
+    #[test]
+    fn finds_clicked_ultimate_cells() {
+        let area = get_ultimate_grid_area(Rect::new(0, 0, 120, 40));
+        assert_eq!((area.width, area.height), (27, 15));
+        let cell_areas = get_ultimate_cell_areas(area);
+        let get_cell = |column, row| {
+            cell_areas
+                .iter()
+                .position(|&cell_area| is_inside(cell_area, area.x + column, area.y + row))
+        };
+        // Each small board has a border, and its cells are two columns apart.
+        assert_eq!(get_cell(0, 0), None);
+        assert_eq!(get_cell(1, 1), Some(0));
+        assert_eq!(get_cell(2, 1), Some(0));
+        assert_eq!(get_cell(3, 2), Some(10));
+        assert_eq!(get_cell(8, 1), None);
+        assert_eq!(get_cell(10, 6), Some(30));
+        assert_eq!(get_cell(24, 13), Some(80));
+        assert_eq!(get_cell(25, 13), None);
+    }
+
+    #[test]
+    fn finds_clicked_layer_cells() {
+        let size = BoardSize::new(4, 4, 4);
+        let area = get_layers_area(Rect::new(0, 0, 120, 40), size);
+        assert_eq!((area.width, area.height), (44, 6));
+        let cell_areas = get_layer_cell_areas(area, size);
+        let get_cell = |column, row| {
+            cell_areas
+                .iter()
+                .position(|&cell_area| is_inside(cell_area, area.x + column, area.y + row))
+        };
+        // The layers sit side by side, so the second layer's first cell is
+        // the fifth cell of the board's top row.
+        assert_eq!(get_cell(0, 0), None);
+        assert_eq!(get_cell(1, 1), Some(0));
+        assert_eq!(get_cell(12, 1), Some(4));
+        assert_eq!(get_cell(41, 4), Some(63));
+        assert_eq!(get_cell(42, 4), None);
+    }
+
+    #[test]
+    fn tab_moves_between_layers() {
+        let mut app = App::new();
+        app.game = new_qubic_game(
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::Human,
+            [None, None],
+            0,
+        )
+        .unwrap();
+        app.selected_cell = 17;
+        app.update_selected_cell(&KeyCode::Tab);
+        assert_eq!(app.selected_cell, 21);
+        app.update_selected_cell(&KeyCode::BackTab);
+        app.update_selected_cell(&KeyCode::BackTab);
+        assert_eq!(app.selected_cell, 17);
+        // The arrows cross from one layer into the next.
+        app.selected_cell = 3;
+        app.update_selected_cell(&KeyCode::Right);
+        assert_eq!(app.selected_cell, 4);
+
+        assert!(new_qubic_game(
+            PlayerTypeEntry::MinimaxComp,
+            PlayerTypeEntry::Human,
+            [None, None],
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn gravity_cursor_picks_columns() {
+        let mut app = App::new();
+        app.game = new_gravity_game(
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::Human,
+            [None, None],
+            Board::with_gravity(7, 6, 4),
+            0,
+        )
+        .unwrap();
+        app.selected_cell = 3;
+        app.update_selected_cell(&KeyCode::Down);
+        app.update_selected_cell(&KeyCode::Right);
+        assert_eq!(app.selected_cell, 4);
+        // The marker lands at the bottom of the column, and the next one on
+        // top of it.
+        assert_eq!(app.get_selected_coord(), CellCoord::new(5, 4));
+        app.game.make_human_move(app.get_selected_coord());
+        assert_eq!(app.get_selected_coord(), CellCoord::new(4, 4));
+
+        assert!(new_gravity_game(
+            PlayerTypeEntry::OptimalComp,
+            PlayerTypeEntry::Human,
+            [None, None],
+            Board::with_gravity(7, 6, 4),
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn switches_wild_markers() {
+        let mut app = App::new();
+        app.game = new_wild_game(
+            PlayerTypeEntry::Human,
+            PlayerTypeEntry::MinimaxComp,
+            [None, None],
+            0,
+        )
+        .unwrap();
+        app.game.switch_marker();
+        assert_eq!(app.game.get_placing_marker(), Some(Marker::O));
+        app.game.make_human_move(CellCoord::new(1, 1));
+        assert_eq!(app.game.get_cellstate_char(4), 'O');
+        // The seat's marker still says whose turn it is.
+        assert_eq!(app.game.get_turn_marker(), Some(Marker::O));
+
+        assert!(new_wild_game(
+            PlayerTypeEntry::OptimalComp,
+            PlayerTypeEntry::Human,
+            [None, None],
+            0
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn describes_seats() {
+        let person = SeatInfo {
+            name: "Alice",
+            kind: "Human",
+            marker: Marker::X,
+        };
+        assert_eq!(get_seat_line(&person, true), "> X  Alice (Human)");
+        let computer = SeatInfo {
+            name: "Optimal",
+            kind: "Optimal",
+            marker: Marker::O,
+        };
+        assert_eq!(get_seat_line(&computer, false), "  O  Optimal AI");
     }
 }
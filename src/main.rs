@@ -1,6 +1,17 @@
-use std::error::Error;
+use std::{env, error::Error};
 
 fn main() -> Result<(), Box<dyn Error>> {
-    miktactoe::run_app()?;
+    // With no arguments we start the TUI. `miktactoe simulate ...` and
+    // `miktactoe tournament ...` play computers against each other without
+    // it, and `miktactoe engine` plays over stdin and stdout for other
+    // programs.
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        None => miktactoe::run_app()?,
+        Some("simulate") => miktactoe::run_simulation(&args[1..])?,
+        Some("tournament") => miktactoe::run_tournament(&args[1..])?,
+        Some("engine") => miktactoe::run_engine(&args[1..])?,
+        Some(command) => return Err(format!("Unknown command: {}", command).into()),
+    }
     Ok(())
 }
@@ -1,14 +1,14 @@
-use super::Player;
+use super::{MiserePlayer, Player, WildPlayer};
 use crate::board::Board;
 use crate::common::*;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use std::{thread, time};
+use rand::SeedableRng;
 
 pub struct BasicAI<'a> {
     pub name: &'a str,
     pub marker: Marker,
-    move_set: Vec<CellCoord>,
+    rng: StdRng,
 }
 
 // BasicAI will make moves with the following priority:
@@ -16,18 +16,11 @@ pub struct BasicAI<'a> {
 //   2. block an opponents winning move
 //   3. move randomly
 impl<'a> Player<'a> for BasicAI<'a> {
-    fn new(name: &'a str, marker: Marker) -> BasicAI {
-        let mut move_set: Vec<CellCoord> = itertools::iproduct!(0..3, 0..3)
-            .map(|(row, column)| CellCoord::new(row, column))
-            .collect();
-
-        let mut rng = thread_rng();
-        move_set.shuffle(&mut rng);
-
+    fn with_seed(name: &'a str, marker: Marker, seed: u64) -> BasicAI<'a> {
         BasicAI {
             name,
             marker,
-            move_set,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
@@ -39,12 +32,13 @@ impl<'a> Player<'a> for BasicAI<'a> {
         self.name
     }
 
+    fn get_kind(&self) -> &'static str {
+        "Basic"
+    }
+
     fn get_valid_move(&mut self, board: &Board) -> CellCoord {
         //println!("{}'s turn.", self.name);
 
-        // Use a sleep here so it seems like the computer is thinking a bit.
-        thread::sleep(time::Duration::from_secs(1));
-
         // place winning move if there is one
         if let Some(cell_coord) = board.get_winning_move(self.marker) {
             return cell_coord;
@@ -55,17 +49,71 @@ impl<'a> Player<'a> for BasicAI<'a> {
             return cell_coord;
         }
 
-        loop {
-            let player_move = self.move_set.pop().unwrap_or_else(|| {
-                panic!(
-                    "{} ran out of generated moves. You shouldn't need this many.",
-                    self.name
-                )
-            });
+        *board
+            .get_valid_moves()
+            .choose(&mut self.rng)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
 
-            if let Move::Valid = board.validate_move(player_move) {
-                break player_move;
+// In misère, BasicAI turns its rules around:
+//   1. don't complete one of our own lines
+//   2. leave the cells that would complete the opponent's lines for them
+//   3. move randomly
+impl<'a> MiserePlayer<'a> for BasicAI<'a> {
+    fn get_misere_move(&mut self, board: &Board) -> CellCoord {
+        let safe_moves = board.get_safe_moves(self.marker);
+        let opponent_safe_moves = board.get_safe_moves(Marker::opposite(self.marker));
+        let moves_leaving_traps: Vec<CellCoord> = safe_moves
+            .iter()
+            .copied()
+            .filter(|cell_coord| opponent_safe_moves.contains(cell_coord))
+            .collect();
+
+        let moves = if !moves_leaving_traps.is_empty() {
+            moves_leaving_traps
+        } else if !safe_moves.is_empty() {
+            safe_moves
+        } else {
+            board.get_valid_moves()
+        };
+        *moves
+            .choose(&mut self.rng)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
+
+// In wild tic-tac-toe, a line one short of complete can be finished by
+// either player, with whichever marker is already in it, so BasicAI:
+//   1. completes a line if it can
+//   2. makes a move that doesn't leave a line one short for the opponent
+//   3. moves randomly
+impl<'a> WildPlayer<'a> for BasicAI<'a> {
+    fn get_wild_move(&mut self, board: &Board) -> WildMove {
+        for &marker in [Marker::X, Marker::O].iter() {
+            if let Some(cell) = board.get_winning_move(marker) {
+                return WildMove::new(cell, marker);
             }
         }
+
+        let wild_moves = board.get_wild_moves();
+        let safe_moves: Vec<WildMove> = wild_moves
+            .iter()
+            .copied()
+            .filter(|wild_move| {
+                let mut next_board = board.clone();
+                next_board.place_marker(wild_move.cell, wild_move.marker);
+                next_board.get_winning_move(Marker::X).is_none()
+                    && next_board.get_winning_move(Marker::O).is_none()
+            })
+            .collect();
+
+        let moves = match safe_moves.is_empty() {
+            true => wild_moves,
+            false => safe_moves,
+        };
+        *moves
+            .choose(&mut self.rng)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
     }
 }
@@ -1,14 +1,14 @@
 use super::Player;
 use crate::board::Board;
 use crate::common::*;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use std::{thread, time};
+use rand::SeedableRng;
 
 pub struct ForkingAI<'a> {
     pub name: &'a str,
     pub marker: Marker,
-    move_set: Vec<CellCoord>,
+    rng: StdRng,
 }
 
 // ForkingAI will make moves with the following priority:
@@ -17,18 +17,11 @@ pub struct ForkingAI<'a> {
 //   3. create a fork if possible
 //   3. move randomly
 impl<'a> Player<'a> for ForkingAI<'a> {
-    fn new(name: &'a str, marker: Marker) -> ForkingAI {
-        let mut move_set: Vec<CellCoord> = itertools::iproduct!(0..3, 0..3)
-            .map(|(row, column)| CellCoord::new(row, column))
-            .collect();
-
-        let mut rng = thread_rng();
-        move_set.shuffle(&mut rng);
-
+    fn with_seed(name: &'a str, marker: Marker, seed: u64) -> ForkingAI<'a> {
         ForkingAI {
             name,
             marker,
-            move_set,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
@@ -40,12 +33,13 @@ impl<'a> Player<'a> for ForkingAI<'a> {
         self.name
     }
 
+    fn get_kind(&self) -> &'static str {
+        "Forking"
+    }
+
     fn get_valid_move(&mut self, board: &Board) -> CellCoord {
         //println!("{}'s turn.", self.name);
 
-        // Use a sleep here so it seems like the computer is thinking a bit.
-        thread::sleep(time::Duration::from_secs(1));
-
         // place winning move if there is one
         if let Some(cell_coord) = board.get_winning_move(self.marker) {
             return cell_coord;
@@ -59,28 +53,20 @@ impl<'a> Player<'a> for ForkingAI<'a> {
         // make a fork if possible
         let forking_moves = board.get_forking_move(self.marker);
         if !forking_moves.is_empty() {
-            println!("making a fork");
+            //println!("making a fork");
             return forking_moves[0];
         }
 
         // block opponent's fork
         let opp_forking_moves = board.get_forking_move(Marker::opposite(self.marker));
         if !opp_forking_moves.is_empty() {
-            println!("blocking forking move");
-            return forking_moves[0];
+            //println!("blocking forking move");
+            return opp_forking_moves[0];
         }
 
-        loop {
-            let player_move = self.move_set.pop().unwrap_or_else(|| {
-                panic!(
-                    "{} ran out of generated moves. You shouldn't need this many.",
-                    self.name
-                )
-            });
-
-            if let Move::Valid = board.validate_move(player_move) {
-                break player_move;
-            }
-        }
+        *board
+            .get_valid_moves()
+            .choose(&mut self.rng)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
     }
 }
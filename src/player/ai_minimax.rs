@@ -0,0 +1,593 @@
+use super::{MiserePlayer, Player, UltimatePlayer, WildPlayer};
+use crate::board::{Board, BoardState};
+use crate::common::*;
+use crate::ultimate::UltimateBoard;
+
+// Scores are relative to the player whose turn it is. A win is worth more the
+// sooner it happens, so the search prefers quick wins and slow losses. It's
+// also worth more than any guess at an unfinished position below.
+const WIN_SCORE: i32 = 100_000;
+
+// Ultimate tic-tac-toe's game tree is far too big to search to the end, so
+// the search stops ULTIMATE_DEPTH moves ahead and guesses how good the
+// position is instead. The same goes for a board with gravity, which is
+// usually Connect Four's 7x6, and for any board bigger than 3x3. Without
+// gravity every cell can be a move, so the bigger the board, the less far
+// ahead the search can look. It goes as deep as it can while looking at
+// about SEARCH_BUDGET positions, but always at least MIN_DEPTH moves, so it
+// sees the other player's reply.
+const ULTIMATE_DEPTH: i32 = 4;
+const GRAVITY_DEPTH: i32 = 6;
+const SEARCH_BUDGET: usize = 200_000;
+const MIN_DEPTH: i32 = 2;
+
+pub struct MinimaxAI<'a> {
+    pub name: &'a str,
+    pub marker: Marker,
+}
+
+// MinimaxAI doesn't use any of the heuristics from OptimalAI. Instead, it
+// searches the whole game tree from the current position and picks the move
+// with the best guaranteed outcome. The tic-tac-toe tree is small enough to
+// search completely, and alpha-beta pruning skips branches that can't change
+// the result, so this stays fast.
+//
+// Boards with gravity or bigger than 3x3 are too big to search to the end,
+// so there the search stops a few moves ahead, the same as for ultimate
+// below.
+//
+// In misère, completing a line loses instead. That's the only thing the
+// search needs to know, so `misere` is passed all the way down to where a
+// completed line gets scored, or an unfinished one gets guessed at.
+impl<'a> MinimaxAI<'a> {
+    fn find_best_move(&self, board: &Board, misere: bool) -> Option<CellCoord> {
+        let mut best_move = None;
+        let mut alpha = -WIN_SCORE - 1;
+        let beta = WIN_SCORE + 1;
+
+        for player_move in get_search_order(board) {
+            let score = Self::score_move(board, player_move, self.marker, misere, 0, alpha, beta);
+            if best_move.is_none() || score > alpha {
+                alpha = score;
+                best_move = Some(player_move);
+            }
+        }
+        best_move
+    }
+
+    // Scores placing `marker` at `player_move` from the point of view of the
+    // player who owns `marker`. This is the negamax form of minimax: our
+    // opponent's best score is our worst, so we negate the child's score and
+    // swap the alpha-beta window.
+    fn score_move(
+        board: &Board,
+        player_move: CellCoord,
+        marker: Marker,
+        misere: bool,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let mut next_board = board.clone();
+        next_board.place_marker(player_move, marker);
+
+        match next_board.check_board_state(player_move, marker) {
+            // A loss is worth more the later it happens.
+            BoardState::Win(_) if misere => depth - WIN_SCORE,
+            BoardState::Win(_) => WIN_SCORE - depth,
+            BoardState::Tie => 0,
+            // In misère, the lines we could still finish are a danger
+            // instead of a chance, so the guess flips too.
+            BoardState::Playing if matches!(get_search_depth(&next_board), Some(max) if depth + 1 >= max) => {
+                match misere {
+                    true => -evaluate(&next_board, marker),
+                    false => evaluate(&next_board, marker),
+                }
+            }
+            BoardState::Playing => -Self::search(
+                &next_board,
+                Marker::opposite(marker),
+                misere,
+                depth + 1,
+                -beta,
+                -alpha,
+            ),
+        }
+    }
+
+    fn search(
+        board: &Board,
+        marker: Marker,
+        misere: bool,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let mut best_score = -WIN_SCORE - 1;
+        for player_move in get_search_order(board) {
+            let score = Self::score_move(board, player_move, marker, misere, depth, alpha, beta);
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+}
+
+// The same negamax search as above, for ultimate tic-tac-toe. Cloning the
+// whole grid of boards for every move adds up, so moves are placed and then
+// taken back on one copy of the grid.
+impl<'a> MinimaxAI<'a> {
+    fn find_best_ultimate_move(&self, board: &UltimateBoard) -> Option<CellCoord> {
+        let mut board = board.clone();
+        let mut best_move = None;
+        let mut alpha = -WIN_SCORE - 1;
+        let beta = WIN_SCORE + 1;
+
+        for player_move in board.get_valid_moves() {
+            let score =
+                Self::score_ultimate_move(&mut board, player_move, self.marker, 0, alpha, beta);
+            if best_move.is_none() || score > alpha {
+                alpha = score;
+                best_move = Some(player_move);
+            }
+        }
+        best_move
+    }
+
+    fn score_ultimate_move(
+        board: &mut UltimateBoard,
+        player_move: CellCoord,
+        marker: Marker,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let next_board = board.get_next_board();
+        let score = match board.place_marker(player_move, marker) {
+            BoardState::Win(_) => WIN_SCORE - depth,
+            BoardState::Tie => 0,
+            BoardState::Playing if depth + 1 >= ULTIMATE_DEPTH => evaluate_ultimate(board, marker),
+            BoardState::Playing => {
+                -Self::search_ultimate(board, Marker::opposite(marker), depth + 1, -beta, -alpha)
+            }
+        };
+        board.remove_marker(player_move, next_board);
+        score
+    }
+
+    fn search_ultimate(
+        board: &mut UltimateBoard,
+        marker: Marker,
+        depth: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let mut best_score = -WIN_SCORE - 1;
+        for player_move in board.get_valid_moves() {
+            let score = Self::score_ultimate_move(board, player_move, marker, depth, alpha, beta);
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+}
+
+// The same search again, for wild tic-tac-toe. Completing a line with
+// either marker wins, so a move is a cell and a marker, and there are twice
+// as many of them to search.
+impl<'a> MinimaxAI<'a> {
+    fn find_best_wild_move(&self, board: &Board) -> Option<WildMove> {
+        let mut best_move = None;
+        let mut alpha = -WIN_SCORE - 1;
+        let beta = WIN_SCORE + 1;
+
+        for wild_move in board.get_wild_moves() {
+            let score = Self::score_wild_move(board, wild_move, 0, alpha, beta);
+            if best_move.is_none() || score > alpha {
+                alpha = score;
+                best_move = Some(wild_move);
+            }
+        }
+        best_move
+    }
+
+    fn score_wild_move(
+        board: &Board,
+        wild_move: WildMove,
+        depth: i32,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let mut next_board = board.clone();
+        next_board.place_marker(wild_move.cell, wild_move.marker);
+
+        match next_board.check_board_state(wild_move.cell, wild_move.marker) {
+            BoardState::Win(_) => WIN_SCORE - depth,
+            BoardState::Tie => 0,
+            // A line left one short can be finished by the opponent with
+            // either marker, so they win on their next move. Scoring that
+            // straight away, instead of searching it, keeps the search fast.
+            BoardState::Playing
+                if next_board.get_winning_move(Marker::X).is_some()
+                    || next_board.get_winning_move(Marker::O).is_some() =>
+            {
+                depth + 1 - WIN_SCORE
+            }
+            BoardState::Playing => -Self::search_wild(&next_board, depth + 1, -beta, -alpha),
+        }
+    }
+
+    fn search_wild(board: &Board, depth: i32, mut alpha: i32, beta: i32) -> i32 {
+        let mut best_score = -WIN_SCORE - 1;
+        for wild_move in board.get_wild_moves() {
+            let score = Self::score_wild_move(board, wild_move, depth, alpha, beta);
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_score
+    }
+}
+
+// Guesses how good an unfinished ultimate position is for `marker`. Lines of
+// won boards on the big board count the most, then the boards themselves,
+// then lines on the small boards that are still being played.
+fn evaluate_ultimate(board: &UltimateBoard, marker: Marker) -> i32 {
+    let mine = match marker {
+        Marker::X => 'X',
+        Marker::O => 'O',
+    };
+    // A board that filled up without a winner blocks every line through it
+    // on the big board, so it's marked with a # that belongs to nobody.
+    let get_owner = |board_index: usize| match board.get_board_winner(board_index) {
+        '_' if !board.is_board_open(board_index) => '#',
+        winner => winner,
+    };
+
+    let meta_board = board.get_meta_board();
+    let mut score = 0;
+    for set_type in meta_board.get_sets() {
+        let owners = meta_board
+            .get_set_coords(set_type)
+            .into_iter()
+            .map(|cell| get_owner(cell.row * meta_board.get_width() + cell.column));
+        score += 20 * score_line(owners, mine);
+    }
+    for board_index in 0..meta_board.get_width() * meta_board.get_height() {
+        match get_owner(board_index) {
+            '_' => score += evaluate(board.get_board(board_index), marker),
+            '#' => {}
+            owner if owner == mine => score += 10,
+            _ => score -= 10,
+        }
+    }
+    score
+}
+
+// Guesses how good an unfinished position on a single board is for
+// `marker`, from the lines each player could still complete.
+fn evaluate(board: &Board, marker: Marker) -> i32 {
+    let mine = match marker {
+        Marker::X => 'X',
+        Marker::O => 'O',
+    };
+    board
+        .get_sets()
+        .into_iter()
+        .map(|set_type| {
+            let cells = board
+                .get_set_coords(set_type)
+                .into_iter()
+                .map(|cell| board.get_cellstate_char(cell.row * board.get_width() + cell.column));
+            score_line(cells, mine)
+        })
+        .sum()
+}
+
+// How many moves ahead to search on `board`, or None to search to the end.
+fn get_search_depth(board: &Board) -> Option<i32> {
+    let cells = board.get_width() * board.get_height();
+    if board.has_gravity() {
+        return Some(GRAVITY_DEPTH);
+    }
+    if cells <= 9 {
+        return None;
+    }
+    let mut depth = MIN_DEPTH;
+    while cells.pow(depth as u32 + 1) <= SEARCH_BUDGET {
+        depth += 1;
+    }
+    Some(depth)
+}
+
+// With gravity, moves near the middle take part in the most lines, so
+// searching them first lets alpha-beta prune more of the rest. Everything
+// else is quick enough to search in the board's own order.
+fn get_search_order(board: &Board) -> Vec<CellCoord> {
+    let mut moves = board.get_valid_moves();
+    if board.has_gravity() {
+        let middle = board.get_width() / 2;
+        moves.sort_by_key(|cell| (cell.column as isize - middle as isize).abs());
+    }
+    moves
+}
+
+// A line only one player can still complete is worth more to them the
+// closer they are to completing it. Lines both players have a marker in,
+// or that are blocked, aren't worth anything.
+fn score_line(cells: impl Iterator<Item = char>, mine: char) -> i32 {
+    let (mut my_count, mut their_count) = (0, 0);
+    for cell in cells {
+        match cell {
+            '_' => {}
+            '#' => return 0,
+            cell if cell == mine => my_count += 1,
+            _ => their_count += 1,
+        }
+    }
+    match (my_count, their_count) {
+        (count, 0) => count * count,
+        (0, count) => -count * count,
+        _ => 0,
+    }
+}
+
+impl<'a> Player<'a> for MinimaxAI<'a> {
+    fn with_seed(name: &'a str, marker: Marker, _seed: u64) -> MinimaxAI<'a> {
+        MinimaxAI { name, marker }
+    }
+
+    fn get_marker(&self) -> Marker {
+        self.marker
+    }
+
+    fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "Minimax"
+    }
+
+    fn get_valid_move(&mut self, board: &Board) -> CellCoord {
+        self.find_best_move(board, false)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
+
+impl<'a> MiserePlayer<'a> for MinimaxAI<'a> {
+    fn get_misere_move(&mut self, board: &Board) -> CellCoord {
+        self.find_best_move(board, true)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
+
+impl<'a> WildPlayer<'a> for MinimaxAI<'a> {
+    fn get_wild_move(&mut self, board: &Board) -> WildMove {
+        self.find_best_wild_move(board)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
+
+impl<'a> UltimatePlayer<'a> for MinimaxAI<'a> {
+    fn get_ultimate_move(&mut self, board: &UltimateBoard) -> CellCoord {
+        self.find_best_ultimate_move(board)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn makes_winning_move() {
+        let marker = Marker::X;
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(0, 0), marker);
+        board.place_marker(CellCoord::new(1, 0), Marker::O);
+        board.place_marker(CellCoord::new(0, 1), marker);
+        board.place_marker(CellCoord::new(1, 1), Marker::O);
+
+        let minimax_ai = MinimaxAI::new("TestMinimax", marker);
+        assert_eq!(
+            minimax_ai.find_best_move(&board, false),
+            Some(CellCoord::new(0, 2))
+        );
+    }
+
+    #[test]
+    fn blocks_winning_move() {
+        let opponent = Marker::X;
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(0, 0), opponent);
+        board.place_marker(CellCoord::new(1, 1), Marker::O);
+        board.place_marker(CellCoord::new(0, 1), opponent);
+
+        let minimax_ai = MinimaxAI::new("TestMinimax", Marker::opposite(opponent));
+        assert_eq!(
+            minimax_ai.find_best_move(&board, false),
+            Some(CellCoord::new(0, 2))
+        );
+    }
+
+    #[test]
+    fn blocks_corner_fork() {
+        // X holds opposite corners and O the center. Taking a corner lets X
+        // fork, so O has to answer on an edge.
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(0, 0), Marker::X);
+        board.place_marker(CellCoord::new(1, 1), Marker::O);
+        board.place_marker(CellCoord::new(2, 2), Marker::X);
+
+        let minimax_ai = MinimaxAI::new("TestMinimax", Marker::O);
+        let player_move = minimax_ai.find_best_move(&board, false).unwrap();
+        assert_eq!(board.get_cell_type(player_move), CellType::Edge);
+    }
+
+    #[test]
+    fn wins_ultimate_games() {
+        // X already has the middle left and bottom left boards, and the top
+        // row of the top left board only needs c3.
+        let moves = [
+            "a1", "a2", "a4", "a3", "a7", "b1", "d1", "c1", "g1", "c2", "g4", "b3", "e7", "d2",
+            "b4", "d3", "b7", "e1", "f1", "g2", "c4", "g3", "c7", "h1", "e2", "d4", "b2", "d5",
+        ];
+        let mut board = UltimateBoard::new();
+        for (move_number, notation) in moves.iter().enumerate() {
+            let marker = match move_number % 2 {
+                0 => Marker::X,
+                _ => Marker::O,
+            };
+            board.place_marker(crate::save::notation_to_cell(notation).unwrap(), marker);
+        }
+
+        let minimax_ai = MinimaxAI::new("TestMinimax", Marker::X);
+        assert_eq!(
+            minimax_ai.find_best_ultimate_move(&board),
+            Some(CellCoord::new(2, 2))
+        );
+    }
+
+    #[test]
+    fn blocks_gravity_threats() {
+        // X has three along the bottom row, and only d6 stops the fourth.
+        let mut board = Board::with_gravity(7, 6, 4);
+        for (column, marker) in [
+            (0, Marker::X),
+            (0, Marker::O),
+            (1, Marker::X),
+            (1, Marker::O),
+        ] {
+            board.place_marker(board.get_drop_cell(column).unwrap(), marker);
+        }
+        board.place_marker(CellCoord::new(5, 2), Marker::X);
+
+        let minimax_ai = MinimaxAI::new("TestMinimax", Marker::O);
+        assert_eq!(
+            minimax_ai.find_best_move(&board, false),
+            Some(CellCoord::new(5, 3))
+        );
+
+        // On an empty board, it starts in the middle column.
+        let minimax_ai = MinimaxAI::new("TestMinimax", Marker::X);
+        assert_eq!(
+            minimax_ai.find_best_move(&Board::with_gravity(7, 6, 4), false),
+            Some(CellCoord::new(5, 3))
+        );
+    }
+
+    #[test]
+    fn blocks_threats_on_big_boards() {
+        // X needs d1 to finish four in a row, and the search has to stop
+        // short of the end of the game to find that in time.
+        let mut board = Board::with_size(5, 5, 4);
+        for (cell, marker) in [
+            (CellCoord::new(0, 0), Marker::X),
+            (CellCoord::new(4, 4), Marker::O),
+            (CellCoord::new(0, 1), Marker::X),
+            (CellCoord::new(3, 4), Marker::O),
+            (CellCoord::new(0, 2), Marker::X),
+        ] {
+            board.place_marker(cell, marker);
+        }
+        assert_eq!(get_search_depth(&board), Some(3));
+
+        let minimax_ai = MinimaxAI::new("TestMinimax", Marker::O);
+        assert_eq!(
+            minimax_ai.find_best_move(&board, false),
+            Some(CellCoord::new(0, 3))
+        );
+    }
+
+    #[test]
+    fn wins_wild_games_from_the_start() {
+        // The first player can always win wild tic-tac-toe, and the search
+        // finds the way.
+        let mut board = Board::new();
+        let mut players = [
+            MinimaxAI::new("TestMinimax 1", Marker::X),
+            MinimaxAI::new("TestMinimax 2", Marker::O),
+        ];
+        for move_number in 0.. {
+            let wild_move = players[move_number % 2].get_wild_move(&board);
+            board.place_marker(wild_move.cell, wild_move.marker);
+            match board.check_board_state(wild_move.cell, wild_move.marker) {
+                BoardState::Win(_) => {
+                    assert_eq!(move_number % 2, 0, "the second player won");
+                    break;
+                }
+                BoardState::Tie => panic!("the first player didn't win"),
+                BoardState::Playing => {}
+            }
+        }
+    }
+
+    #[test]
+    fn self_play_is_a_tie() {
+        let mut board = Board::new();
+        let mut marker = Marker::X;
+        loop {
+            let minimax_ai = MinimaxAI::new("TestMinimax", marker);
+            let player_move = minimax_ai.find_best_move(&board, false).unwrap();
+            board.place_marker(player_move, marker);
+            match board.check_board_state(player_move, marker) {
+                BoardState::Win(_) => panic!("{:?} won a game between perfect players", marker),
+                BoardState::Tie => break,
+                BoardState::Playing => marker = Marker::opposite(marker),
+            }
+        }
+    }
+
+    #[test]
+    fn avoids_completing_misere_lines() {
+        let marker = Marker::X;
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(0, 0), marker);
+        board.place_marker(CellCoord::new(1, 0), Marker::O);
+        board.place_marker(CellCoord::new(0, 1), marker);
+        board.place_marker(CellCoord::new(1, 1), Marker::O);
+
+        let minimax_ai = MinimaxAI::new("TestMinimax", marker);
+        let player_move = minimax_ai.find_best_move(&board, true).unwrap();
+        assert_ne!(player_move, CellCoord::new(0, 2));
+    }
+
+    #[test]
+    fn stays_out_of_lines_on_big_misere_boards() {
+        // A 5x5 board is searched a few moves ahead and then guessed at. In
+        // misère the center is the worst opening, being in the most lines.
+        let board = Board::with_size(5, 5, 4);
+        let minimax_ai = MinimaxAI::new("TestMinimax", Marker::X);
+        assert_eq!(
+            minimax_ai.find_best_move(&board, false),
+            Some(CellCoord::new(2, 2))
+        );
+        let player_move = minimax_ai.find_best_move(&board, true).unwrap();
+        assert_eq!(board.get_cell_type(player_move), CellType::Corner);
+    }
+
+    #[test]
+    fn misere_self_play_is_a_tie() {
+        let mut board = Board::new();
+        let mut marker = Marker::X;
+        loop {
+            let minimax_ai = MinimaxAI::new("TestMinimax", marker);
+            let player_move = minimax_ai.find_best_move(&board, true).unwrap();
+            board.place_marker(player_move, marker);
+            match board.check_board_state(player_move, marker) {
+                BoardState::Win(_) => panic!("{:?} completed a line in misère", marker),
+                BoardState::Tie => break,
+                BoardState::Playing => marker = Marker::opposite(marker),
+            }
+        }
+    }
+}
@@ -1,39 +1,91 @@
-use super::Player;
-use crate::board::{Board, SetType};
+use super::{MiserePlayer, Player};
+use crate::board::Board;
 use crate::common::*;
-use std::{thread, time};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::fmt;
+
+// Why OptimalAI picked a move, in the order it checks for them.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum MoveReason {
+    Win,
+    Block,
+    Fork,
+    BlockFork,
+    ForceDefense,
+    Center,
+    Corner,
+    Edge,
+    Open,
+    // The rest are for misère, where completing a line loses.
+    Mirror,
+    Safe,
+    Forced,
+}
+
+impl MoveReason {
+    // A few words on why the move is good, for people asking for a hint.
+    pub fn get_description(self) -> &'static str {
+        match self {
+            MoveReason::Win => "completes a line",
+            MoveReason::Block => "stops the other player completing a line",
+            MoveReason::Fork => "threatens two lines at once",
+            MoveReason::BlockFork => "stops the other player threatening two lines",
+            MoveReason::ForceDefense => "makes the other player block",
+            MoveReason::Center => "the center is in the most lines",
+            MoveReason::Corner => "corners are in more lines than edges",
+            MoveReason::Edge => "only edges are left",
+            MoveReason::Open => "no rule applies, so any cell will do",
+            MoveReason::Mirror => "copying them makes them finish first",
+            MoveReason::Safe => "in the fewest lines you can finish",
+            MoveReason::Forced => "every open cell finishes a line",
+        }
+    }
+}
+
+impl fmt::Display for MoveReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let text = match self {
+            MoveReason::Win => "win",
+            MoveReason::Block => "block",
+            MoveReason::Fork => "fork",
+            MoveReason::BlockFork => "block fork",
+            MoveReason::ForceDefense => "force a block",
+            MoveReason::Center => "center",
+            MoveReason::Corner => "corner",
+            MoveReason::Edge => "edge",
+            MoveReason::Open => "open cell",
+            MoveReason::Mirror => "mirror",
+            MoveReason::Safe => "safe cell",
+            MoveReason::Forced => "forced",
+        };
+        write!(f, "{}", text)
+    }
+}
 
 pub struct OptimalAI<'a> {
     pub name: &'a str,
     pub marker: Marker,
+    rng: StdRng,
 }
 
 impl<'a> OptimalAI<'a> {
     fn force_defending_move(
         &self,
         board: &Board,
-        forking_moves: &Vec<CellCoord>,
+        forking_moves: &[CellCoord],
     ) -> Option<CellCoord> {
         // Try to place 2 in a row to force opp to defend
         // without providing them a forking move.
-        let single_marker_sets = board.get_single_marker_sets(self.marker);
+        let potential_fork_sets = board.get_potential_fork_sets(self.marker);
 
-        for (coord, set_type) in single_marker_sets {
-            //println!("Considering {:?}", set_type);
-            let empties: Vec<CellCoord> = self.get_empties(coord, set_type);
-            let both_empties_are_forks = empties.iter().all(|&empty| {
-                if let Some(_) = forking_moves
-                    .iter()
-                    .find(|&&forking_move| forking_move == empty)
-                {
-                    return true;
-                } else {
-                    return false;
-                }
-            });
+        for (_set_type, empties) in potential_fork_sets {
+            //println!("Considering {:?}", _set_type);
+            let both_empties_are_forks = empties.iter().all(|empty| forking_moves.contains(empty));
 
             if both_empties_are_forks {
-                //println!("Both empties are forks for {:?}", set_type);
+                //println!("Both empties are forks for {:?}", _set_type);
                 continue;
             }
 
@@ -47,100 +99,45 @@ impl<'a> OptimalAI<'a> {
         None
     }
 
-    fn get_empties(&self, placed: CellCoord, set_type: SetType) -> Vec<CellCoord> {
-        let mut result = Vec::new();
-        match set_type {
-            SetType::Row(row) => {
-                for col in 0..3 {
-                    let coord = CellCoord::new(row, col);
-                    if placed != coord {
-                        result.push(coord);
-                    }
-                }
-            }
-            SetType::Column(col) => {
-                for row in 0..3 {
-                    let coord = CellCoord::new(row, col);
-                    if placed != coord {
-                        result.push(coord);
-                    }
-                }
-            }
-            SetType::Diag1 => {
-                for index in 0..3 {
-                    let coord = CellCoord::new(index, index);
-                    if placed != coord {
-                        result.push(coord);
-                    }
-                }
-            }
-            SetType::Diag2 => {
-                for index in 0..3 {
-                    let coord = CellCoord::new(index, 2 - index);
-                    if placed != coord {
-                        result.push(coord);
-                    }
-                }
-            }
-        }
-        result
-    }
-}
-
-impl<'a> Player<'a> for OptimalAI<'a> {
-    fn new(name: &'a str, marker: Marker) -> OptimalAI {
-        OptimalAI { name, marker }
-    }
-
-    fn get_marker(&self) -> Marker {
-        self.marker
-    }
-
-    fn get_name(&self) -> &'a str {
-        self.name
-    }
-
-    fn get_valid_move(&mut self, board: &Board) -> CellCoord {
-        //println!("{}'s turn.", self.name);
-
-        // Use a sleep here so it seems like the computer is thinking a bit.
-        thread::sleep(time::Duration::from_secs(1));
-
+    // Picks a move the same way `get_valid_move()` does, and says which rule
+    // picked it. The hint key uses this to explain its suggestions.
+    pub fn get_move_with_reason(&mut self, board: &Board) -> (CellCoord, MoveReason) {
         // place winning move if there is one
         if let Some(cell_coord) = board.get_winning_move(self.marker) {
-            return cell_coord;
+            return (cell_coord, MoveReason::Win);
         }
 
         // block opponent's winning move if they have one
         if let Some(cell_coord) = board.get_winning_move(Marker::opposite(self.marker)) {
             //println!("blocking a winning move");
-            return cell_coord;
+            return (cell_coord, MoveReason::Block);
         }
 
         // make a fork if possible
         let forking_moves = board.get_forking_move(self.marker);
         if !forking_moves.is_empty() {
             //println!("making a fork");
-            return forking_moves[0];
+            return (forking_moves[0], MoveReason::Fork);
         }
 
         // block opponent's fork
         let opp_forking_moves = board.get_forking_move(Marker::opposite(self.marker));
         if opp_forking_moves.len() == 1 {
             //println!("blocking forking move");
-            return opp_forking_moves[0];
+            return (opp_forking_moves[0], MoveReason::BlockFork);
         }
 
         // force opponent to defend
-        if let Some(cell_coord) = self.force_defending_move(&board, &opp_forking_moves) {
+        if let Some(cell_coord) = self.force_defending_move(board, &opp_forking_moves) {
             //println!("forcing opponent defend");
-            return cell_coord;
+            return (cell_coord, MoveReason::ForceDefense);
         }
 
         // play center
-        if let Move::Valid = board.validate_move(CellCoord::new(1, 1)) {
+        let center = CellCoord::new(board.get_height() / 2, board.get_width() / 2);
+        if let Move::Valid = board.validate_move(center) {
             //println!("playing center");
-            return CellCoord::new(1, 1);
+            return (center, MoveReason::Center);
         }
 
         // According to wikipedia, the computer should play the opposite corner here
@@ -148,24 +145,116 @@ impl<'a> Player<'a> for OptimalAI<'a> {
         // the current algorithm seems optimal already. Not implementing for now.
 
         // play empty corner
-        if let Some(cell_coord) = board.get_corner_move() {
+        if let Some(cell_coord) = board.get_corner_move(&mut self.rng) {
             //println!("playing corner");
-            return cell_coord;
+            return (cell_coord, MoveReason::Corner);
         }
 
         // play empty edge
-        if let Some(cell_coord) = board.get_edge_move() {
+        if let Some(cell_coord) = board.get_edge_move(&mut self.rng) {
             //println!("playing edge");
-            return cell_coord;
+            return (cell_coord, MoveReason::Edge);
         }
 
-        panic!("Unexpected path in OptimalAI::get_valid_move() logic");
+        // Boards larger than 3x3 have inner cells that are neither corners
+        // nor edges. Take any of them.
+        if let Some(&cell_coord) = board.get_valid_moves().first() {
+            return (cell_coord, MoveReason::Open);
+        }
+
+        panic!("Unexpected path in OptimalAI::get_move_with_reason() logic");
+    }
+}
+
+// Misère turns the rules above upside down, so OptimalAI plays it with a
+// different set:
+//   1. take the center of an empty board, and from then on answer each move
+//      with the cell opposite it. If we ever completed a line that way, the
+//      opponent would have completed its mirror image first.
+//   2. otherwise, stay out of the cells that would complete one of our lines,
+//      and of the rest, take one in the fewest lines we could still complete.
+//   3. if every open cell completes one of our lines, we've lost anyway.
+// On the standard board, that never loses as either player.
+impl<'a> OptimalAI<'a> {
+    pub fn get_misere_move_with_reason(&mut self, board: &Board) -> (CellCoord, MoveReason) {
+        let safe_moves = board.get_safe_moves(self.marker);
+
+        // Only boards with an odd number of rows and columns have a center.
+        let center = CellCoord::new(board.get_height() / 2, board.get_width() / 2);
+        if board.get_mirror_cell(center) == center {
+            if board.get_valid_moves().len() == board.get_width() * board.get_height() {
+                return (center, MoveReason::Mirror);
+            }
+            if board.get_marker_at(center) == Some(self.marker) {
+                let opponent = Some(Marker::opposite(self.marker));
+                let mirror_move = safe_moves.iter().copied().find(|&cell_coord| {
+                    board.get_marker_at(board.get_mirror_cell(cell_coord)) == opponent
+                });
+                if let Some(cell_coord) = mirror_move {
+                    return (cell_coord, MoveReason::Mirror);
+                }
+            }
+        }
+
+        let fewest_open_sets = safe_moves
+            .iter()
+            .map(|&cell_coord| board.count_open_sets(cell_coord, self.marker))
+            .min();
+        let safest_moves: Vec<CellCoord> = safe_moves
+            .into_iter()
+            .filter(|&cell_coord| {
+                Some(board.count_open_sets(cell_coord, self.marker)) == fewest_open_sets
+            })
+            .collect();
+        if let Some(&cell_coord) = safest_moves.choose(&mut self.rng) {
+            return (cell_coord, MoveReason::Safe);
+        }
+
+        match board.get_valid_moves().choose(&mut self.rng) {
+            Some(&cell_coord) => (cell_coord, MoveReason::Forced),
+            None => panic!("{} was asked to move on a full board", self.name),
+        }
+    }
+}
+
+impl<'a> Player<'a> for OptimalAI<'a> {
+    fn with_seed(name: &'a str, marker: Marker, seed: u64) -> OptimalAI<'a> {
+        OptimalAI {
+            name,
+            marker,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn get_marker(&self) -> Marker {
+        self.marker
+    }
+
+    fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "Optimal"
+    }
+
+    fn get_valid_move(&mut self, board: &Board) -> CellCoord {
+        //println!("{}'s turn.", self.name);
+        self.get_move_with_reason(board).0
+    }
+}
+
+impl<'a> MiserePlayer<'a> for OptimalAI<'a> {
+    fn get_misere_move(&mut self, board: &Board) -> CellCoord {
+        self.get_misere_move_with_reason(board).0
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::board::BoardState;
+    use crate::save;
 
     #[test]
     fn makes_winning_move() {
@@ -174,7 +263,7 @@ mod tests {
         board.place_marker(CellCoord::new(0, 0), marker);
         board.place_marker(CellCoord::new(0, 1), marker);
 
-        let optimal_ai = OptimalAI::new("TestOptimal", marker);
+        let mut optimal_ai = OptimalAI::new("TestOptimal", marker);
         assert_eq!(optimal_ai.get_valid_move(&board), CellCoord::new(0, 2));
     }
 
@@ -185,7 +274,196 @@ mod tests {
         board.place_marker(CellCoord::new(0, 0), opponent);
         board.place_marker(CellCoord::new(0, 1), opponent);
 
-        let optimal_ai = OptimalAI::new("TestOptimal", Marker::opposite(opponent));
+        let mut optimal_ai = OptimalAI::new("TestOptimal", Marker::opposite(opponent));
         assert_eq!(optimal_ai.get_valid_move(&board), CellCoord::new(0, 2));
     }
+
+    #[test]
+    fn explains_its_moves() {
+        let mut board = Board::new();
+        let mut optimal_ai = OptimalAI::new("TestOptimal", Marker::O);
+        board.place_marker(CellCoord::new(0, 0), Marker::X);
+        assert_eq!(
+            optimal_ai.get_move_with_reason(&board),
+            (CellCoord::new(1, 1), MoveReason::Center)
+        );
+
+        // X has two in the top row, and O has to block it.
+        board.place_marker(CellCoord::new(1, 1), Marker::O);
+        board.place_marker(CellCoord::new(0, 1), Marker::X);
+        assert_eq!(
+            optimal_ai.get_move_with_reason(&board),
+            (CellCoord::new(0, 2), MoveReason::Block)
+        );
+    }
+
+    #[test]
+    fn explains_its_misere_moves() {
+        let mut board = Board::new();
+        let mut optimal_ai = OptimalAI::new("TestOptimal", Marker::X);
+        assert_eq!(
+            optimal_ai.get_misere_move_with_reason(&board),
+            (CellCoord::new(1, 1), MoveReason::Mirror)
+        );
+
+        // X answers a1 with c3, on the other side of the center.
+        board.place_marker(CellCoord::new(1, 1), Marker::X);
+        board.place_marker(CellCoord::new(0, 0), Marker::O);
+        assert_eq!(
+            optimal_ai.get_misere_move_with_reason(&board),
+            (CellCoord::new(2, 2), MoveReason::Mirror)
+        );
+
+        // O has to stay out of c1, which would finish the top row. Of the
+        // other cells, c3 is the only one where X already blocks every line.
+        let mut board = Board::new();
+        let mut optimal_ai = OptimalAI::new("TestOptimal", Marker::O);
+        let markers = [
+            (1, 1, Marker::X),
+            (0, 0, Marker::O),
+            (2, 1, Marker::X),
+            (0, 1, Marker::O),
+            (1, 2, Marker::X),
+        ];
+        for (row, column, marker) in markers {
+            board.place_marker(CellCoord::new(row, column), marker);
+        }
+        assert_eq!(
+            optimal_ai.get_misere_move_with_reason(&board),
+            (CellCoord::new(2, 2), MoveReason::Safe)
+        );
+    }
+
+    // Every move OptimalAI might make on `board`. It picks at random between
+    // equally good corners, edges and safe cells, so any of them could come
+    // up. It takes the first fork it finds, but any fork it found should do
+    // as well, so those are all tried too.
+    fn get_possible_moves(board: &Board, marker: Marker, misere: bool) -> Vec<CellCoord> {
+        let mut optimal_ai = OptimalAI::with_seed("TestOptimal", marker, 0);
+        let (player_move, reason) = match misere {
+            true => optimal_ai.get_misere_move_with_reason(board),
+            false => optimal_ai.get_move_with_reason(board),
+        };
+        let get_cells_of_type = |cell_type| {
+            board
+                .get_valid_moves()
+                .into_iter()
+                .filter(|&cell_coord| board.get_cell_type(cell_coord) == cell_type)
+                .collect()
+        };
+        let possible_moves: Vec<CellCoord> = match reason {
+            MoveReason::Fork => board.get_forking_move(marker),
+            MoveReason::Corner => get_cells_of_type(CellType::Corner),
+            MoveReason::Edge => get_cells_of_type(CellType::Edge),
+            MoveReason::Safe => {
+                let safe_moves = board.get_safe_moves(marker);
+                let fewest_open_sets = safe_moves
+                    .iter()
+                    .map(|&cell_coord| board.count_open_sets(cell_coord, marker))
+                    .min();
+                safe_moves
+                    .into_iter()
+                    .filter(|&cell_coord| {
+                        Some(board.count_open_sets(cell_coord, marker)) == fewest_open_sets
+                    })
+                    .collect()
+            }
+            MoveReason::Forced => board.get_valid_moves(),
+            _ => vec![player_move],
+        };
+        assert!(possible_moves.contains(&player_move));
+        possible_moves
+    }
+
+    // Walks the whole game tree from `board`, trying every move the opponent
+    // could make and every move OptimalAI could answer with. Returns the
+    // moves of the first line OptimalAI loses, or None if it never does. In
+    // misère, OptimalAI loses by completing a line instead.
+    fn find_losing_line(
+        board: &Board,
+        optimal_marker: Marker,
+        to_move: Marker,
+        misere: bool,
+        moves: &mut Vec<CellCoord>,
+    ) -> Option<Vec<CellCoord>> {
+        let candidates = if to_move == optimal_marker {
+            let possible_moves = get_possible_moves(board, optimal_marker, misere);
+            for &player_move in possible_moves.iter() {
+                assert_eq!(
+                    board.validate_move(player_move),
+                    Move::Valid,
+                    "OptimalAI made an illegal move after {}",
+                    to_notation(moves)
+                );
+            }
+            possible_moves
+        } else {
+            board.get_valid_moves()
+        };
+
+        for player_move in candidates {
+            let mut next_board = board.clone();
+            next_board.place_marker(player_move, to_move);
+            moves.push(player_move);
+            let losing_line = match next_board.check_board_state(player_move, to_move) {
+                BoardState::Win(_) if (to_move == optimal_marker) == misere => Some(moves.clone()),
+                BoardState::Win(_) | BoardState::Tie => None,
+                BoardState::Playing => find_losing_line(
+                    &next_board,
+                    optimal_marker,
+                    Marker::opposite(to_move),
+                    misere,
+                    moves,
+                ),
+            };
+            moves.pop();
+            if losing_line.is_some() {
+                return losing_line;
+            }
+        }
+        None
+    }
+
+    fn to_notation(moves: &[CellCoord]) -> String {
+        let moves: Vec<String> = moves.iter().map(|&m| save::cell_to_notation(m)).collect();
+        moves.join(" ")
+    }
+
+    fn assert_never_loses(optimal_marker: Marker, misere: bool) {
+        let losing_line = find_losing_line(
+            &Board::new(),
+            optimal_marker,
+            Marker::X,
+            misere,
+            &mut Vec::new(),
+        );
+        if let Some(moves) = losing_line {
+            panic!(
+                "OptimalAI ({:?}, misère: {}) lost after {}",
+                optimal_marker,
+                misere,
+                to_notation(&moves)
+            );
+        }
+    }
+
+    #[test]
+    fn never_loses_as_x() {
+        assert_never_loses(Marker::X, false);
+    }
+
+    #[test]
+    fn never_loses_as_o() {
+        assert_never_loses(Marker::O, false);
+    }
+
+    #[test]
+    fn never_loses_misere_as_x() {
+        assert_never_loses(Marker::X, true);
+    }
+
+    #[test]
+    fn never_loses_misere_as_o() {
+        assert_never_loses(Marker::O, true);
+    }
 }
@@ -1,54 +1,23 @@
-use super::Player;
+use super::{MiserePlayer, Player, UltimatePlayer, WildPlayer};
 use crate::board::Board;
 use crate::common::*;
+use crate::ultimate::UltimateBoard;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use std::{thread, time};
+use rand::{Rng, SeedableRng};
 
 pub struct RandomAI<'a> {
     pub name: &'a str,
     pub marker: Marker,
-    move_set: Vec<CellCoord>,
-}
-
-impl<'a> RandomAI<'a> {
-    pub fn new(name: &str, marker: Marker) -> RandomAI {
-        // To create the RandomAI's move set, we first use iproduct! macro
-        // to make a cartesian product of our row and column ranges. This enumerates
-        // all possible cell coordinates. We collect() it to form a vector of these
-        // coordinates, and then randomly shuffle it.
-        let mut move_set: Vec<CellCoord> = itertools::iproduct!(0..3, 0..3)
-            .map(|(row, column)| CellCoord::new(row, column))
-            .collect();
-
-        let mut rng = thread_rng();
-        move_set.shuffle(&mut rng);
-
-        RandomAI {
-            name,
-            marker,
-            move_set,
-        }
-    }
+    rng: StdRng,
 }
 
 impl<'a> Player<'a> for RandomAI<'a> {
-    fn new(name: &'a str, marker: Marker) -> RandomAI {
-        // To create the RandomAI's move set, we first use iproduct! macro
-        // to make a cartesian product of our row and column ranges. This enumerates
-        // all possible cell coordinates. We collect() it to form a vector of these
-        // coordinates, and then randomly shuffle it.
-        let mut move_set: Vec<CellCoord> = itertools::iproduct!(0..3, 0..3)
-            .map(|(row, column)| CellCoord::new(row, column))
-            .collect();
-
-        let mut rng = thread_rng();
-        move_set.shuffle(&mut rng);
-
+    fn with_seed(name: &'a str, marker: Marker, seed: u64) -> RandomAI<'a> {
         RandomAI {
             name,
             marker,
-            move_set,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
@@ -60,6 +29,10 @@ impl<'a> Player<'a> for RandomAI<'a> {
         self.name
     }
 
+    fn get_kind(&self) -> &'static str {
+        "Random"
+    }
+
     // The computer should be smart enough to always make valid moves. Initially,
     // we did move validation at the game level, but we can do that here instead
     // by providing a reference to the board as a method parameter. We can then
@@ -67,20 +40,36 @@ impl<'a> Player<'a> for RandomAI<'a> {
     fn get_valid_move(&mut self, board: &Board) -> CellCoord {
         //println!("{}'s turn.", self.name);
 
-        // Use a sleep here so it seems like the computer is thinking a bit.
-        thread::sleep(time::Duration::from_secs(1));
+        *board
+            .get_valid_moves()
+            .choose(&mut self.rng)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
+
+// Random moves don't go for lines, so they're no worse at avoiding them.
+impl<'a> MiserePlayer<'a> for RandomAI<'a> {
+    fn get_misere_move(&mut self, board: &Board) -> CellCoord {
+        self.get_valid_move(board)
+    }
+}
 
-        loop {
-            let player_move = self.move_set.pop().unwrap_or_else(|| {
-                panic!(
-                    "{} ran out of generated moves. You shouldn't need this many.",
-                    self.name
-                )
-            });
+impl<'a> UltimatePlayer<'a> for RandomAI<'a> {
+    fn get_ultimate_move(&mut self, board: &UltimateBoard) -> CellCoord {
+        *board
+            .get_valid_moves()
+            .choose(&mut self.rng)
+            .unwrap_or_else(|| panic!("{} was asked to move on a full board", self.name))
+    }
+}
 
-            if let Move::Valid = board.validate_move(player_move) {
-                break player_move;
-            }
-        }
+impl<'a> WildPlayer<'a> for RandomAI<'a> {
+    fn get_wild_move(&mut self, board: &Board) -> WildMove {
+        let cell = self.get_valid_move(board);
+        let marker = match self.rng.gen() {
+            true => Marker::X,
+            false => Marker::O,
+        };
+        WildMove::new(cell, marker)
     }
 }
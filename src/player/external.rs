@@ -0,0 +1,290 @@
+use super::Player;
+use crate::board::Board;
+use crate::common::*;
+use crate::save;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+// A program that plays over the engine protocol described in engine.rs.
+// `command` starts it, like "./bot --fast", and `name` is only for showing.
+// The engine is started when it's first asked for a move.
+pub struct ExternalPlayer<'a> {
+    pub name: &'a str,
+    pub command: &'a str,
+    pub marker: Marker,
+    process: Option<EngineProcess>,
+    // How many markers were on the board the last time we were asked to
+    // move. Fewer markers than that means a new game (or an undo).
+    last_marker_count: Option<usize>,
+}
+
+struct EngineProcess {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl EngineProcess {
+    fn spawn(command: &str) -> io::Result<EngineProcess> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "The engine command is empty")
+        })?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("stdin was piped");
+        let stdout = BufReader::new(child.stdout.take().expect("stdout was piped"));
+        Ok(EngineProcess {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    fn send(&mut self, command: &str) -> io::Result<()> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()
+    }
+
+    // Reads lines until one starts with `reply`, and returns the rest of it.
+    // An `error` line means the engine didn't like what we sent.
+    fn wait_for(&mut self, reply: &str) -> io::Result<String> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "The engine quit",
+                ));
+            }
+            let mut words = line.trim().splitn(2, ' ');
+            match words.next() {
+                Some(word) if word == reply => {
+                    return Ok(words.next().unwrap_or("").trim().to_string())
+                }
+                Some("error") => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, line.trim()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for EngineProcess {
+    fn drop(&mut self) {
+        // Ask nicely first, but don't wait around for an engine that's stuck.
+        let _ = self.send("quit");
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Starts `command` and checks it answers the protocol, so a typo in an engine
+// command can be reported before a game starts, rather than as a forfeit in
+// every game.
+pub fn check_engine(command: &str) -> io::Result<()> {
+    let mut process = EngineProcess::spawn(command)?;
+    process.send("isready")?;
+    process.wait_for("readyok").map(|_| ())
+}
+
+// The engine only needs the position, not how we got there. So instead of
+// keeping our own record of the game, we list X's markers and O's markers
+// in turn, which is a legal way to reach any board that isn't over yet.
+fn get_position(board: &Board) -> Vec<CellCoord> {
+    let width = board.get_width();
+    let get_cells = |marker_char| {
+        (0..width * board.get_height())
+            .filter(|&index| board.get_cellstate_char(index) == marker_char)
+            .map(|index| CellCoord::new(index / width, index % width))
+            .collect::<Vec<CellCoord>>()
+    };
+    let (x_cells, o_cells) = (get_cells('X'), get_cells('O'));
+
+    let mut moves = Vec::new();
+    for (index, &cell) in x_cells.iter().enumerate() {
+        moves.push(cell);
+        if let Some(&cell) = o_cells.get(index) {
+            moves.push(cell);
+        }
+    }
+    moves
+}
+
+impl<'a> ExternalPlayer<'a> {
+    pub fn with_command(name: &'a str, command: &'a str, marker: Marker) -> ExternalPlayer<'a> {
+        ExternalPlayer {
+            name,
+            command,
+            marker,
+            process: None,
+            last_marker_count: None,
+        }
+    }
+
+    fn ask_for_move(&mut self, board: &Board) -> io::Result<CellCoord> {
+        if self.process.is_none() {
+            self.process = Some(EngineProcess::spawn(self.command)?);
+        }
+        let process = self.process.as_mut().expect("the engine was just started");
+
+        let position = get_position(board);
+        let new_game = match self.last_marker_count {
+            Some(count) => position.len() < count,
+            None => true,
+        };
+        if new_game {
+            process.send(&format!(
+                "newgame {}x{} {}",
+                board.get_width(),
+                board.get_height(),
+                board.get_win_length()
+            ))?;
+        }
+        self.last_marker_count = Some(position.len());
+
+        let moves: Vec<String> = position.into_iter().map(save::cell_to_notation).collect();
+        process.send(format!("position {}", moves.join(" ")).trim_end())?;
+        process.send("go")?;
+        let best_move = process.wait_for("bestmove")?;
+        save::notation_to_cell(&best_move)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl<'a> Player<'a> for ExternalPlayer<'a> {
+    // Whatever randomness the engine uses is up to the engine. Given only a
+    // name, the name is the command too.
+    fn with_seed(name: &'a str, marker: Marker, _seed: u64) -> ExternalPlayer<'a> {
+        ExternalPlayer::with_command(name, name, marker)
+    }
+
+    // The next game gets a fresh copy of the same engine.
+    fn reseeded(&self, _seed: u64) -> ExternalPlayer<'a> {
+        ExternalPlayer::with_command(self.name, self.command, self.marker)
+    }
+
+    fn get_marker(&self) -> Marker {
+        self.marker
+    }
+
+    fn get_name(&self) -> &'a str {
+        self.name
+    }
+
+    fn get_kind(&self) -> &'static str {
+        "External"
+    }
+
+    // Only for callers that can't take a forfeit. Games use `try_get_move()`.
+    fn get_valid_move(&mut self, board: &Board) -> CellCoord {
+        self.try_get_move(board)
+            .unwrap_or_else(|error| panic!("{}", error))
+    }
+
+    // An engine that breaks the rules or crashes forfeits the game.
+    fn try_get_move(&mut self, board: &Board) -> Result<CellCoord, String> {
+        match self.ask_for_move(board) {
+            Ok(cell) if board.validate_move(cell) == Move::Valid => Ok(cell),
+            Ok(cell) => Err(format!(
+                "{} made an illegal move: {}",
+                self.name,
+                save::cell_to_notation(cell)
+            )),
+            Err(error) => Err(format!("{} couldn't make a move: {}", self.name, error)),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::game::{Game, GameState, TicTacToe, Winner};
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    // A tiny engine that answers every `go` with the same move.
+    fn write_engine(name: &str, answer: &str) -> String {
+        let path = env::temp_dir().join(format!("miktactoe-{}-{}.sh", name, process::id()));
+        let script = format!(
+            "while read command; do\n\
+             case $command in\n\
+             go) echo \"info thinking\"; echo \"bestmove {}\" ;;\n\
+             isready) echo readyok ;;\n\
+             quit) exit ;;\n\
+             esac\n\
+             done\n",
+            answer
+        );
+        fs::write(&path, script).unwrap();
+        format!("sh {}", path.display())
+    }
+
+    #[test]
+    fn lists_position_moves_in_turn() {
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(2, 2), Marker::X);
+        board.place_marker(CellCoord::new(0, 0), Marker::O);
+        board.place_marker(CellCoord::new(1, 1), Marker::X);
+        assert_eq!(
+            get_position(&board),
+            vec![
+                CellCoord::new(1, 1),
+                CellCoord::new(0, 0),
+                CellCoord::new(2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn plays_engine_moves() {
+        let command = write_engine("plays", "c3");
+        check_engine(&command).unwrap();
+
+        let mut player = ExternalPlayer::with_command("Bot", &command, Marker::X);
+        assert_eq!(player.get_valid_move(&Board::new()), CellCoord::new(2, 2));
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(0, 0), Marker::X);
+        board.place_marker(CellCoord::new(1, 1), Marker::O);
+        assert_eq!(player.get_valid_move(&board), CellCoord::new(2, 2));
+
+        // The name is only a name, so the next game still starts the command.
+        let mut player = player.reseeded(1);
+        assert_eq!(player.get_name(), "Bot");
+        assert_eq!(player.get_valid_move(&Board::new()), CellCoord::new(2, 2));
+    }
+
+    #[test]
+    fn fails_on_illegal_moves() {
+        let command = write_engine("illegal", "b2");
+        let mut board = Board::new();
+        board.place_marker(CellCoord::new(1, 1), Marker::X);
+        let result = ExternalPlayer::with_command("Bot", &command, Marker::O).try_get_move(&board);
+        assert_eq!(result, Err(String::from("Bot made an illegal move: b2")));
+    }
+
+    #[test]
+    fn illegal_moves_forfeit_games() {
+        let command = write_engine("forfeit", "b2");
+        let mut game = TicTacToe::new(
+            ExternalPlayer::with_command("Bot", &command, Marker::X),
+            ExternalPlayer::with_command("Bot", &command, Marker::O),
+        );
+        while game.run() != GameState::Done {}
+        assert_eq!(game.get_winner(), Winner::Player1);
+        assert_eq!(game.get_forfeit(), Some("Bot made an illegal move: b2"));
+        assert_eq!(game.get_move_history().len(), 1);
+    }
+
+    #[test]
+    fn rejects_missing_engines() {
+        assert!(check_engine("./no-such-miktactoe-engine").is_err());
+        assert!(check_engine("").is_err());
+    }
+}
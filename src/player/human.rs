@@ -1,6 +1,7 @@
+use super::{MiserePlayer, Player, UltimatePlayer, WildPlayer};
 use crate::board::Board;
 use crate::common::*;
-use super::Player;
+use crate::ultimate::UltimateBoard;
 use std::io;
 
 #[derive(Debug)]
@@ -10,7 +11,7 @@ pub struct Human<'a> {
 }
 
 impl<'a> Player<'a> for Human<'a> {
-    fn new(name: &'a str, marker: Marker) -> Human<'a> {
+    fn with_seed(name: &'a str, marker: Marker, _seed: u64) -> Human<'a> {
         Human { name, marker }
     }
 
@@ -22,6 +23,14 @@ impl<'a> Player<'a> for Human<'a> {
         self.name
     }
 
+    fn get_kind(&self) -> &'static str {
+        "Human"
+    }
+
+    fn is_human(&self) -> bool {
+        true
+    }
+
     fn get_valid_move(&mut self, board: &Board) -> CellCoord {
         loop {
             let mut input = String::new();
@@ -52,7 +61,35 @@ impl<'a> Player<'a> for Human<'a> {
                     println!("Out of bounds move. Please try again.");
                     continue;
                 }
+                Move::WrongBoard => {
+                    println!("You have to play in the board you were sent to.");
+                    continue;
+                }
+                Move::Floating => {
+                    println!("Markers drop to the bottom of their column. Please try again.");
+                    continue;
+                }
             }
         }
     }
 }
+
+// There's no text version of ultimate tic-tac-toe, so humans always make
+// their moves through the UI, and the game never asks them for one.
+impl<'a> UltimatePlayer<'a> for Human<'a> {
+    fn get_ultimate_move(&mut self, _board: &UltimateBoard) -> CellCoord {
+        panic!("{} makes their moves through the UI", self.name)
+    }
+}
+
+impl<'a> MiserePlayer<'a> for Human<'a> {
+    fn get_misere_move(&mut self, _board: &Board) -> CellCoord {
+        panic!("{} makes their moves through the UI", self.name)
+    }
+}
+
+impl<'a> WildPlayer<'a> for Human<'a> {
+    fn get_wild_move(&mut self, _board: &Board) -> WildMove {
+        panic!("{} makes their moves through the UI", self.name)
+    }
+}
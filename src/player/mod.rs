@@ -1,11 +1,14 @@
 pub mod ai_basic;
 pub mod ai_forking;
+pub mod ai_minimax;
 pub mod ai_optimal;
 pub mod ai_random;
+pub mod external;
 pub mod human;
 
 use crate::board::Board;
-use crate::common::{CellCoord, Marker};
+use crate::common::{CellCoord, Marker, WildMove};
+use crate::ultimate::UltimateBoard;
 
 // Traits can specify lifetime parameters just like structs.
 // We need one here because we create a concrete Player object
@@ -14,8 +17,98 @@ pub trait Player<'a> {
     // Inside traits, Self refers to the implementing type.
     // So, the structs that implement this trait can implement
     // a `new()` function that returns their respective concrete type.
-    fn new(name: &'a str, marker: Marker) -> Self;
+    fn new(name: &'a str, marker: Marker) -> Self
+    where
+        Self: Sized,
+    {
+        Self::with_seed(name, marker, rand::random())
+    }
+    // Computers that make random choices draw them from an RNG seeded with
+    // `seed`, so the same seed always makes the same choices. That lets a
+    // test, simulation or bug report reproduce a game exactly. `new()` just
+    // picks a random seed.
+    fn with_seed(name: &'a str, marker: Marker, seed: u64) -> Self
+    where
+        Self: Sized;
+    // A fresh copy of this player for another game, with a new seed. That's
+    // just `with_seed()` for players who are only a name and a marker.
+    fn reseeded(&self, seed: u64) -> Self
+    where
+        Self: Sized,
+    {
+        Self::with_seed(self.get_name(), self.get_marker(), seed)
+    }
     fn get_valid_move(&mut self, board: &Board) -> CellCoord;
+    // Like `get_valid_move()`, for players that can fail to come up with a
+    // move at all, like an engine that crashed. Games ask this way, and a
+    // failure forfeits the game instead of stopping the program.
+    fn try_get_move(&mut self, board: &Board) -> Result<CellCoord, String> {
+        Ok(self.get_valid_move(board))
+    }
     fn get_marker(&self) -> Marker;
     fn get_name(&self) -> &'a str;
+    // The kind of player this is, like "Human" or "Optimal". Saved games
+    // use it to put the same kind of player back in each seat.
+    fn get_kind(&self) -> &'static str;
+    // Human players make their moves through the UI instead of
+    // `get_valid_move()`, so the game needs to know to wait for them.
+    fn is_human(&self) -> bool {
+        false
+    }
+}
+
+// Ultimate tic-tac-toe is played on a grid of boards, so a player needs the
+// whole grid to pick a move. Everything else about a player is the same, so
+// players that know how to play it implement this on top of `Player`.
+pub trait UltimatePlayer<'a>: Player<'a> {
+    fn get_ultimate_move(&mut self, board: &UltimateBoard) -> CellCoord;
+}
+
+// In misère tic-tac-toe, completing a line loses. It's played on an ordinary
+// board, but the computers that go for lines would play exactly the wrong
+// moves, so only players that implement this can take part.
+pub trait MiserePlayer<'a>: Player<'a> {
+    fn get_misere_move(&mut self, board: &Board) -> CellCoord;
+}
+
+// In wild tic-tac-toe, either player can place either marker, and whoever
+// completes a line wins. A player's own marker only says who moves first,
+// so players that know how to play it pick a marker along with each cell.
+pub trait WildPlayer<'a>: Player<'a> {
+    fn get_wild_move(&mut self, board: &Board) -> WildMove;
+}
+
+// A seat for a MiserePlayer in a TicTacToe game. The game asks for moves with
+// `get_valid_move()` like always, and the seat passes on the player's misère
+// move instead.
+pub struct Misere<P>(pub P);
+
+impl<'a, P: MiserePlayer<'a>> Player<'a> for Misere<P> {
+    fn with_seed(name: &'a str, marker: Marker, seed: u64) -> Misere<P> {
+        Misere(P::with_seed(name, marker, seed))
+    }
+
+    fn reseeded(&self, seed: u64) -> Misere<P> {
+        Misere(self.0.reseeded(seed))
+    }
+
+    fn get_valid_move(&mut self, board: &Board) -> CellCoord {
+        self.0.get_misere_move(board)
+    }
+
+    fn get_marker(&self) -> Marker {
+        self.0.get_marker()
+    }
+
+    fn get_name(&self) -> &'a str {
+        self.0.get_name()
+    }
+
+    fn get_kind(&self) -> &'static str {
+        self.0.get_kind()
+    }
+
+    fn is_human(&self) -> bool {
+        self.0.is_human()
+    }
 }
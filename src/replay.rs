@@ -0,0 +1,263 @@
+use crate::board::{Board, BoardState};
+use crate::common::{BoardSize, CellCoord, Marker, Move};
+use crate::game::{Variant, Winner};
+use crate::save::{self, SavedGame};
+
+// Steps through a saved game one move at a time. `position` is how many of
+// the game's moves are on the board, so 0 is the empty board and
+// `moves.len()` is the final position.
+pub struct Replay {
+    saved_game: SavedGame,
+    board: Board,
+    position: usize,
+    // Worked out by playing the moves, rather than trusting the file's
+    // Result header. None if the game wasn't finished.
+    result: Option<Winner>,
+}
+
+impl Replay {
+    // Checks that every move in the saved game can actually be played, so
+    // stepping through it later can't go wrong.
+    pub fn new(saved_game: SavedGame) -> Result<Replay, String> {
+        if saved_game.variant == Variant::Ultimate {
+            return Err(String::from(
+                "Only games on a single board can be replayed, and this is Ultimate",
+            ));
+        }
+        let mut board = saved_game.new_board();
+        let mut result = None;
+        for (index, &player_move) in saved_game.moves.iter().enumerate() {
+            let notation = save::cell_to_notation(player_move);
+            if result.is_some() {
+                return Err(format!(
+                    "Move {} ({}) comes after the game ended",
+                    index + 1,
+                    notation
+                ));
+            }
+            if board.validate_move(player_move) != Move::Valid {
+                return Err(format!(
+                    "Move {} ({}) isn't a legal move",
+                    index + 1,
+                    notation
+                ));
+            }
+
+            // In misère, whoever completes a line loses.
+            let marker = Self::get_move_marker(&saved_game, index);
+            let (mover, other) = match index % 2 {
+                0 => (Winner::Player1, Winner::Player2),
+                _ => (Winner::Player2, Winner::Player1),
+            };
+            board.place_marker(player_move, marker);
+            result = match board.check_board_state(player_move, marker) {
+                BoardState::Win(_) if saved_game.variant == Variant::Misere => Some(other),
+                BoardState::Win(_) => Some(mover),
+                BoardState::Tie => Some(Winner::None),
+                BoardState::Playing => None,
+            };
+        }
+
+        Ok(Replay {
+            board: saved_game.new_board(),
+            saved_game,
+            position: 0,
+            result,
+        })
+    }
+
+    // Player 1 plays X and moves first. In a wild game, the players picked
+    // their markers, and the saved game says which.
+    fn get_move_marker(saved_game: &SavedGame, move_number: usize) -> Marker {
+        match (saved_game.markers.get(move_number), move_number % 2) {
+            (Some(&marker), _) => marker,
+            (None, 0) => Marker::X,
+            (None, _) => Marker::O,
+        }
+    }
+
+    pub fn step_forward(&mut self) {
+        if let Some(&player_move) = self.saved_game.moves.get(self.position) {
+            let marker = Self::get_move_marker(&self.saved_game, self.position);
+            self.board.place_marker(player_move, marker);
+            self.position += 1;
+        }
+    }
+
+    pub fn step_back(&mut self) {
+        if let Some(player_move) = self.get_last_move() {
+            self.board.remove_marker(player_move);
+            self.position -= 1;
+        }
+    }
+
+    pub fn go_to_start(&mut self) {
+        while self.position > 0 {
+            self.step_back();
+        }
+    }
+
+    pub fn go_to_end(&mut self) {
+        while self.position < self.get_move_count() {
+            self.step_forward();
+        }
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    pub fn get_move_count(&self) -> usize {
+        self.saved_game.moves.len()
+    }
+
+    pub fn get_last_move(&self) -> Option<CellCoord> {
+        match self.position {
+            0 => None,
+            position => Some(self.saved_game.moves[position - 1]),
+        }
+    }
+
+    // The marker of the side to move, or None once the finished game's last
+    // move is on the board. Like the player's seats, that's X for player 1
+    // and O for player 2, even in a wild game.
+    pub fn get_turn_marker(&self) -> Option<Marker> {
+        match self.position % 2 {
+            _ if self.result.is_some() && self.position == self.get_move_count() => None,
+            0 => Some(Marker::X),
+            _ => Some(Marker::O),
+        }
+    }
+
+    // The cells of the line that decided the game, once it's on the board.
+    pub fn get_winning_line(&self) -> Vec<CellCoord> {
+        let last_move = match self.get_last_move() {
+            Some(last_move) => last_move,
+            None => return Vec::new(),
+        };
+        let marker = Self::get_move_marker(&self.saved_game, self.position - 1);
+        match self.board.check_board_state(last_move, marker) {
+            BoardState::Win(set_type) => self.board.get_set_coords(set_type),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn get_result(&self) -> Option<Winner> {
+        self.result
+    }
+
+    pub fn get_saved_game(&self) -> &SavedGame {
+        &self.saved_game
+    }
+
+    pub fn get_cellstate_char(&self, cell_index: usize) -> char {
+        self.board.get_cellstate_char(cell_index)
+    }
+
+    pub fn get_board_size(&self) -> BoardSize {
+        self.board.get_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved_game(moves: &[&str]) -> SavedGame {
+        let text = format!(
+            "[Player1Type \"Human\"]\n[Player2Type \"Optimal\"]\n\n{}",
+            moves.join(" ")
+        );
+        SavedGame::from_text(&text).unwrap()
+    }
+
+    #[test]
+    fn steps_through_moves() {
+        let mut replay = Replay::new(saved_game(&["b2", "a1", "c3"])).unwrap();
+        assert_eq!(replay.get_turn_marker(), Some(Marker::X));
+        assert_eq!(replay.get_last_move(), None);
+
+        replay.step_forward();
+        replay.step_forward();
+        assert_eq!(replay.get_position(), 2);
+        assert_eq!(replay.get_cellstate_char(0), 'O');
+        assert_eq!(replay.get_turn_marker(), Some(Marker::X));
+
+        replay.step_back();
+        assert_eq!(replay.get_cellstate_char(0), '_');
+        assert_eq!(replay.get_last_move(), Some(CellCoord::new(1, 1)));
+        assert_eq!(replay.get_turn_marker(), Some(Marker::O));
+
+        // Stepping stops at either end of the game.
+        replay.step_back();
+        replay.step_back();
+        assert_eq!(replay.get_position(), 0);
+        replay.go_to_end();
+        replay.step_forward();
+        assert_eq!(replay.get_position(), 3);
+        assert_eq!(replay.get_result(), None);
+    }
+
+    #[test]
+    fn finds_result() {
+        let mut replay = Replay::new(saved_game(&["a1", "a2", "b1", "b2", "c1"])).unwrap();
+        assert_eq!(replay.get_result(), Some(Winner::Player1));
+        replay.go_to_end();
+        assert_eq!(replay.get_turn_marker(), None);
+        assert_eq!(
+            replay.get_winning_line(),
+            vec![
+                CellCoord::new(0, 0),
+                CellCoord::new(0, 1),
+                CellCoord::new(0, 2)
+            ]
+        );
+        replay.step_back();
+        assert!(replay.get_winning_line().is_empty());
+        replay.go_to_start();
+        assert_eq!(replay.get_cellstate_char(2), '_');
+    }
+
+    #[test]
+    fn rejects_impossible_games() {
+        assert!(Replay::new(saved_game(&["a1", "a1"])).is_err());
+        assert!(Replay::new(saved_game(&["a4"])).is_err());
+        assert!(Replay::new(saved_game(&["a1", "a2", "b1", "b2", "c1", "c2"])).is_err());
+    }
+
+    #[test]
+    fn completing_a_misere_line_loses() {
+        let mut saved_game = saved_game(&["a1", "a2", "b1", "b2", "c1"]);
+        saved_game.variant = Variant::Misere;
+        let mut replay = Replay::new(saved_game).unwrap();
+        assert_eq!(replay.get_result(), Some(Winner::Player2));
+        replay.go_to_end();
+        assert_eq!(replay.get_winning_line().len(), 3);
+    }
+
+    #[test]
+    fn plays_wild_markers() {
+        // Player 2 finishes the column of Xs that player 1 started.
+        let mut saved_game = saved_game(&["a1", "b2", "a2", "a3"]);
+        saved_game.variant = Variant::Wild;
+        saved_game.markers = vec![Marker::X, Marker::O, Marker::X, Marker::X];
+        let mut replay = Replay::new(saved_game).unwrap();
+        assert_eq!(replay.get_result(), Some(Winner::Player2));
+        replay.go_to_end();
+        assert_eq!(replay.get_cellstate_char(6), 'X');
+        assert_eq!(replay.get_winning_line().len(), 3);
+    }
+
+    #[test]
+    fn gravity_moves_land_at_the_bottom() {
+        let mut saved_game = saved_game(&["d6", "d5", "e6"]);
+        saved_game.variant = Variant::Gravity;
+        saved_game.size = BoardSize::new(7, 6, 1);
+        saved_game.win_length = 4;
+        assert!(Replay::new(saved_game.clone()).is_ok());
+
+        // e5 would be left floating above an empty e6.
+        saved_game.moves[2] = CellCoord::new(4, 4);
+        assert!(Replay::new(saved_game).is_err());
+    }
+}
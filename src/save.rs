@@ -0,0 +1,521 @@
+// Saved games are plain text, loosely modeled on chess's PGN format. A file
+// starts with some headers, one per line, followed by the move list:
+//
+//     [Game "miktactoe"]
+//     [Date "2026.10.17"]
+//     [Board "3x3"]
+//     [WinLength "3"]
+//     [Player1 "Human"]
+//     [Player1Type "Human"]
+//     [Player2 "Optimal"]
+//     [Player2Type "Optimal"]
+//     [Result "*"]
+//     [Seed "4217"]
+//
+//     1. b2 a1 2. c3 c1 3. b1 *
+//
+// Board is the width and height of the board, and WinLength is how many in a
+// row it takes to win. Player1 always plays X and moves first. The PlayerType
+// headers hold the kind of player in each seat: Human, Random, Basic, Forking,
+// Optimal or Minimax. Result is "1-0" when player 1 won, "0-1" when player 2
+// won, "1/2-1/2" for a tie, and "*" for a game that isn't finished yet.
+//
+// Games other than classic tic-tac-toe add a Variant header, like
+// [Variant "Ultimate"], right after the Game header. A file without one is a
+// classic game, which keeps files from before variants loading as they were.
+// An ultimate game is saved as a 9x9 board, and its moves name cells on it.
+// A Qubic game's Board is the size of its cube, like 4x4x4, and its moves
+// name cells with the cube's layers side by side from left to right. A
+// Gravity game is saved with its board size, like any other, and its moves
+// name the cells the markers landed in. In a Wild game, each move names the
+// marker that was placed as well as the cell, like `Xb2` or `Ob2`.
+//
+// Seed is the number every random choice the computers made came from, so
+// loading the game plays on with it. Files without one get a random seed.
+//
+// Each move names a cell with a column letter and a row number. Columns are
+// lettered from the left starting at `a`, and rows are numbered from the top
+// starting at 1. So on a 3x3 board:
+//
+//     a1 b1 c1
+//     a2 b2 c2
+//     a3 b3 c3
+//
+// Move numbers like "1." count pairs of moves, and are only there to make the
+// list easier to read. The result may be repeated at the end of the list.
+use crate::board::Board;
+use crate::common::{BoardSize, CellCoord, Marker};
+use crate::game::{Variant, Winner};
+use std::{
+    env,
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SAVE_EXTENSION: &str = "ttt";
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct SavedGame {
+    pub date: String,
+    pub variant: Variant,
+    pub size: BoardSize,
+    pub win_length: usize,
+    pub player1: String,
+    pub player1_kind: String,
+    pub player2: String,
+    pub player2_kind: String,
+    // None while the game is still being played.
+    pub result: Option<Winner>,
+    pub moves: Vec<CellCoord>,
+    // The marker placed with each move in a wild game, where the players
+    // pick. Other games leave it empty, since player 1 always places X.
+    pub markers: Vec<Marker>,
+    // The seed the computers' choices were drawn from, if it's known.
+    pub seed: Option<u64>,
+}
+
+impl SavedGame {
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        let mut headers = vec![("Game", String::from("miktactoe"))];
+        if self.variant != Variant::Classic {
+            headers.push(("Variant", String::from(self.variant.get_name())));
+        }
+        headers.extend([
+            ("Date", self.date.clone()),
+            ("Board", self.size.to_string()),
+            ("WinLength", self.win_length.to_string()),
+            ("Player1", self.player1.clone()),
+            ("Player1Type", self.player1_kind.clone()),
+            ("Player2", self.player2.clone()),
+            ("Player2Type", self.player2_kind.clone()),
+            ("Result", String::from(result_to_text(self.result))),
+        ]);
+        if let Some(seed) = self.seed {
+            headers.push(("Seed", seed.to_string()));
+        }
+        for (key, value) in headers.iter() {
+            text.push_str(&format!("[{} \"{}\"]\n", key, value));
+        }
+        text.push('\n');
+
+        let mut move_list: Vec<String> = Vec::new();
+        for (index, &player_move) in self.moves.iter().enumerate() {
+            if index % 2 == 0 {
+                move_list.push(format!("{}.", index / 2 + 1));
+            }
+            let marker = match self.markers.get(index) {
+                Some(marker) => format!("{:?}", marker),
+                None => String::new(),
+            };
+            move_list.push(format!("{}{}", marker, cell_to_notation(player_move)));
+        }
+        move_list.push(String::from(result_to_text(self.result)));
+        text.push_str(&move_list.join(" "));
+        text.push('\n');
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<SavedGame, String> {
+        let mut saved_game = SavedGame {
+            date: String::new(),
+            variant: Variant::Classic,
+            size: BoardSize::new(3, 3, 1),
+            win_length: 3,
+            player1: String::new(),
+            player1_kind: String::new(),
+            player2: String::new(),
+            player2_kind: String::new(),
+            result: None,
+            moves: Vec::new(),
+            markers: Vec::new(),
+            seed: None,
+        };
+
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                let (key, value) = parse_header(line)?;
+                match key {
+                    "Variant" => {
+                        saved_game.variant = Variant::from_name(value)
+                            .ok_or_else(|| format!("Unknown variant: {}", value))?
+                    }
+                    "Date" => saved_game.date = String::from(value),
+                    "Board" => {
+                        saved_game.size = BoardSize::from_text(value)
+                            .ok_or_else(|| format!("Bad Board header: {}", value))?
+                    }
+                    "WinLength" => {
+                        saved_game.win_length = value
+                            .parse()
+                            .map_err(|_| format!("Bad WinLength header: {}", value))?
+                    }
+                    "Player1" => saved_game.player1 = String::from(value),
+                    "Player1Type" => saved_game.player1_kind = String::from(value),
+                    "Player2" => saved_game.player2 = String::from(value),
+                    "Player2Type" => saved_game.player2_kind = String::from(value),
+                    "Result" => saved_game.result = result_from_text(value)?,
+                    "Seed" => {
+                        saved_game.seed = Some(
+                            value
+                                .parse()
+                                .map_err(|_| format!("Bad Seed header: {}", value))?,
+                        )
+                    }
+                    // Unknown headers are allowed, so files can carry extra notes.
+                    _ => {}
+                }
+                continue;
+            }
+
+            for token in line.split_whitespace() {
+                if token.ends_with('.') || result_from_text(token).is_ok() {
+                    continue;
+                }
+                let (marker, notation) = match (token.strip_prefix('X'), token.strip_prefix('O')) {
+                    (Some(notation), _) => (Some(Marker::X), notation),
+                    (_, Some(notation)) => (Some(Marker::O), notation),
+                    _ => (None, token),
+                };
+                saved_game.moves.push(notation_to_cell(notation)?);
+                saved_game.markers.extend(marker);
+            }
+        }
+
+        let size = saved_game.size;
+        if saved_game.win_length == 0
+            || (saved_game.win_length > size.width && saved_game.win_length > size.height)
+        {
+            return Err(format!(
+                "A {} board can't fit {} in a row",
+                size, saved_game.win_length
+            ));
+        }
+        let wild = saved_game.variant == Variant::Wild;
+        if wild && saved_game.markers.len() != saved_game.moves.len() {
+            return Err(String::from("Every move in a Wild game needs a marker"));
+        }
+        if !wild && !saved_game.markers.is_empty() {
+            return Err(String::from("Only moves in a Wild game have markers"));
+        }
+        let qubic = saved_game.variant == Variant::Qubic;
+        let cube = BoardSize::new(size.height, size.height, size.height);
+        if qubic && (size != cube || saved_game.win_length != size.height) {
+            return Err(format!(
+                "A Qubic board should be {} with {} in a row",
+                cube, size.height
+            ));
+        }
+        if !qubic && size.layers != 1 {
+            return Err(format!("Only a Qubic board has layers, not {}", size));
+        }
+        Ok(saved_game)
+    }
+
+    // The empty board the game was played on.
+    pub fn new_board(&self) -> Board {
+        match self.variant {
+            Variant::Qubic => Board::cube(self.size.width),
+            Variant::Gravity => {
+                Board::with_gravity(self.size.width, self.size.height, self.win_length)
+            }
+            _ => Board::with_size(self.size.width, self.size.height, self.win_length),
+        }
+    }
+}
+
+// Splits a header line like `[Player1 "Human"]` into its key and value.
+fn parse_header(line: &str) -> Result<(&str, &str), String> {
+    let inner = line
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or_else(|| format!("Bad header: {}", line))?;
+    let (key, value) = inner
+        .split_once(' ')
+        .ok_or_else(|| format!("Bad header: {}", line))?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+        .ok_or_else(|| format!("Header value should be quoted: {}", line))?;
+    Ok((key, value))
+}
+
+fn result_to_text(result: Option<Winner>) -> &'static str {
+    match result {
+        Some(Winner::Player1) => "1-0",
+        Some(Winner::Player2) => "0-1",
+        Some(Winner::None) => "1/2-1/2",
+        None => "*",
+    }
+}
+
+fn result_from_text(text: &str) -> Result<Option<Winner>, String> {
+    match text {
+        "1-0" => Ok(Some(Winner::Player1)),
+        "0-1" => Ok(Some(Winner::Player2)),
+        "1/2-1/2" => Ok(Some(Winner::None)),
+        "*" => Ok(None),
+        _ => Err(format!("Unknown result: {}", text)),
+    }
+}
+
+pub fn cell_to_notation(cell_coord: CellCoord) -> String {
+    let column = (b'a' + cell_coord.column as u8) as char;
+    format!("{}{}", column, cell_coord.row + 1)
+}
+
+pub fn notation_to_cell(notation: &str) -> Result<CellCoord, String> {
+    let bad_move = || format!("Bad move: {}", notation);
+    let mut chars = notation.chars();
+    let column = chars
+        .next()
+        .filter(char::is_ascii_lowercase)
+        .ok_or_else(bad_move)?;
+    let row: usize = chars.as_str().parse().map_err(|_| bad_move())?;
+    if row == 0 {
+        return Err(bad_move());
+    }
+    Ok(CellCoord::new(row - 1, (column as u8 - b'a') as usize))
+}
+
+// Today's date in the YYYY.MM.DD form used by the Date header.
+pub fn get_today() -> String {
+    let (year, month, day, _) = get_utc_now();
+    format!("{:04}.{:02}.{:02}", year, month, day)
+}
+
+// Returns the current UTC (year, month, day, seconds into the day).
+fn get_utc_now() -> (i64, u32, u32, u64) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (year, month, day) = get_civil_date((seconds / 86_400) as i64);
+    (year, month, day, seconds % 86_400)
+}
+
+// The standard library only gives us seconds since the epoch, so we convert
+// days since the epoch to a calendar date ourselves, using Howard Hinnant's
+// civil_from_days algorithm.
+fn get_civil_date(days_since_epoch: i64) -> (i64, u32, u32) {
+    let days = days_since_epoch + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Everything miktactoe keeps between runs lives under
+// $XDG_DATA_HOME/miktactoe, falling back to ~/.local/share/miktactoe.
+pub fn get_data_dir() -> PathBuf {
+    let base = match env::var_os("XDG_DATA_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => {
+            let home = env::var_os("HOME").unwrap_or_else(|| ".".into());
+            Path::new(&home).join(".local").join("share")
+        }
+    };
+    base.join("miktactoe")
+}
+
+pub fn get_save_dir() -> PathBuf {
+    get_data_dir().join("games")
+}
+
+// Writes the game to a new, timestamped file in the save directory and
+// returns the file's path.
+pub fn save_game(saved_game: &SavedGame) -> io::Result<PathBuf> {
+    let dir = get_save_dir();
+    fs::create_dir_all(&dir)?;
+
+    let (year, month, day, seconds) = get_utc_now();
+    let stem = format!(
+        "game-{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+    // Saving twice in the same second shouldn't overwrite the first save.
+    let mut path = dir.join(format!("{}.{}", stem, SAVE_EXTENSION));
+    let mut copy = 1;
+    while path.exists() {
+        copy += 1;
+        path = dir.join(format!("{}-{}.{}", stem, copy, SAVE_EXTENSION));
+    }
+
+    fs::write(&path, saved_game.to_text())?;
+    Ok(path)
+}
+
+pub fn load_game(path: &Path) -> io::Result<SavedGame> {
+    let text = fs::read_to_string(path)?;
+    SavedGame::from_text(&text)
+        .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+// Lists the saved games, newest first. The file names start with a
+// timestamp, so sorting by name sorts by age.
+pub fn list_saved_games() -> io::Result<Vec<PathBuf>> {
+    let dir = get_save_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension() == Some(OsStr::new(SAVE_EXTENSION)))
+        .collect();
+    paths.sort();
+    paths.reverse();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_game() -> SavedGame {
+        SavedGame {
+            date: String::from("2026.10.17"),
+            variant: Variant::Classic,
+            size: BoardSize::new(3, 3, 1),
+            win_length: 3,
+            player1: String::from("Human"),
+            player1_kind: String::from("Human"),
+            player2: String::from("Optimal"),
+            player2_kind: String::from("Optimal"),
+            result: None,
+            moves: vec![
+                CellCoord::new(1, 1),
+                CellCoord::new(0, 0),
+                CellCoord::new(2, 2),
+            ],
+            markers: Vec::new(),
+            seed: None,
+        }
+    }
+
+    #[test]
+    fn writes_notation() {
+        assert_eq!(cell_to_notation(CellCoord::new(0, 0)), "a1");
+        assert_eq!(cell_to_notation(CellCoord::new(2, 1)), "b3");
+        assert_eq!(cell_to_notation(CellCoord::new(5, 6)), "g6");
+    }
+
+    #[test]
+    fn reads_notation() {
+        assert_eq!(notation_to_cell("c1"), Ok(CellCoord::new(0, 2)));
+        assert_eq!(notation_to_cell("a12"), Ok(CellCoord::new(11, 0)));
+        assert!(notation_to_cell("a0").is_err());
+        assert!(notation_to_cell("B2").is_err());
+        assert!(notation_to_cell("b").is_err());
+    }
+
+    #[test]
+    fn converts_days_to_dates() {
+        assert_eq!(get_civil_date(0), (1970, 1, 1));
+        assert_eq!(get_civil_date(11_016), (2000, 2, 29));
+        assert_eq!(get_civil_date(20_743), (2026, 10, 17));
+    }
+
+    #[test]
+    fn writes_text() {
+        let text = sample_game().to_text();
+        assert!(text.contains("[Player2Type \"Optimal\"]\n"));
+        assert!(text.contains("[Result \"*\"]\n"));
+        assert!(!text.contains("Variant"));
+        assert!(text.ends_with("\n1. b2 a1 2. c3 *\n"));
+    }
+
+    #[test]
+    fn round_trips_text() {
+        let mut saved_game = sample_game();
+        assert_eq!(
+            SavedGame::from_text(&saved_game.to_text()),
+            Ok(saved_game.clone())
+        );
+
+        saved_game.seed = Some(u64::MAX);
+        assert!(saved_game
+            .to_text()
+            .contains("[Seed \"18446744073709551615\"]\n"));
+        assert_eq!(
+            SavedGame::from_text(&saved_game.to_text()),
+            Ok(saved_game.clone())
+        );
+
+        saved_game.result = Some(Winner::Player2);
+        saved_game.size = BoardSize::new(7, 6, 1);
+        saved_game.win_length = 4;
+        assert_eq!(
+            SavedGame::from_text(&saved_game.to_text()),
+            Ok(saved_game.clone())
+        );
+
+        saved_game.variant = Variant::Ultimate;
+        assert!(saved_game.to_text().contains("[Variant \"Ultimate\"]\n"));
+        assert_eq!(
+            SavedGame::from_text(&saved_game.to_text()),
+            Ok(saved_game.clone())
+        );
+
+        saved_game.variant = Variant::Qubic;
+        saved_game.size = BoardSize::new(4, 4, 4);
+        assert!(saved_game.to_text().contains("[Board \"4x4x4\"]\n"));
+        let loaded_game = SavedGame::from_text(&saved_game.to_text()).unwrap();
+        assert_eq!(loaded_game.new_board().get_layers(), 4);
+        assert_eq!(loaded_game, saved_game);
+
+        saved_game.variant = Variant::Gravity;
+        saved_game.size = BoardSize::new(7, 6, 1);
+        let loaded_game = SavedGame::from_text(&saved_game.to_text()).unwrap();
+        assert!(loaded_game.new_board().has_gravity());
+        assert_eq!(loaded_game, saved_game);
+
+        saved_game.variant = Variant::Wild;
+        saved_game.size = BoardSize::new(3, 3, 1);
+        saved_game.win_length = 3;
+        saved_game.markers = vec![Marker::O, Marker::O, Marker::X];
+        assert!(saved_game.to_text().contains("1. Ob2 Oa1 2. Xc3"));
+        assert_eq!(
+            SavedGame::from_text(&saved_game.to_text()),
+            Ok(saved_game.clone())
+        );
+    }
+
+    #[test]
+    fn rejects_bad_text() {
+        assert!(SavedGame::from_text("[Board \"3by3\"]").is_err());
+        assert!(SavedGame::from_text("[Result \"win\"]").is_err());
+        assert!(SavedGame::from_text("[WinLength \"4\"]").is_err());
+        assert!(SavedGame::from_text("1. b2 zz").is_err());
+        assert!(SavedGame::from_text("[Variant \"Huge\"]").is_err());
+        assert!(SavedGame::from_text("[Variant \"Qubic\"]\n[Board \"4x4\"]").is_err());
+        assert!(SavedGame::from_text("[Variant \"Qubic\"]\n[Board \"16x4\"]").is_err());
+        assert!(SavedGame::from_text("[Board \"3x3x3\"]").is_err());
+        assert!(SavedGame::from_text("[Board \"9x3x3\"]").is_err());
+        assert!(SavedGame::from_text("[Variant \"Wild\"]\n\n1. Xb2 a1").is_err());
+        assert!(SavedGame::from_text("1. Xb2").is_err());
+        assert!(SavedGame::from_text("[Seed \"-1\"]").is_err());
+    }
+}
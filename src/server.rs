@@ -0,0 +1,540 @@
+// The server hosts games over TCP, so people can play from different
+// terminals or machines. The protocol is plain text, one message per line,
+// so it's easy to poke at with netcat.
+//
+// Client to server:
+//
+//     LIST                     ask which games are waiting for a second player
+//     CREATE <opponent> [X|O]  start a game against `human` or a computer
+//                              (random, basic, forking, optimal, minimax),
+//                              playing X unless O is given
+//     JOIN <id>                take the open seat in a waiting game
+//     MOVE <cell>              place a marker, like MOVE b2
+//     LEAVE                    give up the current game
+//
+// Server to client:
+//
+//     WELCOME miktactoe        sent once, right after connecting
+//     GAMES <id>:<marker> ...  the open games, and the marker you'd play
+//     JOINED <id> <marker>     you're seated in a game as X or O
+//     BOARD <w>x<h> <cells> <status>
+//     OPPONENT_LEFT            the other player left, and the game is over
+//     ERROR <reason>           the last command didn't work
+//
+// Cells use the same notation as saved games: a column letter and a row
+// number, so a1 is the top left corner. A BOARD message lists every cell row
+// by row, as X, O or _ for empty, and ends with the game's status. That's
+// TURN X or TURN O while the game is on, WAITING until a second player joins,
+// and OVER X, OVER O or OVER TIE once it's finished. Every player in a game
+// gets a BOARD message whenever the board changes.
+//
+// Each connection gets its own thread that reads commands, and every game
+// runs on its own thread too. The connection threads send the game thread
+// events over a channel, so only one thread ever touches a game.
+use crate::board::Board;
+use crate::common::{CellCoord, Marker, Move};
+use crate::game::{Game, GameState, Winner};
+use crate::save;
+use crate::{new_game, PlayerTypeEntry};
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+type GameId = u32;
+
+// Lines waiting to be written to a client. Each client has a thread that
+// writes them to its socket, so lines from different threads never mix.
+type Outbox = Sender<String>;
+
+enum GameEvent {
+    Join(Marker, Outbox),
+    Move(Marker, CellCoord, Outbox),
+    Leave(Marker),
+}
+
+// A game that's waiting for a second human.
+struct OpenGame {
+    marker: Marker,
+    events: Sender<GameEvent>,
+}
+
+#[derive(Default)]
+struct Lobby {
+    next_id: GameId,
+    open_games: BTreeMap<GameId, OpenGame>,
+}
+
+type SharedLobby = Arc<Mutex<Lobby>>;
+
+pub fn run_server(address: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    println!("Serving miktactoe on {}", listener.local_addr()?);
+    serve(listener)
+}
+
+// Accepts connections until the listener fails. Tests bind to port 0 and
+// run this on a thread of their own.
+pub fn serve(listener: TcpListener) -> io::Result<()> {
+    let lobby = SharedLobby::default();
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move || {
+            // A client that drops its connection isn't the server's problem.
+            let _ = Connection::handle(stream, lobby);
+        });
+    }
+    Ok(())
+}
+
+struct Connection {
+    outbox: Outbox,
+    lobby: SharedLobby,
+    // The game we're playing in, and our marker in it.
+    seat: Option<(Marker, Sender<GameEvent>)>,
+}
+
+impl Connection {
+    fn handle(stream: TcpStream, lobby: SharedLobby) -> io::Result<()> {
+        // Messages are tiny and someone is waiting on each one, so send them
+        // right away instead of letting TCP batch them up.
+        stream.set_nodelay(true)?;
+        let (outbox, lines) = mpsc::channel::<String>();
+        let mut writer = stream.try_clone()?;
+        thread::spawn(move || {
+            for line in lines {
+                if writeln!(writer, "{}", line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut connection = Connection {
+            outbox,
+            lobby,
+            seat: None,
+        };
+        connection.send(String::from("WELCOME miktactoe"));
+
+        for line in BufReader::new(stream).lines() {
+            if let Err(reason) = connection.handle_command(&line?) {
+                connection.send(format!("ERROR {}", reason));
+            }
+        }
+        connection.leave();
+        Ok(())
+    }
+
+    fn send(&self, line: String) {
+        // This only fails once the writer thread is gone, which means the
+        // client has disconnected anyway.
+        let _ = self.outbox.send(line);
+    }
+
+    fn handle_command(&mut self, line: &str) -> Result<(), String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let command = match words.first() {
+            Some(command) => command.to_ascii_uppercase(),
+            None => return Ok(()),
+        };
+        match (command.as_str(), &words[1..]) {
+            ("LIST", []) => {
+                self.list();
+                Ok(())
+            }
+            ("CREATE", [opponent]) => self.create(opponent, Marker::X),
+            ("CREATE", [opponent, marker]) => self.create(opponent, parse_marker(marker)?),
+            ("JOIN", [id]) => {
+                let id = id.parse().map_err(|_| format!("Bad game id: {}", id))?;
+                self.join(id)
+            }
+            ("MOVE", [cell]) => self.make_move(save::notation_to_cell(cell)?),
+            ("LEAVE", []) => {
+                self.leave();
+                Ok(())
+            }
+            _ => Err(format!("Unknown command: {}", line)),
+        }
+    }
+
+    fn list(&self) {
+        let lobby = self.lobby.lock().unwrap();
+        let games: Vec<String> = lobby
+            .open_games
+            .iter()
+            .map(|(id, open_game)| format!("{}:{:?}", id, open_game.marker))
+            .collect();
+        self.send(format!("GAMES {}", games.join(" ")).trim_end().to_string());
+    }
+
+    fn create(&mut self, opponent: &str, marker: Marker) -> Result<(), String> {
+        let opponent = PlayerTypeEntry::from_kind(opponent)
+            .ok_or_else(|| format!("Unknown opponent: {}", opponent))?;
+        // Seats are player 1 (X) and player 2 (O), like everywhere else.
+        let seats = match marker {
+            Marker::X => (PlayerTypeEntry::Human, opponent),
+            Marker::O => (opponent, PlayerTypeEntry::Human),
+        };
+
+        self.leave();
+        let (events, receiver) = mpsc::channel();
+        let mut lobby = self.lobby.lock().unwrap();
+        lobby.next_id += 1;
+        let id = lobby.next_id;
+        if opponent == PlayerTypeEntry::Human {
+            lobby.open_games.insert(
+                id,
+                OpenGame {
+                    marker: Marker::opposite(marker),
+                    events: events.clone(),
+                },
+            );
+        }
+        drop(lobby);
+
+        let lobby = Arc::clone(&self.lobby);
+        thread::spawn(move || Session::run(id, seats, receiver, lobby));
+        self.take_seat(id, marker, events)
+    }
+
+    fn join(&mut self, id: GameId) -> Result<(), String> {
+        let open_game = self
+            .lobby
+            .lock()
+            .unwrap()
+            .open_games
+            .remove(&id)
+            .ok_or_else(|| format!("Game {} isn't open", id))?;
+        self.leave();
+        self.take_seat(id, open_game.marker, open_game.events)
+    }
+
+    fn take_seat(
+        &mut self,
+        id: GameId,
+        marker: Marker,
+        events: Sender<GameEvent>,
+    ) -> Result<(), String> {
+        self.send(format!("JOINED {} {:?}", id, marker));
+        events
+            .send(GameEvent::Join(marker, self.outbox.clone()))
+            .map_err(|_| format!("Game {} is over", id))?;
+        self.seat = Some((marker, events));
+        Ok(())
+    }
+
+    fn make_move(&mut self, player_move: CellCoord) -> Result<(), String> {
+        let (marker, events) = self.seat.as_ref().ok_or("You're not in a game")?;
+        let event = GameEvent::Move(*marker, player_move, self.outbox.clone());
+        if events.send(event).is_err() {
+            // The game finished, so there's nothing left to sit at.
+            self.seat = None;
+            return Err(String::from("Your game is over"));
+        }
+        Ok(())
+    }
+
+    fn leave(&mut self) {
+        if let Some((marker, events)) = self.seat.take() {
+            let _ = events.send(GameEvent::Leave(marker));
+        }
+    }
+}
+
+fn parse_marker(text: &str) -> Result<Marker, String> {
+    match text.to_ascii_uppercase().as_str() {
+        "X" => Ok(Marker::X),
+        "O" => Ok(Marker::O),
+        _ => Err(format!("Bad marker: {}", text)),
+    }
+}
+
+struct Session {
+    game: Box<dyn Game>,
+    human_count: usize,
+    players: HashMap<Marker, Outbox>,
+    // Messages for every player, sent once an event has been handled.
+    pending: Vec<String>,
+    lobby: SharedLobby,
+}
+
+impl Session {
+    // Plays one game, until it's finished or a player leaves.
+    fn run(
+        id: GameId,
+        seats: (PlayerTypeEntry, PlayerTypeEntry),
+        events: Receiver<GameEvent>,
+        lobby: SharedLobby,
+    ) {
+        let human_count = [seats.0, seats.1]
+            .iter()
+            .filter(|&&seat| seat == PlayerTypeEntry::Human)
+            .count();
+        let mut session = Session {
+            game: new_game(seats.0, seats.1, Board::new(), rand::random()),
+            human_count,
+            players: HashMap::new(),
+            pending: Vec::new(),
+            lobby,
+        };
+
+        while let Ok(event) = events.recv() {
+            if session.handle_event(event) {
+                // Close the channel before anyone hears the game is over, so
+                // a move sent after that fails right away instead of going
+                // unanswered.
+                drop(events);
+                session.lobby.lock().unwrap().open_games.remove(&id);
+                session.flush();
+                return;
+            }
+            session.flush();
+        }
+    }
+
+    // Returns true once the game is over.
+    fn handle_event(&mut self, event: GameEvent) -> bool {
+        match event {
+            GameEvent::Join(marker, outbox) => {
+                self.players.insert(marker, outbox);
+                self.update()
+            }
+            GameEvent::Move(marker, player_move, outbox) => {
+                match self.make_move(marker, player_move) {
+                    Ok(()) => self.update(),
+                    Err(reason) => {
+                        let _ = outbox.send(format!("ERROR {}", reason));
+                        false
+                    }
+                }
+            }
+            GameEvent::Leave(marker) => {
+                self.players.remove(&marker);
+                self.broadcast(String::from("OPPONENT_LEFT"));
+                true
+            }
+        }
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.players.len() < self.human_count
+    }
+
+    fn make_move(&mut self, marker: Marker, player_move: CellCoord) -> Result<(), String> {
+        if self.is_waiting() {
+            return Err(String::from("Still waiting for an opponent"));
+        }
+        if self.game.get_turn_marker() != Some(marker) {
+            return Err(String::from("It's not your turn"));
+        }
+        match self.game.validate_move(player_move) {
+            Move::Valid => {
+                self.game.make_human_move(player_move);
+                Ok(())
+            }
+            Move::AlreadyUsed => Err(format!(
+                "{} is already taken",
+                save::cell_to_notation(player_move)
+            )),
+            Move::OutOfBounds => Err(format!(
+                "{} is off the board",
+                save::cell_to_notation(player_move)
+            )),
+            Move::WrongBoard => Err(format!(
+                "{} isn't on the board you have to play in",
+                save::cell_to_notation(player_move)
+            )),
+            Move::Floating => Err(format!(
+                "{} isn't at the bottom of its column",
+                save::cell_to_notation(player_move)
+            )),
+        }
+    }
+
+    // Lets the server's computer move if it's its turn, then sends everyone
+    // the board. Returns true once the game is over.
+    fn update(&mut self) -> bool {
+        if !self.is_waiting() {
+            while self.game.get_game_state() != GameState::Done && !self.game.is_human_turn() {
+                self.game.run();
+            }
+        }
+        let message = self.get_board_message();
+        self.broadcast(message);
+        self.game.get_game_state() == GameState::Done
+    }
+
+    fn get_board_message(&self) -> String {
+        let size = self.game.get_board_size();
+        let cells: String = (0..size.get_cell_count())
+            .map(|index| self.game.get_cellstate_char(index))
+            .collect();
+        let status = match (self.game.get_game_state(), self.game.get_turn_marker()) {
+            (GameState::Done, _) => match self.game.get_winner() {
+                Winner::Player1 => String::from("OVER X"),
+                Winner::Player2 => String::from("OVER O"),
+                Winner::None => String::from("OVER TIE"),
+            },
+            _ if self.is_waiting() => String::from("WAITING"),
+            (_, Some(marker)) => format!("TURN {:?}", marker),
+            (_, None) => String::from("WAITING"),
+        };
+        format!("BOARD {} {} {}", size, cells, status)
+    }
+
+    fn broadcast(&mut self, line: String) {
+        self.pending.push(line);
+    }
+
+    fn flush(&mut self) {
+        for line in self.pending.drain(..) {
+            for outbox in self.players.values() {
+                let _ = outbox.send(line.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct TestClient {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl TestClient {
+        fn connect(address: &str) -> TestClient {
+            let stream = TcpStream::connect(address).unwrap();
+            stream.set_nodelay(true).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let mut client = TestClient {
+                writer: stream.try_clone().unwrap(),
+                reader: BufReader::new(stream),
+            };
+            assert_eq!(client.read(), "WELCOME miktactoe");
+            client
+        }
+
+        fn send(&mut self, line: &str) {
+            writeln!(self.writer, "{}", line).unwrap();
+        }
+
+        fn read(&mut self) -> String {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+            line.trim_end().to_string()
+        }
+
+        fn request(&mut self, line: &str) -> String {
+            self.send(line);
+            self.read()
+        }
+    }
+
+    fn start_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || serve(listener));
+        address
+    }
+
+    #[test]
+    fn two_humans_play_a_game() {
+        let address = start_server();
+        let mut alice = TestClient::connect(&address);
+        let mut bob = TestClient::connect(&address);
+
+        assert_eq!(alice.request("CREATE human"), "JOINED 1 X");
+        assert_eq!(alice.read(), "BOARD 3x3 _________ WAITING");
+        assert_eq!(
+            alice.request("MOVE a1"),
+            "ERROR Still waiting for an opponent"
+        );
+        assert_eq!(bob.request("LIST"), "GAMES 1:O");
+
+        assert_eq!(bob.request("JOIN 1"), "JOINED 1 O");
+        assert_eq!(bob.read(), "BOARD 3x3 _________ TURN X");
+        assert_eq!(alice.read(), "BOARD 3x3 _________ TURN X");
+        assert_eq!(bob.request("LIST"), "GAMES");
+
+        assert_eq!(bob.request("MOVE b2"), "ERROR It's not your turn");
+        for (player, cell) in [("alice", "a1"), ("bob", "b2"), ("alice", "b1")] {
+            match player {
+                "alice" => alice.send(&format!("MOVE {}", cell)),
+                _ => bob.send(&format!("MOVE {}", cell)),
+            }
+            alice.read();
+            bob.read();
+        }
+        assert_eq!(bob.request("MOVE a1"), "ERROR a1 is already taken");
+        bob.send("MOVE c3");
+        assert_eq!(alice.read(), "BOARD 3x3 XX__O___O TURN X");
+        bob.read();
+
+        alice.send("MOVE c1");
+        assert_eq!(alice.read(), "BOARD 3x3 XXX_O___O OVER X");
+        assert_eq!(bob.read(), "BOARD 3x3 XXX_O___O OVER X");
+        assert_eq!(bob.request("MOVE a3"), "ERROR Your game is over");
+    }
+
+    #[test]
+    fn human_plays_server_computer() {
+        let address = start_server();
+        let mut client = TestClient::connect(&address);
+
+        // Optimal plays X, so it has already taken the center by the time
+        // we see the board.
+        assert_eq!(client.request("CREATE optimal O"), "JOINED 1 O");
+        assert_eq!(client.read(), "BOARD 3x3 ____X____ TURN O");
+        client.send("MOVE a1");
+        let board = client.read();
+        let cells: Vec<char> = board.split_whitespace().nth(2).unwrap().chars().collect();
+        assert_eq!((cells[0], cells[4]), ('O', 'X'), "{}", board);
+        assert_eq!(cells.iter().filter(|&&cell| cell == 'X').count(), 2);
+        assert!(board.ends_with("TURN O"), "{}", board);
+    }
+
+    #[test]
+    fn leaving_ends_the_game() {
+        let address = start_server();
+        let mut alice = TestClient::connect(&address);
+        let mut bob = TestClient::connect(&address);
+
+        alice.request("CREATE human O");
+        alice.read();
+        assert_eq!(bob.request("JOIN 1"), "JOINED 1 X");
+        bob.read();
+        alice.read();
+
+        // Dropping the connection counts as leaving.
+        drop(bob);
+        assert_eq!(alice.read(), "OPPONENT_LEFT");
+        assert_eq!(alice.request("MOVE a1"), "ERROR Your game is over");
+    }
+
+    #[test]
+    fn rejects_bad_commands() {
+        let address = start_server();
+        let mut client = TestClient::connect(&address);
+        assert_eq!(client.request("FLY"), "ERROR Unknown command: FLY");
+        assert_eq!(client.request("JOIN 7"), "ERROR Game 7 isn't open");
+        assert_eq!(
+            client.request("CREATE wizard"),
+            "ERROR Unknown opponent: wizard"
+        );
+        assert_eq!(client.request("MOVE b2"), "ERROR You're not in a game");
+        assert_eq!(client.request("CREATE human Y"), "ERROR Bad marker: Y");
+    }
+}
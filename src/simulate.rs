@@ -0,0 +1,319 @@
+// The `simulate` command plays computers against each other without the TUI,
+// as fast as they can move, and prints some statistics about the results:
+//
+//     miktactoe simulate --p1 random --p2 optimal --games 10000 --seed 42
+//
+// Player 1 plays X and moves first. This is mostly for checking that the
+// stronger computers really are stronger, like making sure OptimalAI never
+// loses. The same seed always plays the same games, whichever computers
+// are playing. When no seed is given, the report says which one was picked,
+// so the run can be repeated.
+//
+// A player can also be a program speaking the engine protocol (see
+// engine.rs), which is how to find out whether a bot holds up against
+// OptimalAI:
+//
+//     miktactoe simulate --p1 "engine:./my-bot --depth 3" --p2 optimal
+//
+// Results call it by its command, unless it's given a name first, like
+// "engine:Deep=./my-bot --depth 3".
+use crate::board::Board;
+use crate::common::CellCoord;
+use crate::game::{Game, GameState, Winner};
+use crate::player::external;
+use crate::save;
+use crate::{new_game, PlayerTypeEntry};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+const USAGE: &str =
+    "usage: miktactoe simulate --p1 <player> --p2 <player> [--games <count>] [--seed <seed>]
+players: random, basic, forking, optimal, minimax, engine:[<name>=]<command>";
+
+#[derive(Debug, PartialEq)]
+struct SimulationConfig {
+    player1: PlayerTypeEntry,
+    player2: PlayerTypeEntry,
+    games: usize,
+    seed: u64,
+}
+
+#[derive(Debug, PartialEq, Default)]
+struct SimulationStats {
+    games: usize,
+    player1_wins: usize,
+    player2_wins: usize,
+    draws: usize,
+    total_moves: usize,
+    // Sorted by coordinate, so the report lists cells in reading order.
+    first_moves: BTreeMap<CellCoord, usize>,
+}
+
+impl SimulationConfig {
+    fn from_args(args: &[String]) -> Result<SimulationConfig, String> {
+        let mut player1 = None;
+        let mut player2 = None;
+        let mut games = 1000;
+        let mut seed = rand::random();
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut get_value = || {
+                args.next()
+                    .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))
+            };
+            match arg.as_str() {
+                "--p1" => player1 = Some(parse_player(get_value()?)?),
+                "--p2" => player2 = Some(parse_player(get_value()?)?),
+                "--games" => {
+                    let value = get_value()?;
+                    games = value
+                        .parse()
+                        .map_err(|_| format!("--games should be a number, not {}", value))?;
+                }
+                "--seed" => {
+                    let value = get_value()?;
+                    seed = value
+                        .parse()
+                        .map_err(|_| format!("--seed should be a number, not {}", value))?;
+                }
+                _ => return Err(format!("Unknown option: {}\n{}", arg, USAGE)),
+            }
+        }
+
+        match (player1, player2) {
+            (Some(player1), Some(player2)) => Ok(SimulationConfig {
+                player1,
+                player2,
+                games,
+                seed,
+            }),
+            _ => Err(format!("Both players are needed\n{}", USAGE)),
+        }
+    }
+}
+
+// Also used by the tournament command.
+pub fn parse_player(name: &str) -> Result<PlayerTypeEntry, String> {
+    if let Some(spec) = name.strip_prefix("engine:") {
+        // An engine can be given a name to show instead of its command, like
+        // "engine:FastBot=./my-bot --fast".
+        let (name, command) = match spec.split_once('=') {
+            Some((name, command)) if !name.is_empty() && !name.contains(char::is_whitespace) => {
+                (name, command)
+            }
+            _ => (spec, spec),
+        };
+        external::check_engine(command)
+            .map_err(|error| format!("Couldn't start engine {}: {}", command, error))?;
+        // Players borrow their names and commands for as long as the game
+        // lasts. A simulation runs until the program exits, so leaking the
+        // one copy of each is simplest.
+        let leak = |text: &str| -> &'static str { Box::leak(text.to_string().into_boxed_str()) };
+        return Ok(PlayerTypeEntry::External {
+            name: leak(name),
+            command: leak(command),
+        });
+    }
+    match PlayerTypeEntry::from_kind(name) {
+        Some(PlayerTypeEntry::Human) => {
+            Err(String::from("Simulations can only use computer players"))
+        }
+        Some(player) => Ok(player),
+        None => Err(format!("Unknown player: {}\n{}", name, USAGE)),
+    }
+}
+
+impl SimulationStats {
+    fn record_game(&mut self, game: &dyn Game) {
+        self.games += 1;
+        match game.get_winner() {
+            Winner::Player1 => self.player1_wins += 1,
+            Winner::Player2 => self.player2_wins += 1,
+            Winner::None => self.draws += 1,
+        }
+
+        let move_history = game.get_move_history();
+        self.total_moves += move_history.len();
+        if let Some(&first_move) = move_history.first() {
+            *self.first_moves.entry(first_move).or_insert(0) += 1;
+        }
+    }
+
+    fn get_percent(&self, count: usize) -> f64 {
+        match self.games {
+            0 => 0.0,
+            games => 100.0 * count as f64 / games as f64,
+        }
+    }
+
+    fn get_report(&self, config: &SimulationConfig) -> String {
+        let mut lines = vec![
+            format!(
+                "{} (X) vs {} (O), {} games",
+                config.player1.get_name(),
+                config.player2.get_name(),
+                self.games
+            ),
+            format!("Seed: {}", config.seed),
+            format!(
+                "Player 1 wins: {:>7} ({:.1}%)",
+                self.player1_wins,
+                self.get_percent(self.player1_wins)
+            ),
+            format!(
+                "Player 2 wins: {:>7} ({:.1}%)",
+                self.player2_wins,
+                self.get_percent(self.player2_wins)
+            ),
+            format!(
+                "Draws:         {:>7} ({:.1}%)",
+                self.draws,
+                self.get_percent(self.draws)
+            ),
+            format!(
+                "Average game length: {:.2} moves",
+                match self.games {
+                    0 => 0.0,
+                    games => self.total_moves as f64 / games as f64,
+                }
+            ),
+            String::new(),
+            String::from("First moves:"),
+        ];
+        for (&first_move, &count) in self.first_moves.iter() {
+            lines.push(format!(
+                "  {:<4}{:>7} ({:.1}%)",
+                save::cell_to_notation(first_move),
+                count,
+                self.get_percent(count)
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+fn simulate(config: &SimulationConfig) -> SimulationStats {
+    let mut stats = SimulationStats::default();
+    let mut seeds = StdRng::seed_from_u64(config.seed);
+    for _ in 0..config.games {
+        let mut game = new_game(config.player1, config.player2, Board::new(), seeds.gen());
+        while game.run() != GameState::Done {}
+        // A forfeit counts as a loss, but a broken engine should still be
+        // told apart from a weak one.
+        if let Some(reason) = game.get_forfeit() {
+            eprintln!("Forfeit: {}", reason);
+        }
+        stats.record_game(game.as_ref());
+    }
+    stats
+}
+
+pub fn run_simulation(args: &[String]) -> Result<(), String> {
+    let config = SimulationConfig::from_args(args)?;
+    let stats = simulate(&config);
+    println!("{}", stats.get_report(&config));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_args() {
+        let config =
+            SimulationConfig::from_args(&to_args("--p1 random --p2 Optimal --games 10 --seed 42"))
+                .unwrap();
+        assert_eq!(
+            config,
+            SimulationConfig {
+                player1: PlayerTypeEntry::RandomComp,
+                player2: PlayerTypeEntry::OptimalComp,
+                games: 10,
+                seed: 42,
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_bad_args() {
+        assert!(SimulationConfig::from_args(&to_args("--p1 random")).is_err());
+        assert!(SimulationConfig::from_args(&to_args("--p1 random --p2")).is_err());
+        assert!(SimulationConfig::from_args(&to_args("--p1 human --p2 basic")).is_err());
+        assert!(SimulationConfig::from_args(&to_args("--p1 basic --p2 perfect")).is_err());
+        assert!(SimulationConfig::from_args(&to_args("--p1 basic --p2 basic --games x")).is_err());
+        assert!(SimulationConfig::from_args(&to_args("--p1 basic --p2 basic --fast")).is_err());
+        assert!(SimulationConfig::from_args(&to_args("--p1 basic --p2 basic --seed -1")).is_err());
+        assert!(
+            SimulationConfig::from_args(&to_args("--p1 engine:./no-such-bot --p2 basic")).is_err()
+        );
+    }
+
+    #[test]
+    fn optimal_never_loses_to_random() {
+        let config = SimulationConfig {
+            player1: PlayerTypeEntry::RandomComp,
+            player2: PlayerTypeEntry::OptimalComp,
+            games: 200,
+            seed: 1,
+        };
+        let stats = simulate(&config);
+        assert_eq!(stats.games, 200);
+        assert_eq!(stats.player1_wins, 0);
+        assert_eq!(stats.player2_wins + stats.draws, 200);
+        assert_eq!(stats.first_moves.values().sum::<usize>(), 200);
+    }
+
+    #[test]
+    fn reports_stats() {
+        let config = SimulationConfig {
+            player1: PlayerTypeEntry::OptimalComp,
+            player2: PlayerTypeEntry::OptimalComp,
+            games: 4,
+            seed: 1,
+        };
+        let report = simulate(&config).get_report(&config);
+        assert!(report.starts_with("Optimal (X) vs Optimal (O), 4 games\nSeed: 1\n"));
+        assert!(report.contains("Draws:               4 (100.0%)"));
+        assert!(report.contains("Average game length: 9.00 moves"));
+        assert!(report.contains("  b2        4 (100.0%)"));
+    }
+
+    #[test]
+    fn same_seed_plays_same_games() {
+        let mut config = SimulationConfig {
+            player1: PlayerTypeEntry::RandomComp,
+            player2: PlayerTypeEntry::BasicComp,
+            games: 50,
+            seed: 42,
+        };
+        let stats = simulate(&config);
+        assert_eq!(simulate(&config), stats);
+
+        config.seed = 43;
+        assert_ne!(simulate(&config), stats);
+
+        // ForkingAI and OptimalAI pick between forks, which have to come out
+        // in the same order every time for their games to repeat.
+        let pairings = [
+            (PlayerTypeEntry::ForkingComp, PlayerTypeEntry::RandomComp),
+            (PlayerTypeEntry::RandomComp, PlayerTypeEntry::OptimalComp),
+            (PlayerTypeEntry::ForkingComp, PlayerTypeEntry::OptimalComp),
+        ];
+        for &(player1, player2) in pairings.iter() {
+            let config = SimulationConfig {
+                player1,
+                player2,
+                games: 300,
+                seed: 42,
+            };
+            let stats = simulate(&config);
+            assert_eq!(simulate(&config), stats);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+// Every game a person finishes against a computer is added to a history
+// file, $XDG_DATA_HOME/miktactoe/stats.tsv, so the Statistics screen can
+// show how they've done over time. It's one game per line, with tab
+// separated fields:
+//
+//     2026.10.17	Optimal	X	draw	3x3	b2 a1 c3 c1 b1 b3 a2 c2 a3
+//
+// That's the date, the computer's kind, the marker the person played, how
+// the game went for them (win, loss or draw), the board size (like 4x4x4
+// for a Qubic cube), and the moves in the notation described in save.rs.
+// Lines starting with # are comments.
+//
+// Only the games are stored. Counts and streaks are worked out from them
+// whenever they're needed, so they can't drift out of step with the history.
+use crate::common::{BoardSize, CellCoord, Marker};
+use crate::save;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+};
+
+const STATS_HEADER: &str = "# date\topponent\tside\tresult\tboard\tmoves\n";
+
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum GameResult {
+    Win,
+    Loss,
+    Draw,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct GameRecord {
+    pub date: String,
+    pub opponent: String,
+    pub marker: Marker,
+    pub result: GameResult,
+    pub size: BoardSize,
+    pub moves: Vec<CellCoord>,
+}
+
+// A person's record against one opponent, or against everyone.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RecordSummary {
+    pub wins: usize,
+    pub losses: usize,
+    pub draws: usize,
+    // The result of the last game, and how many games in a row ended that way.
+    pub streak: Option<(GameResult, usize)>,
+    pub best_win_streak: usize,
+}
+
+impl GameResult {
+    fn to_text(self) -> &'static str {
+        match self {
+            GameResult::Win => "win",
+            GameResult::Loss => "loss",
+            GameResult::Draw => "draw",
+        }
+    }
+
+    fn from_text(text: &str) -> Option<GameResult> {
+        match text {
+            "win" => Some(GameResult::Win),
+            "loss" => Some(GameResult::Loss),
+            "draw" => Some(GameResult::Draw),
+            _ => None,
+        }
+    }
+}
+
+impl GameRecord {
+    pub fn to_line(&self) -> String {
+        let moves: Vec<String> = self
+            .moves
+            .iter()
+            .map(|&cell| save::cell_to_notation(cell))
+            .collect();
+        format!(
+            "{}\t{}\t{:?}\t{}\t{}\t{}",
+            self.date,
+            self.opponent,
+            self.marker,
+            self.result.to_text(),
+            self.size,
+            moves.join(" ")
+        )
+    }
+
+    pub fn from_line(line: &str) -> Result<GameRecord, String> {
+        let bad_line = || format!("Bad game record: {}", line);
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (date, opponent, marker, result, size, moves) = match fields.as_slice() {
+            [date, opponent, marker, result, size, moves] => {
+                (date, opponent, marker, result, size, moves)
+            }
+            _ => return Err(bad_line()),
+        };
+        let marker = match *marker {
+            "X" => Marker::X,
+            "O" => Marker::O,
+            _ => return Err(bad_line()),
+        };
+        Ok(GameRecord {
+            date: date.to_string(),
+            opponent: opponent.to_string(),
+            marker,
+            result: GameResult::from_text(result).ok_or_else(bad_line)?,
+            size: BoardSize::from_text(size).ok_or_else(bad_line)?,
+            moves: moves
+                .split_whitespace()
+                .map(save::notation_to_cell)
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+pub fn parse_history(text: &str) -> Result<Vec<GameRecord>, String> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(GameRecord::from_line)
+        .collect()
+}
+
+// Adds up `games`, which should be oldest first so the streaks come out
+// right.
+pub fn summarize<'r>(games: impl Iterator<Item = &'r GameRecord>) -> RecordSummary {
+    let mut summary = RecordSummary::default();
+    let mut win_streak = 0;
+    for game in games {
+        match game.result {
+            GameResult::Win => summary.wins += 1,
+            GameResult::Loss => summary.losses += 1,
+            GameResult::Draw => summary.draws += 1,
+        }
+        summary.streak = match summary.streak {
+            Some((result, count)) if result == game.result => Some((result, count + 1)),
+            _ => Some((game.result, 1)),
+        };
+        win_streak = match game.result {
+            GameResult::Win => win_streak + 1,
+            _ => 0,
+        };
+        summary.best_win_streak = summary.best_win_streak.max(win_streak);
+    }
+    summary
+}
+
+// Each opponent's record, in the order they were first played.
+pub fn summarize_by_opponent(games: &[GameRecord]) -> Vec<(String, RecordSummary)> {
+    let mut opponents: Vec<&str> = Vec::new();
+    for game in games {
+        if !opponents.contains(&game.opponent.as_str()) {
+            opponents.push(&game.opponent);
+        }
+    }
+    opponents
+        .into_iter()
+        .map(|opponent| {
+            let summary = summarize(games.iter().filter(|game| game.opponent == opponent));
+            (opponent.to_string(), summary)
+        })
+        .collect()
+}
+
+pub fn get_stats_path() -> PathBuf {
+    save::get_data_dir().join("stats.tsv")
+}
+
+// Returns every recorded game, oldest first. No file just means no games
+// have been played yet.
+pub fn load_history() -> io::Result<Vec<GameRecord>> {
+    let text = match fs::read_to_string(get_stats_path()) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error),
+    };
+    parse_history(&text).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+}
+
+// Games are appended rather than rewriting the whole file, so a crash can at
+// worst lose the game being written.
+pub fn record_game(game: &GameRecord) -> io::Result<()> {
+    let path = get_stats_path();
+    fs::create_dir_all(save::get_data_dir())?;
+    let is_new = !path.exists();
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    if is_new {
+        file.write_all(STATS_HEADER.as_bytes())?;
+    }
+    writeln!(file, "{}", game.to_line())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(opponent: &str, result: GameResult) -> GameRecord {
+        GameRecord {
+            date: String::from("2026.10.17"),
+            opponent: String::from(opponent),
+            marker: Marker::X,
+            result,
+            size: BoardSize::new(3, 3, 1),
+            moves: vec![CellCoord::new(1, 1), CellCoord::new(0, 0)],
+        }
+    }
+
+    #[test]
+    fn round_trips_lines() {
+        let game = record("Optimal", GameResult::Draw);
+        let line = game.to_line();
+        assert_eq!(line, "2026.10.17\tOptimal\tX\tdraw\t3x3\tb2 a1");
+        assert_eq!(GameRecord::from_line(&line), Ok(game.clone()));
+
+        let text = format!("{}{}\n\n", STATS_HEADER, line);
+        assert_eq!(parse_history(&text).unwrap().len(), 1);
+
+        let game = GameRecord {
+            size: BoardSize::new(4, 4, 4),
+            ..game
+        };
+        let line = game.to_line();
+        assert!(line.contains("\t4x4x4\t"));
+        assert_eq!(GameRecord::from_line(&line), Ok(game));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(GameRecord::from_line("2026.10.17\tOptimal\tX\tdraw\t3x3").is_err());
+        assert!(GameRecord::from_line("2026.10.17\tOptimal\tZ\tdraw\t3x3\t").is_err());
+        assert!(GameRecord::from_line("2026.10.17\tOptimal\tX\tlost\t3x3\t").is_err());
+        assert!(GameRecord::from_line("2026.10.17\tOptimal\tX\twin\t3x3\tb2 ?").is_err());
+    }
+
+    #[test]
+    fn counts_results_and_streaks() {
+        let games = vec![
+            record("Basic", GameResult::Win),
+            record("Optimal", GameResult::Loss),
+            record("Basic", GameResult::Win),
+            record("Basic", GameResult::Win),
+            record("Optimal", GameResult::Draw),
+            record("Basic", GameResult::Loss),
+            record("Optimal", GameResult::Draw),
+        ];
+
+        let overall = summarize(games.iter());
+        assert_eq!((overall.wins, overall.losses, overall.draws), (3, 2, 2));
+        assert_eq!(overall.streak, Some((GameResult::Draw, 1)));
+        assert_eq!(overall.best_win_streak, 2);
+
+        let by_opponent = summarize_by_opponent(&games);
+        assert_eq!(by_opponent.len(), 2);
+        let (opponent, basic) = &by_opponent[0];
+        assert_eq!(opponent, "Basic");
+        assert_eq!((basic.wins, basic.losses, basic.draws), (3, 1, 0));
+        assert_eq!(basic.streak, Some((GameResult::Loss, 1)));
+        assert_eq!(basic.best_win_streak, 3);
+        let (opponent, optimal) = &by_opponent[1];
+        assert_eq!(opponent, "Optimal");
+        assert_eq!(optimal.streak, Some((GameResult::Draw, 2)));
+        assert_eq!(optimal.best_win_streak, 0);
+    }
+}
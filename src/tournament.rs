@@ -0,0 +1,593 @@
+// The `tournament` command plays every computer against every other one, on
+// both sides of the board, and ranks them with Elo ratings:
+//
+//     miktactoe tournament --players random,basic,forking,optimal --games 50
+//
+// Each pairing plays `--games` games with each player as X. The games are
+// played a round at a time, every pairing once per round, because Elo
+// ratings are updated after every game and depend a little on the order
+// the games come in. Playing the pairings in turn keeps any one of them
+// from pulling the ratings around at the end.
+//
+// Besides the crosstable printed at the end, `--csv <file>` and
+// `--json <file>` save the results for spreadsheets and scripts.
+use crate::board::Board;
+use crate::game::{GameState, Winner};
+use crate::simulate;
+use crate::{new_game, PlayerTypeEntry};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::fs;
+use std::path::PathBuf;
+
+const USAGE: &str =
+    "usage: miktactoe tournament [--players <player>,<player>,...] [--games <count>]
+                            [--seed <seed>] [--csv <file>] [--json <file>]
+players: random, basic, forking, optimal, minimax, engine:[<name>=]<command>";
+
+// Everyone starts here, so the ratings average out to it too.
+const START_RATING: f64 = 1500.0;
+// How far a single game can move a rating. 16 is on the low side for chess,
+// which suits tournaments with lots of games between the same players.
+const K_FACTOR: f64 = 16.0;
+
+#[derive(Debug, PartialEq)]
+struct TournamentConfig {
+    players: Vec<PlayerTypeEntry>,
+    games: usize,
+    seed: u64,
+    csv: Option<PathBuf>,
+    json: Option<PathBuf>,
+}
+
+// One player's results against one opponent.
+#[derive(Debug, PartialEq, Default, Copy, Clone)]
+struct Tally {
+    wins: usize,
+    draws: usize,
+    losses: usize,
+}
+
+#[derive(Debug, PartialEq)]
+struct TournamentResults {
+    players: Vec<PlayerTypeEntry>,
+    games: usize,
+    seed: u64,
+    // tallies[i][j] is how player i did against player j.
+    tallies: Vec<Vec<Tally>>,
+    ratings: Vec<f64>,
+}
+
+impl TournamentConfig {
+    fn from_args(args: &[String]) -> Result<TournamentConfig, String> {
+        let mut config = TournamentConfig {
+            players: vec![
+                PlayerTypeEntry::RandomComp,
+                PlayerTypeEntry::BasicComp,
+                PlayerTypeEntry::ForkingComp,
+                PlayerTypeEntry::OptimalComp,
+                PlayerTypeEntry::MinimaxComp,
+            ],
+            games: 10,
+            seed: rand::random(),
+            csv: None,
+            json: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("{} needs a value\n{}", arg, USAGE))?;
+            match arg.as_str() {
+                "--players" => {
+                    config.players = value
+                        .split(',')
+                        .map(simulate::parse_player)
+                        .collect::<Result<_, String>>()?
+                }
+                "--games" => {
+                    config.games = value
+                        .parse()
+                        .map_err(|_| format!("--games should be a number, not {}", value))?
+                }
+                "--seed" => {
+                    config.seed = value
+                        .parse()
+                        .map_err(|_| format!("--seed should be a number, not {}", value))?
+                }
+                "--csv" => config.csv = Some(PathBuf::from(value)),
+                "--json" => config.json = Some(PathBuf::from(value)),
+                _ => return Err(format!("Unknown option: {}\n{}", arg, USAGE)),
+            }
+        }
+
+        if config.players.len() < 2 {
+            return Err(format!(
+                "A tournament needs at least two players\n{}",
+                USAGE
+            ));
+        }
+        // The crosstable tells players apart by name.
+        for (index, player) in config.players.iter().enumerate() {
+            if config.players[..index].contains(player) {
+                return Err(format!("{} is listed twice", player.get_name()));
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl Tally {
+    fn get_games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    // A win is worth a point and a draw half of one, like in chess.
+    fn get_score(&self) -> f64 {
+        self.wins as f64 + self.draws as f64 / 2.0
+    }
+
+    fn add(&self, other: &Tally) -> Tally {
+        Tally {
+            wins: self.wins + other.wins,
+            draws: self.draws + other.draws,
+            losses: self.losses + other.losses,
+        }
+    }
+}
+
+// The score player 1 is expected to take from a game against player 2,
+// between 0 and 1. A 400 point lead means winning about ten times as often
+// as losing.
+fn get_expected_score(rating1: f64, rating2: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((rating2 - rating1) / 400.0))
+}
+
+fn play_tournament(config: &TournamentConfig) -> TournamentResults {
+    let count = config.players.len();
+    let mut results = TournamentResults {
+        players: config.players.clone(),
+        games: config.games,
+        seed: config.seed,
+        tallies: vec![vec![Tally::default(); count]; count],
+        ratings: vec![START_RATING; count],
+    };
+
+    let mut seeds = StdRng::seed_from_u64(config.seed);
+    for _ in 0..config.games {
+        for first in 0..count {
+            for second in 0..count {
+                if first != second {
+                    results.play_game(first, second, seeds.gen());
+                }
+            }
+        }
+    }
+    results
+}
+
+impl TournamentResults {
+    // Plays one game with player `x` as X, and records it.
+    fn play_game(&mut self, x: usize, o: usize, seed: u64) {
+        let mut game = new_game(self.players[x], self.players[o], Board::new(), seed);
+        while game.run() != GameState::Done {}
+        // Forfeits count as losses, like in `simulate`.
+        if let Some(reason) = game.get_forfeit() {
+            eprintln!("Forfeit: {}", reason);
+        }
+
+        let x_score = match game.get_winner() {
+            Winner::Player1 => {
+                self.tallies[x][o].wins += 1;
+                self.tallies[o][x].losses += 1;
+                1.0
+            }
+            Winner::Player2 => {
+                self.tallies[x][o].losses += 1;
+                self.tallies[o][x].wins += 1;
+                0.0
+            }
+            Winner::None => {
+                self.tallies[x][o].draws += 1;
+                self.tallies[o][x].draws += 1;
+                0.5
+            }
+        };
+
+        let x_expected = get_expected_score(self.ratings[x], self.ratings[o]);
+        let change = K_FACTOR * (x_score - x_expected);
+        self.ratings[x] += change;
+        self.ratings[o] -= change;
+    }
+
+    fn get_total(&self, player: usize) -> Tally {
+        self.tallies[player]
+            .iter()
+            .fold(Tally::default(), |total, tally| total.add(tally))
+    }
+
+    // Player indexes, best rated first.
+    fn get_standings(&self) -> Vec<usize> {
+        let mut standings: Vec<usize> = (0..self.players.len()).collect();
+        standings.sort_by(|&a, &b| self.ratings[b].total_cmp(&self.ratings[a]));
+        standings
+    }
+
+    // Each cell is the row player's wins, draws and losses against the
+    // column player.
+    fn get_crosstable(&self) -> String {
+        let standings = self.get_standings();
+        let name_width = self
+            .players
+            .iter()
+            .map(|player| player.get_name().len())
+            .max()
+            .unwrap_or(0)
+            .max("Player".len());
+        let cell_width = (self.games * 2).to_string().len() * 3 + 2;
+        let column_width = cell_width.max(
+            standings
+                .iter()
+                .map(|&player| self.players[player].get_name().len())
+                .max()
+                .unwrap_or(0),
+        );
+
+        let mut header = format!("{:<4}{:<name_width$}", "#", "Player");
+        for &opponent in standings.iter() {
+            header += &format!(
+                "  {:>column_width$}",
+                self.players[opponent].get_name(),
+                column_width = column_width
+            );
+        }
+        header += &format!("  {:>8}  {:>6}", "Score", "Elo");
+
+        let mut lines = vec![
+            format!(
+                "Round robin, {} games per side per pairing, seed {}",
+                self.games, self.seed
+            ),
+            String::new(),
+            header,
+        ];
+        for (rank, &player) in standings.iter().enumerate() {
+            let mut line = format!(
+                "{:<4}{:<name_width$}",
+                rank + 1,
+                self.players[player].get_name()
+            );
+            for &opponent in standings.iter() {
+                let cell = match player == opponent {
+                    true => String::from("-"),
+                    false => {
+                        let tally = self.tallies[player][opponent];
+                        format!("{}-{}-{}", tally.wins, tally.draws, tally.losses)
+                    }
+                };
+                line += &format!("  {:>column_width$}", cell, column_width = column_width);
+            }
+            let total = self.get_total(player);
+            line += &format!(
+                "  {:>8}  {:>6.0}",
+                format!("{}/{}", total.get_score(), total.get_games()),
+                self.ratings[player]
+            );
+            lines.push(line);
+        }
+        lines.push(String::new());
+        lines.push(String::from(
+            "Cells are wins-draws-losses for the row player.",
+        ));
+        lines.join("\n")
+    }
+
+    // One row per player in the order they finished, with their results
+    // against each opponent in the same wins-draws-losses form as the
+    // crosstable.
+    fn to_csv(&self) -> String {
+        let standings = self.get_standings();
+        let mut header = vec![
+            String::from("rank"),
+            String::from("player"),
+            String::from("elo"),
+            String::from("games"),
+            String::from("wins"),
+            String::from("draws"),
+            String::from("losses"),
+            String::from("score"),
+        ];
+        for &opponent in standings.iter() {
+            header.push(format!("vs {}", self.players[opponent].get_name()));
+        }
+
+        let mut rows = vec![header];
+        for (rank, &player) in standings.iter().enumerate() {
+            let total = self.get_total(player);
+            let mut row = vec![
+                (rank + 1).to_string(),
+                self.players[player].get_name().to_string(),
+                format!("{:.0}", self.ratings[player]),
+                total.get_games().to_string(),
+                total.wins.to_string(),
+                total.draws.to_string(),
+                total.losses.to_string(),
+                total.get_score().to_string(),
+            ];
+            for &opponent in standings.iter() {
+                let tally = self.tallies[player][opponent];
+                row.push(match player == opponent {
+                    true => String::new(),
+                    false => format!("{}-{}-{}", tally.wins, tally.draws, tally.losses),
+                });
+            }
+            rows.push(row);
+        }
+
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .map(|field| to_csv_field(field))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            })
+            .map(|row| row + "\n")
+            .collect()
+    }
+
+    fn to_json(&self) -> String {
+        let standings = self.get_standings();
+        let players: Vec<String> = standings
+            .iter()
+            .map(|&player| {
+                let total = self.get_total(player);
+                let results: Vec<String> = standings
+                    .iter()
+                    .filter(|&&opponent| opponent != player)
+                    .map(|&opponent| {
+                        let tally = self.tallies[player][opponent];
+                        format!(
+                            "        {}: {{\"wins\": {}, \"draws\": {}, \"losses\": {}}}",
+                            to_json_string(self.players[opponent].get_name()),
+                            tally.wins,
+                            tally.draws,
+                            tally.losses
+                        )
+                    })
+                    .collect();
+                format!(
+                    "    {{\n      \"name\": {},\n      \"elo\": {:.0},\n      \
+                     \"wins\": {},\n      \"draws\": {},\n      \"losses\": {},\n      \
+                     \"score\": {},\n      \"results\": {{\n{}\n      }}\n    }}",
+                    to_json_string(self.players[player].get_name()),
+                    self.ratings[player],
+                    total.wins,
+                    total.draws,
+                    total.losses,
+                    total.get_score(),
+                    results.join(",\n")
+                )
+            })
+            .collect();
+        format!(
+            "{{\n  \"games_per_side\": {},\n  \"seed\": {},\n  \"players\": [\n{}\n  ]\n}}\n",
+            self.games,
+            self.seed,
+            players.join(",\n")
+        )
+    }
+}
+
+// Engine commands can have commas and quotes in them.
+fn to_csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn to_json_string(text: &str) -> String {
+    let mut json = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+pub fn run_tournament(args: &[String]) -> Result<(), String> {
+    let config = TournamentConfig::from_args(args)?;
+    let results = play_tournament(&config);
+    println!("{}", results.get_crosstable());
+
+    let exports = [
+        (&config.csv, results.to_csv()),
+        (&config.json, results.to_json()),
+    ];
+    for (path, text) in exports.iter() {
+        if let Some(path) = path {
+            fs::write(path, text)
+                .map_err(|error| format!("Couldn't write {}: {}", path.display(), error))?;
+            println!("Saved {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn get_config(games: usize) -> TournamentConfig {
+        TournamentConfig {
+            players: vec![
+                PlayerTypeEntry::RandomComp,
+                PlayerTypeEntry::BasicComp,
+                PlayerTypeEntry::OptimalComp,
+            ],
+            games,
+            seed: 7,
+            csv: None,
+            json: None,
+        }
+    }
+
+    #[test]
+    fn parses_args() {
+        let config = TournamentConfig::from_args(&to_args(
+            "--players random,basic,optimal --games 20 --seed 7 --csv out.csv",
+        ))
+        .unwrap();
+        assert_eq!(
+            config,
+            TournamentConfig {
+                games: 20,
+                csv: Some(PathBuf::from("out.csv")),
+                ..get_config(20)
+            }
+        );
+
+        assert!(TournamentConfig::from_args(&to_args("--players optimal")).is_err());
+        assert!(TournamentConfig::from_args(&to_args("--players basic,basic")).is_err());
+        assert!(TournamentConfig::from_args(&to_args("--players basic,human")).is_err());
+        assert!(TournamentConfig::from_args(&to_args("--games")).is_err());
+    }
+
+    #[test]
+    fn expected_scores_follow_rating_gaps() {
+        assert_eq!(get_expected_score(1500.0, 1500.0), 0.5);
+        assert!((get_expected_score(1900.0, 1500.0) - 10.0 / 11.0).abs() < 1e-9);
+        assert!((get_expected_score(1500.0, 1900.0) - 1.0 / 11.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn ranks_stronger_computers_higher() {
+        let games_per_side = 20;
+        let config = get_config(games_per_side);
+        let results = play_tournament(&config);
+        // Every player meets every other player, and each pairing plays
+        // `games_per_side` games a side. Every game shows up in the totals
+        // of both of its players.
+        let players = config.players.len();
+        let pairings = players * (players - 1) / 2;
+        let games = pairings * games_per_side * 2;
+        let counted: usize = (0..players)
+            .map(|player| results.get_total(player).get_games())
+            .sum();
+        assert_eq!(counted, games * 2);
+        assert_eq!(
+            results.tallies[2][0].losses + results.tallies[2][1].losses,
+            0
+        );
+        assert_eq!(results.get_standings(), vec![2, 1, 0]);
+
+        // Elo only moves points between players.
+        let average: f64 = results.ratings.iter().sum::<f64>() / players as f64;
+        assert!((average - START_RATING).abs() < 1e-6);
+
+        // Fork cells come out of the board in a fixed order, so a seeded
+        // tournament always plays out the same way.
+        assert_eq!(play_tournament(&config), results);
+    }
+
+    #[test]
+    fn same_seed_plays_same_tournament() {
+        // ForkingAI and OptimalAI take the first of the forks they find, so
+        // the forks have to come out in the same order every time.
+        let config = TournamentConfig {
+            players: vec![
+                PlayerTypeEntry::RandomComp,
+                PlayerTypeEntry::ForkingComp,
+                PlayerTypeEntry::OptimalComp,
+            ],
+            ..get_config(100)
+        };
+        let results = play_tournament(&config);
+        for _ in 0..3 {
+            assert_eq!(play_tournament(&config), results);
+        }
+    }
+
+    #[test]
+    fn exports_results() {
+        let mut results = play_tournament(&get_config(1));
+        results.tallies = vec![
+            vec![
+                Tally::default(),
+                Tally {
+                    wins: 0,
+                    draws: 1,
+                    losses: 1,
+                },
+                Tally {
+                    wins: 0,
+                    draws: 0,
+                    losses: 2,
+                },
+            ],
+            vec![
+                Tally {
+                    wins: 1,
+                    draws: 1,
+                    losses: 0,
+                },
+                Tally::default(),
+                Tally {
+                    wins: 0,
+                    draws: 1,
+                    losses: 1,
+                },
+            ],
+            vec![
+                Tally {
+                    wins: 2,
+                    draws: 0,
+                    losses: 0,
+                },
+                Tally {
+                    wins: 1,
+                    draws: 1,
+                    losses: 0,
+                },
+                Tally::default(),
+            ],
+        ];
+        results.ratings = vec![1450.0, 1500.0, 1550.0];
+        results.players[1] = PlayerTypeEntry::External {
+            name: "bot \"fast\", v2",
+            command: "./bot --fast",
+        };
+
+        let csv = results.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("rank,player,elo,games,wins,draws,losses,score,vs Optimal,\"vs bot \"\"fast\"\", v2\",vs Random")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("1,Optimal,1550,4,3,1,0,3.5,,1-1-0,2-0-0")
+        );
+        assert_eq!(lines.nth(1), Some("3,Random,1450,4,0,1,3,0.5,0-0-2,0-1-1,"));
+
+        let json = results.to_json();
+        assert!(json.starts_with("{\n  \"games_per_side\": 1,\n  \"seed\": 7,\n"));
+        assert!(json.contains("\"name\": \"bot \\\"fast\\\", v2\""));
+        assert!(json.contains("\"Random\": {\"wins\": 1, \"draws\": 1, \"losses\": 0}"));
+
+        let crosstable = results.get_crosstable();
+        assert!(crosstable.contains("1   Optimal"));
+        assert!(crosstable.contains("3.5/4"));
+    }
+}
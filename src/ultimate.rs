@@ -0,0 +1,559 @@
+// Ultimate tic-tac-toe is played on a 3x3 grid of small 3x3 boards. Winning
+// a small board claims its spot on the big board, and three small boards in
+// a row wins the game. The catch is that the cell you play in decides which
+// small board your opponent has to play in next: taking the top right cell
+// of any small board sends them to the top right board. If that board has
+// already been won or filled up, they can play in any board that's still
+// open.
+//
+// The UI and saved games treat the grid as one 9x9 board, so a move is just
+// a CellCoord like any other. Cell (4, 7) is in the middle row of boards and
+// the right column, and it's the middle right cell of that board.
+use crate::board::{Board, BoardState};
+use crate::common::{BoardSize, CellCoord, Marker, Move};
+use crate::game::{Game, GameState, Record, SeatInfo, Variant, Winner};
+use crate::player::ai_optimal::MoveReason;
+use crate::player::UltimatePlayer;
+use crate::save::{self, SavedGame};
+
+// The small boards are 3x3, and so is the grid they're laid out in.
+const GRID_SIZE: usize = 3;
+pub const ULTIMATE_SIZE: usize = GRID_SIZE * GRID_SIZE;
+
+#[derive(Clone)]
+pub struct UltimateBoard {
+    // The small boards, one row of the grid after another.
+    boards: Vec<Board>,
+    // Each small board's winner goes in the matching cell of this board, so
+    // three in a row here wins the game.
+    meta_board: Board,
+    // The small board the last move sent the next player to. None at the
+    // start of the game.
+    next_board: Option<usize>,
+}
+
+impl UltimateBoard {
+    pub fn new() -> UltimateBoard {
+        UltimateBoard {
+            boards: vec![Board::new(); ULTIMATE_SIZE],
+            meta_board: Board::new(),
+            next_board: None,
+        }
+    }
+
+    // Splits a cell on the 9x9 grid into the small board it's in, and where
+    // it is on that board.
+    fn split_cell(cell: CellCoord) -> (usize, CellCoord) {
+        let board_index = (cell.row / GRID_SIZE) * GRID_SIZE + cell.column / GRID_SIZE;
+        let local_cell = CellCoord::new(cell.row % GRID_SIZE, cell.column % GRID_SIZE);
+        (board_index, local_cell)
+    }
+
+    fn join_cell(board_index: usize, local_cell: CellCoord) -> CellCoord {
+        CellCoord::new(
+            (board_index / GRID_SIZE) * GRID_SIZE + local_cell.row,
+            (board_index % GRID_SIZE) * GRID_SIZE + local_cell.column,
+        )
+    }
+
+    pub fn get_board(&self, board_index: usize) -> &Board {
+        &self.boards[board_index]
+    }
+
+    pub fn get_meta_board(&self) -> &Board {
+        &self.meta_board
+    }
+
+    pub fn get_next_board(&self) -> Option<usize> {
+        self.next_board
+    }
+
+    // 'X' or 'O' for a won board, and '_' for one that nobody has won.
+    pub fn get_board_winner(&self, board_index: usize) -> char {
+        self.meta_board.get_cellstate_char(board_index)
+    }
+
+    // A board is open until someone wins it or it fills up.
+    pub fn is_board_open(&self, board_index: usize) -> bool {
+        self.get_board_winner(board_index) == '_'
+            && !self.boards[board_index].get_valid_moves().is_empty()
+    }
+
+    // The boards the next move can go in.
+    pub fn get_open_boards(&self) -> Vec<usize> {
+        match self.next_board {
+            Some(board_index) if self.is_board_open(board_index) => vec![board_index],
+            _ => (0..ULTIMATE_SIZE)
+                .filter(|&board_index| self.is_board_open(board_index))
+                .collect(),
+        }
+    }
+
+    pub fn get_valid_moves(&self) -> Vec<CellCoord> {
+        self.get_open_boards()
+            .into_iter()
+            .flat_map(|board_index| {
+                self.boards[board_index]
+                    .get_valid_moves()
+                    .into_iter()
+                    .map(move |local_cell| Self::join_cell(board_index, local_cell))
+            })
+            .collect()
+    }
+
+    pub fn validate_move(&self, cell: CellCoord) -> Move {
+        if cell.row >= ULTIMATE_SIZE || cell.column >= ULTIMATE_SIZE {
+            return Move::OutOfBounds;
+        }
+        let (board_index, local_cell) = Self::split_cell(cell);
+        match self.boards[board_index].validate_move(local_cell) {
+            Move::Valid if !self.get_open_boards().contains(&board_index) => Move::WrongBoard,
+            valid_or_used => valid_or_used,
+        }
+    }
+
+    // Places a marker, which must be a valid move, and says how the game
+    // stands afterwards. A win holds the set completed on the big board.
+    pub fn place_marker(&mut self, cell: CellCoord, marker: Marker) -> BoardState {
+        let (board_index, local_cell) = Self::split_cell(cell);
+        let board = &mut self.boards[board_index];
+        board.place_marker(local_cell, marker);
+        self.next_board = Some(local_cell.row * GRID_SIZE + local_cell.column);
+
+        if let BoardState::Win(_) = board.check_board_state(local_cell, marker) {
+            let meta_cell = CellCoord::new(board_index / GRID_SIZE, board_index % GRID_SIZE);
+            self.meta_board.place_marker(meta_cell, marker);
+            if let BoardState::Win(set_type) = self.meta_board.check_board_state(meta_cell, marker)
+            {
+                return BoardState::Win(set_type);
+            }
+        }
+        match self.get_valid_moves().is_empty() {
+            true => BoardState::Tie,
+            false => BoardState::Playing,
+        }
+    }
+
+    // The inverse of place_marker(), given where the next player was sent
+    // before the move. Nobody can play in a board once it's won, so if the
+    // board has a winner, this move must have been the one that won it.
+    pub fn remove_marker(&mut self, cell: CellCoord, next_board: Option<usize>) {
+        let (board_index, local_cell) = Self::split_cell(cell);
+        self.boards[board_index].remove_marker(local_cell);
+        if self.get_board_winner(board_index) != '_' {
+            self.meta_board.remove_marker(CellCoord::new(
+                board_index / GRID_SIZE,
+                board_index % GRID_SIZE,
+            ));
+        }
+        self.next_board = next_board;
+    }
+
+    // `cell_index` counts across the whole 9x9 grid, a row at a time.
+    pub fn get_cellstate_char(&self, cell_index: usize) -> char {
+        let cell = CellCoord::new(cell_index / ULTIMATE_SIZE, cell_index % ULTIMATE_SIZE);
+        let (board_index, local_cell) = Self::split_cell(cell);
+        self.boards[board_index].get_cellstate_char(local_cell.row * GRID_SIZE + local_cell.column)
+    }
+
+    // The line of three that won a small board, as cells on the 9x9 grid.
+    fn get_board_line(&self, board_index: usize) -> Vec<CellCoord> {
+        let winner = self.get_board_winner(board_index);
+        let board = &self.boards[board_index];
+        board
+            .get_sets()
+            .into_iter()
+            .map(|set_type| board.get_set_coords(set_type))
+            .find(|coords| {
+                coords.iter().all(|local_cell| {
+                    board.get_cellstate_char(local_cell.row * GRID_SIZE + local_cell.column)
+                        == winner
+                })
+            })
+            .map(|coords| {
+                coords
+                    .into_iter()
+                    .map(|local_cell| Self::join_cell(board_index, local_cell))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+pub struct UltimateTicTacToe<'a, P1, P2> {
+    player1: P1,
+    player2: P2,
+    board: UltimateBoard,
+    record: Record<'a>,
+    state: GameState,
+}
+
+impl<'a, P1: UltimatePlayer<'a>, P2: UltimatePlayer<'a>> UltimateTicTacToe<'a, P1, P2> {
+    pub fn new(player1: P1, player2: P2) -> UltimateTicTacToe<'a, P1, P2> {
+        UltimateTicTacToe {
+            record: Record::new(player1.get_name(), player2.get_name(), rand::random()),
+            player1,
+            player2,
+            board: UltimateBoard::new(),
+            state: GameState::Player1Turn,
+        }
+    }
+
+    // Sets the seed the seats were made from, like `TicTacToe::seeded()`.
+    pub fn seeded(mut self, seed: u64) -> Self {
+        self.record.seed = seed;
+        self
+    }
+
+    fn apply_move(&mut self, player_move: CellCoord) {
+        self.record.record_move(player_move);
+        self.play_move(player_move);
+    }
+
+    // Works the same way as in TicTacToe: places the marker for whoever's
+    // turn it is, and leaves recording the move to the caller.
+    fn play_move(&mut self, player_move: CellCoord) {
+        let (marker, winner, next_state) = match self.state {
+            GameState::Player1Turn => (
+                self.player1.get_marker(),
+                Winner::Player1,
+                GameState::Player2Turn,
+            ),
+            GameState::Player2Turn => (
+                self.player2.get_marker(),
+                Winner::Player2,
+                GameState::Player1Turn,
+            ),
+            GameState::Done => return,
+        };
+
+        match self.board.place_marker(player_move, marker) {
+            BoardState::Win(_) => {
+                self.state = GameState::Done;
+                self.record.record_outcome(winner);
+            }
+            BoardState::Tie => {
+                self.state = GameState::Done;
+                self.record.record_outcome(Winner::None);
+            }
+            BoardState::Playing => self.state = next_state,
+        }
+    }
+
+    fn get_move_turn(move_number: usize) -> GameState {
+        match move_number % 2 {
+            0 => GameState::Player1Turn,
+            _ => GameState::Player2Turn,
+        }
+    }
+
+    fn is_human_seat(&self, state: GameState) -> bool {
+        match state {
+            GameState::Player1Turn => self.player1.is_human(),
+            GameState::Player2Turn => self.player2.is_human(),
+            GameState::Done => false,
+        }
+    }
+}
+
+impl<'a, P1: UltimatePlayer<'a>, P2: UltimatePlayer<'a>> Game for UltimateTicTacToe<'a, P1, P2> {
+    fn run(&mut self) -> GameState {
+        let comp_move = match self.state {
+            GameState::Player1Turn if !self.player1.is_human() => {
+                self.player1.get_ultimate_move(&self.board)
+            }
+            GameState::Player2Turn if !self.player2.is_human() => {
+                self.player2.get_ultimate_move(&self.board)
+            }
+            _ => return self.state,
+        };
+        self.apply_move(comp_move);
+        self.state
+    }
+
+    fn make_human_move(&mut self, player_move: CellCoord) -> GameState {
+        if self.is_human_turn() && self.board.validate_move(player_move) == Move::Valid {
+            self.apply_move(player_move);
+        }
+        self.state
+    }
+
+    fn validate_move(&self, player_move: CellCoord) -> Move {
+        self.board.validate_move(player_move)
+    }
+
+    // Takes back moves until a human's move has been taken back, like
+    // TicTacToe does. Which board each move was sent to depends on the move
+    // before it, so the simplest way back is to play the remaining moves
+    // again on a fresh board.
+    fn undo(&mut self) -> GameState {
+        let move_count = self.record.move_history.len();
+        if !(0..move_count).any(|move_number| self.is_human_seat(Self::get_move_turn(move_number)))
+        {
+            return self.state;
+        }
+
+        while self.record.undo_move().is_some() {
+            let move_number = self.record.move_history.len();
+            if self.is_human_seat(Self::get_move_turn(move_number)) {
+                break;
+            }
+        }
+        self.board = UltimateBoard::new();
+        self.state = GameState::Player1Turn;
+        self.record.record_outcome(Winner::None);
+        for player_move in self.record.move_history.clone() {
+            self.play_move(player_move);
+        }
+        self.state
+    }
+
+    fn redo(&mut self) -> GameState {
+        while self.state != GameState::Done {
+            match self.record.redo_move() {
+                Some(player_move) => self.play_move(player_move),
+                None => break,
+            }
+            if self.is_human_seat(self.state) {
+                break;
+            }
+        }
+        self.state
+    }
+
+    fn get_game_state(&self) -> GameState {
+        self.state
+    }
+
+    fn is_human_turn(&self) -> bool {
+        self.is_human_seat(self.state)
+    }
+
+    fn get_turn_marker(&self) -> Option<Marker> {
+        match self.state {
+            GameState::Player1Turn => Some(self.player1.get_marker()),
+            GameState::Player2Turn => Some(self.player2.get_marker()),
+            GameState::Done => None,
+        }
+    }
+
+    fn get_winner(&self) -> Winner {
+        self.record.winner
+    }
+
+    fn get_move_history(&self) -> &[CellCoord] {
+        &self.record.move_history
+    }
+
+    // The winning lines on each of the three small boards that won the
+    // game.
+    fn get_winning_line(&self) -> Vec<CellCoord> {
+        let marker = match self.record.winner {
+            Winner::Player1 => self.player1.get_marker(),
+            Winner::Player2 => self.player2.get_marker(),
+            Winner::None => return Vec::new(),
+        };
+        let (board_index, _) = match self.record.move_history.last() {
+            Some(&last_move) => UltimateBoard::split_cell(last_move),
+            None => return Vec::new(),
+        };
+        let meta_board = self.board.get_meta_board();
+        let meta_cell = CellCoord::new(board_index / GRID_SIZE, board_index % GRID_SIZE);
+        match meta_board.check_board_state(meta_cell, marker) {
+            BoardState::Win(set_type) => meta_board
+                .get_set_coords(set_type)
+                .into_iter()
+                .flat_map(|cell| {
+                    self.board
+                        .get_board_line(cell.row * GRID_SIZE + cell.column)
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn get_seats(&self) -> [SeatInfo<'_>; 2] {
+        [
+            SeatInfo {
+                name: self.player1.get_name(),
+                kind: self.player1.get_kind(),
+                marker: self.player1.get_marker(),
+            },
+            SeatInfo {
+                name: self.player2.get_name(),
+                kind: self.player2.get_kind(),
+                marker: self.player2.get_marker(),
+            },
+        ]
+    }
+
+    // OptimalAI's rules are about a single board, so they don't give useful
+    // hints here.
+    fn get_hint(&self) -> Option<(CellCoord, MoveReason)> {
+        None
+    }
+
+    fn get_variant(&self) -> Variant {
+        Variant::Ultimate
+    }
+
+    fn get_ultimate_board(&self) -> Option<&UltimateBoard> {
+        Some(&self.board)
+    }
+
+    fn get_cellstate_char(&self, cell_index: usize) -> char {
+        self.board.get_cellstate_char(cell_index)
+    }
+
+    fn get_board_size(&self) -> BoardSize {
+        BoardSize::new(ULTIMATE_SIZE, ULTIMATE_SIZE, 1)
+    }
+
+    fn reset(&mut self) {
+        let (player1_seed, player2_seed) = self.record.next_round();
+        self.player1 = self.player1.reseeded(player1_seed);
+        self.player2 = self.player2.reseeded(player2_seed);
+        self.board = UltimateBoard::new();
+        self.state = GameState::Player1Turn;
+    }
+
+    fn to_saved_game(&self) -> SavedGame {
+        SavedGame {
+            date: save::get_today(),
+            variant: Variant::Ultimate,
+            size: self.get_board_size(),
+            win_length: GRID_SIZE,
+            player1: String::from(self.record.player1),
+            player1_kind: String::from(self.player1.get_kind()),
+            player2: String::from(self.record.player2),
+            player2_kind: String::from(self.player2.get_kind()),
+            result: match self.state {
+                GameState::Done => Some(self.record.winner),
+                _ => None,
+            },
+            moves: self.record.move_history.clone(),
+            markers: Vec::new(),
+            seed: Some(self.record.seed),
+        }
+    }
+
+    fn load_moves(&mut self, moves: &[CellCoord]) -> Result<GameState, String> {
+        for (move_number, &player_move) in moves.iter().enumerate() {
+            let notation = save::cell_to_notation(player_move);
+            if self.state == GameState::Done {
+                return Err(format!(
+                    "Move {} ({}) comes after the game ended",
+                    move_number + 1,
+                    notation
+                ));
+            }
+            if self.board.validate_move(player_move) != Move::Valid {
+                return Err(format!(
+                    "Move {} ({}) isn't a legal move",
+                    move_number + 1,
+                    notation
+                ));
+            }
+            self.apply_move(player_move);
+        }
+        Ok(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::ai_random::RandomAI;
+    use crate::player::human::Human;
+    use crate::player::Player;
+
+    fn play(board: &mut UltimateBoard, moves: &[(usize, usize)]) -> BoardState {
+        let mut marker = Marker::X;
+        let mut state = BoardState::Playing;
+        for &(row, column) in moves {
+            let cell = CellCoord::new(row, column);
+            assert_eq!(board.validate_move(cell), Move::Valid, "{:?}", cell);
+            state = board.place_marker(cell, marker);
+            marker = Marker::opposite(marker);
+        }
+        state
+    }
+
+    #[test]
+    fn sends_next_player_to_matching_board() {
+        let mut board = UltimateBoard::new();
+        assert_eq!(board.get_valid_moves().len(), 81);
+
+        // The top right cell of the middle board sends O to the top right board.
+        play(&mut board, &[(3, 5)]);
+        assert_eq!(board.get_open_boards(), vec![2]);
+        assert_eq!(board.validate_move(CellCoord::new(0, 6)), Move::Valid);
+        assert_eq!(board.validate_move(CellCoord::new(0, 0)), Move::WrongBoard);
+        assert_eq!(board.validate_move(CellCoord::new(3, 5)), Move::AlreadyUsed);
+        assert_eq!(board.validate_move(CellCoord::new(9, 0)), Move::OutOfBounds);
+    }
+
+    #[test]
+    fn won_boards_free_the_next_move() {
+        let mut board = UltimateBoard::new();
+        // X takes the top row of the top left board, while O keeps sending
+        // X back there.
+        play(&mut board, &[(0, 1), (0, 3), (0, 2), (0, 6), (0, 0)]);
+        assert_eq!(board.get_board_winner(0), 'X');
+        // X's last move sends O to the board X just won, so O can play in
+        // any other board.
+        assert!(!board.is_board_open(0));
+        assert_eq!(board.get_open_boards().len(), 8);
+        assert_eq!(board.validate_move(CellCoord::new(1, 1)), Move::WrongBoard);
+
+        // Taking the winning move back reopens the board.
+        board.remove_marker(CellCoord::new(0, 0), Some(0));
+        assert_eq!(board.get_board_winner(0), '_');
+        assert_eq!(board.get_open_boards(), vec![0]);
+    }
+
+    #[test]
+    fn three_boards_in_a_row_win() {
+        let mut game = UltimateTicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        );
+        // X ends up with the left column of boards, winning the top left
+        // one last with c3.
+        let moves = [
+            "a1", "a2", "a4", "a3", "a7", "b1", "d1", "c1", "g1", "c2", "g4", "b3", "e7", "d2",
+            "b4", "d3", "b7", "e1", "f1", "g2", "c4", "g3", "c7", "h1", "e2", "d4", "b2", "d5",
+            "c3",
+        ];
+        let moves: Vec<CellCoord> = moves
+            .iter()
+            .map(|notation| save::notation_to_cell(notation).unwrap())
+            .collect();
+        assert_eq!(game.load_moves(&moves), Ok(GameState::Done));
+        assert_eq!(game.get_winner(), Winner::Player1);
+        assert_eq!(game.get_winning_line().len(), 9);
+
+        // Undo takes back X's winning move, and X can play it again.
+        assert_eq!(game.undo(), GameState::Player1Turn);
+        assert_eq!(game.get_winning_line(), Vec::new());
+        assert_eq!(game.redo(), GameState::Done);
+    }
+
+    #[test]
+    fn random_players_finish_games() {
+        for seed in 0..20 {
+            let mut game = UltimateTicTacToe::new(
+                RandomAI::with_seed("Random 1", Marker::X, seed),
+                RandomAI::with_seed("Random 2", Marker::O, seed + 100),
+            );
+            while game.run() != GameState::Done {}
+            let saved_game = game.to_saved_game();
+            let mut replayed = UltimateTicTacToe::new(
+                Human::new("Player 1", Marker::X),
+                Human::new("Player 2", Marker::O),
+            );
+            assert_eq!(replayed.load_moves(&saved_game.moves), Ok(GameState::Done));
+            assert_eq!(replayed.get_winner(), game.get_winner());
+        }
+    }
+}
@@ -0,0 +1,392 @@
+// Wild tic-tac-toe is played on an ordinary 3x3 board, except that on each
+// turn a player can place either an X or an O, and whoever completes a line
+// wins, whichever marker it's made of. So a line with two of anything in it
+// is a win for whoever moves next, and the game is about not leaving one.
+//
+// Players still sit in the X and O seats, but that only decides who moves
+// first. The markers on the board don't belong to anyone, so the game keeps
+// track of whose move was whose by the move number, the same as a saved game
+// does.
+use crate::board::{Board, BoardState};
+use crate::common::{BoardSize, CellCoord, Marker, Move, WildMove};
+use crate::game::{Game, GameState, Record, SeatInfo, Variant, Winner};
+use crate::player::ai_optimal::MoveReason;
+use crate::player::WildPlayer;
+use crate::save::{self, SavedGame};
+
+pub struct WildTicTacToe<'a, P1, P2> {
+    player1: P1,
+    player2: P2,
+    board: Board,
+    record: Record<'a>,
+    // The record only keeps cells. The markers of the moves on the board are
+    // on the board, and the markers of moves that were undone are kept here,
+    // most recent last, so they can be redone.
+    undone_markers: Vec<Marker>,
+    state: GameState,
+    // The marker a human will place with their next move. The UI switches
+    // it with `switch_marker()`.
+    marker: Marker,
+}
+
+impl<'a, P1: WildPlayer<'a>, P2: WildPlayer<'a>> WildTicTacToe<'a, P1, P2> {
+    pub fn new(player1: P1, player2: P2) -> WildTicTacToe<'a, P1, P2> {
+        WildTicTacToe {
+            record: Record::new(player1.get_name(), player2.get_name(), rand::random()),
+            player1,
+            player2,
+            board: Board::new(),
+            undone_markers: Vec::new(),
+            state: GameState::Player1Turn,
+            marker: Marker::X,
+        }
+    }
+
+    // Sets the seed the seats were made from, like `TicTacToe::seeded()`.
+    pub fn seeded(mut self, seed: u64) -> Self {
+        self.record.seed = seed;
+        self
+    }
+
+    // A new move means the undone ones can't be redone, so their markers go
+    // along with them.
+    fn apply_move(&mut self, wild_move: WildMove) {
+        self.record.record_move(wild_move.cell);
+        self.undone_markers.clear();
+        self.play_move(wild_move);
+    }
+
+    // Works the same way as in TicTacToe, except that the marker comes with
+    // the move, and completing a line wins for whoever made the move.
+    fn play_move(&mut self, wild_move: WildMove) {
+        let (winner, next_state) = match self.state {
+            GameState::Player1Turn => (Winner::Player1, GameState::Player2Turn),
+            GameState::Player2Turn => (Winner::Player2, GameState::Player1Turn),
+            GameState::Done => return,
+        };
+
+        self.board.place_marker(wild_move.cell, wild_move.marker);
+        match self
+            .board
+            .check_board_state(wild_move.cell, wild_move.marker)
+        {
+            BoardState::Win(_) => {
+                self.state = GameState::Done;
+                self.record.record_outcome(winner);
+            }
+            BoardState::Tie => {
+                self.state = GameState::Done;
+                self.record.record_outcome(Winner::None);
+            }
+            BoardState::Playing => self.state = next_state,
+        }
+    }
+
+    fn get_move_turn(move_number: usize) -> GameState {
+        match move_number % 2 {
+            0 => GameState::Player1Turn,
+            _ => GameState::Player2Turn,
+        }
+    }
+
+    fn is_human_seat(&self, state: GameState) -> bool {
+        match state {
+            GameState::Player1Turn => self.player1.is_human(),
+            GameState::Player2Turn => self.player2.is_human(),
+            GameState::Done => false,
+        }
+    }
+
+    fn get_played_marker(&self, cell: CellCoord) -> Marker {
+        self.board
+            .get_marker_at(cell)
+            .expect("every move in the record is on the board")
+    }
+}
+
+impl<'a, P1: WildPlayer<'a>, P2: WildPlayer<'a>> Game for WildTicTacToe<'a, P1, P2> {
+    fn run(&mut self) -> GameState {
+        let comp_move = match self.state {
+            GameState::Player1Turn if !self.player1.is_human() => {
+                self.player1.get_wild_move(&self.board)
+            }
+            GameState::Player2Turn if !self.player2.is_human() => {
+                self.player2.get_wild_move(&self.board)
+            }
+            _ => return self.state,
+        };
+        self.apply_move(comp_move);
+        self.state
+    }
+
+    // Humans place whichever marker they've switched to.
+    fn make_human_move(&mut self, player_move: CellCoord) -> GameState {
+        if self.is_human_turn() && self.board.validate_move(player_move) == Move::Valid {
+            self.apply_move(WildMove::new(player_move, self.marker));
+        }
+        self.state
+    }
+
+    fn validate_move(&self, player_move: CellCoord) -> Move {
+        self.board.validate_move(player_move)
+    }
+
+    // Takes back moves until a human's move has been taken back, like
+    // TicTacToe does, keeping each move's marker so it can be redone.
+    fn undo(&mut self) -> GameState {
+        let move_count = self.record.move_history.len();
+        if !(0..move_count).any(|move_number| self.is_human_seat(Self::get_move_turn(move_number)))
+        {
+            return self.state;
+        }
+
+        while let Some(player_move) = self.record.undo_move() {
+            self.undone_markers
+                .push(self.get_played_marker(player_move));
+            self.board.remove_marker(player_move);
+            self.record.record_outcome(Winner::None);
+            self.state = Self::get_move_turn(self.record.move_history.len());
+            if self.is_human_seat(self.state) {
+                break;
+            }
+        }
+        self.state
+    }
+
+    fn redo(&mut self) -> GameState {
+        while self.state != GameState::Done {
+            match self.record.redo_move() {
+                Some(player_move) => {
+                    let marker = self
+                        .undone_markers
+                        .pop()
+                        .expect("every undone move keeps its marker");
+                    self.play_move(WildMove::new(player_move, marker));
+                }
+                None => break,
+            }
+            if self.is_human_seat(self.state) {
+                break;
+            }
+        }
+        self.state
+    }
+
+    fn get_game_state(&self) -> GameState {
+        self.state
+    }
+
+    fn is_human_turn(&self) -> bool {
+        self.is_human_seat(self.state)
+    }
+
+    // The marker of the seat whose turn it is, which isn't necessarily the
+    // marker they'll place.
+    fn get_turn_marker(&self) -> Option<Marker> {
+        match self.state {
+            GameState::Player1Turn => Some(self.player1.get_marker()),
+            GameState::Player2Turn => Some(self.player2.get_marker()),
+            GameState::Done => None,
+        }
+    }
+
+    fn get_placing_marker(&self) -> Option<Marker> {
+        match self.state {
+            GameState::Done => None,
+            _ => Some(self.marker),
+        }
+    }
+
+    fn switch_marker(&mut self) {
+        self.marker = Marker::opposite(self.marker);
+    }
+
+    fn get_winner(&self) -> Winner {
+        self.record.winner
+    }
+
+    fn get_move_history(&self) -> &[CellCoord] {
+        &self.record.move_history
+    }
+
+    // The line the last move completed, whichever marker it's made of.
+    fn get_winning_line(&self) -> Vec<CellCoord> {
+        match (self.record.winner, self.record.move_history.last()) {
+            (Winner::None, _) | (_, None) => Vec::new(),
+            (_, Some(&last_move)) => {
+                let marker = self.get_played_marker(last_move);
+                match self.board.check_board_state(last_move, marker) {
+                    BoardState::Win(set_type) => self.board.get_set_coords(set_type),
+                    _ => Vec::new(),
+                }
+            }
+        }
+    }
+
+    fn get_seats(&self) -> [SeatInfo<'_>; 2] {
+        [
+            SeatInfo {
+                name: self.player1.get_name(),
+                kind: self.player1.get_kind(),
+                marker: self.player1.get_marker(),
+            },
+            SeatInfo {
+                name: self.player2.get_name(),
+                kind: self.player2.get_kind(),
+                marker: self.player2.get_marker(),
+            },
+        ]
+    }
+
+    // OptimalAI only ever places its own marker, so it has nothing useful
+    // to say here.
+    fn get_hint(&self) -> Option<(CellCoord, MoveReason)> {
+        None
+    }
+
+    fn get_variant(&self) -> Variant {
+        Variant::Wild
+    }
+
+    fn get_cellstate_char(&self, cell_index: usize) -> char {
+        self.board.get_cellstate_char(cell_index)
+    }
+
+    fn get_board_size(&self) -> BoardSize {
+        self.board.get_size()
+    }
+
+    fn reset(&mut self) {
+        let (player1_seed, player2_seed) = self.record.next_round();
+        self.player1 = self.player1.reseeded(player1_seed);
+        self.player2 = self.player2.reseeded(player2_seed);
+        self.board = Board::new();
+        self.undone_markers.clear();
+        self.state = GameState::Player1Turn;
+        self.marker = Marker::X;
+    }
+
+    fn to_saved_game(&self) -> SavedGame {
+        SavedGame {
+            date: save::get_today(),
+            variant: Variant::Wild,
+            size: self.board.get_size(),
+            win_length: self.board.get_win_length(),
+            player1: String::from(self.record.player1),
+            player1_kind: String::from(self.player1.get_kind()),
+            player2: String::from(self.record.player2),
+            player2_kind: String::from(self.player2.get_kind()),
+            result: match self.state {
+                GameState::Done => Some(self.record.winner),
+                _ => None,
+            },
+            moves: self.record.move_history.clone(),
+            markers: self
+                .record
+                .move_history
+                .iter()
+                .map(|&player_move| self.get_played_marker(player_move))
+                .collect(),
+            seed: Some(self.record.seed),
+        }
+    }
+
+    // Cells alone don't say which marker went where.
+    fn load_moves(&mut self, _moves: &[CellCoord]) -> Result<GameState, String> {
+        Err(String::from("Every move in a Wild game needs a marker"))
+    }
+
+    fn load_saved_game(&mut self, saved_game: &SavedGame) -> Result<GameState, String> {
+        if saved_game.markers.len() != saved_game.moves.len() {
+            return Err(String::from("Every move in a Wild game needs a marker"));
+        }
+        let moves = saved_game.moves.iter().zip(&saved_game.markers);
+        for (move_number, (&player_move, &marker)) in moves.enumerate() {
+            let notation = save::cell_to_notation(player_move);
+            if self.state == GameState::Done {
+                return Err(format!(
+                    "Move {} ({}) comes after the game ended",
+                    move_number + 1,
+                    notation
+                ));
+            }
+            if self.board.validate_move(player_move) != Move::Valid {
+                return Err(format!(
+                    "Move {} ({}) isn't a legal move",
+                    move_number + 1,
+                    notation
+                ));
+            }
+            self.apply_move(WildMove::new(player_move, marker));
+        }
+        Ok(self.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::ai_basic::BasicAI;
+    use crate::player::ai_random::RandomAI;
+    use crate::player::human::Human;
+    use crate::player::Player;
+
+    fn hotseat_game() -> WildTicTacToe<'static, Human<'static>, Human<'static>> {
+        WildTicTacToe::new(
+            Human::new("Player 1", Marker::X),
+            Human::new("Player 2", Marker::O),
+        )
+    }
+
+    #[test]
+    fn completing_any_line_wins() {
+        // Player 1 starts a column of Xs, and player 2 finishes it.
+        let mut game = hotseat_game();
+        game.make_human_move(CellCoord::new(0, 0));
+        game.switch_marker();
+        game.make_human_move(CellCoord::new(1, 1));
+        game.switch_marker();
+        game.make_human_move(CellCoord::new(1, 0));
+        assert_eq!(game.get_turn_marker(), Some(Marker::O));
+        assert_eq!(game.get_placing_marker(), Some(Marker::X));
+        assert_eq!(game.make_human_move(CellCoord::new(2, 0)), GameState::Done);
+        assert_eq!(game.get_winner(), Winner::Player2);
+        assert_eq!(game.get_winning_line().len(), 3);
+        assert_eq!(game.get_placing_marker(), None);
+    }
+
+    #[test]
+    fn undo_and_redo_keep_markers() {
+        let mut game = hotseat_game();
+        game.switch_marker();
+        game.make_human_move(CellCoord::new(0, 0));
+        game.make_human_move(CellCoord::new(2, 2));
+        assert_eq!(game.get_cellstate_char(0), 'O');
+        game.undo();
+        game.undo();
+        assert_eq!(game.get_cellstate_char(0), '_');
+
+        // The marker picked for the next move doesn't change what's redone.
+        game.switch_marker();
+        game.redo();
+        game.redo();
+        assert_eq!(game.get_cellstate_char(0), 'O');
+        assert_eq!(game.get_cellstate_char(8), 'O');
+    }
+
+    #[test]
+    fn computers_finish_games() {
+        for seed in 0..20 {
+            let mut game = WildTicTacToe::new(
+                RandomAI::with_seed("Random", Marker::X, seed),
+                BasicAI::with_seed("Basic", Marker::O, seed + 100),
+            );
+            while game.run() != GameState::Done {}
+            let saved_game = game.to_saved_game();
+            let mut replayed = hotseat_game();
+            assert!(replayed.load_moves(&saved_game.moves).is_err());
+            assert_eq!(replayed.load_saved_game(&saved_game), Ok(GameState::Done));
+            assert_eq!(replayed.get_winner(), game.get_winner());
+        }
+    }
+}